@@ -0,0 +1,37 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use holo::display::rgb_to_buffer;
+use holo::holo::{transform_bgr_frame, transform_frame, HolomorphicLookup};
+use holo::parsing::parse_expression;
+use holo::webcam::bgr_to_rgb_image;
+
+/// Breite und Höhe eines 1080p-Frames.
+const FULL_HD: (u32, u32) = (1920, 1080);
+
+/// Vergleicht die Kosten je 1080p-Frame vom BGR-Frame bis zum Fensterpuffer.
+///
+/// `rgb_image` ist der Weg des Vorschaufensters mit Vergleichsansicht, Overlays oder
+/// Aufzeichnung, `bgr_direct` der Weg der reinen Ergebnisansicht (siehe
+/// `Pipeline::set_direct`).
+fn benchmark_frame_1080p(c: &mut Criterion) {
+    let (width, height) = FULL_HD;
+    // Synthetisches Kamerabild im BGR-Format, wie es OpenCV liefert
+    let bgr: Vec<u8> = (0..width * height * 3).map(|i| (i % 251) as u8).collect();
+    let (_, expr) = parse_expression("z^2").unwrap();
+    let lookup = HolomorphicLookup::new(|z| expr.evaluate(z), width, height);
+
+    let mut group = c.benchmark_group("frame_1080p");
+    group.bench_function("rgb_image", |b| {
+        b.iter(|| {
+            let rgb = bgr_to_rgb_image(black_box(&bgr), width, height).unwrap();
+            let transformed = transform_frame(&lookup, &rgb).unwrap();
+            black_box(rgb_to_buffer(&transformed));
+        })
+    });
+    group.bench_function("bgr_direct", |b| {
+        b.iter(|| black_box(transform_bgr_frame(&lookup, black_box(&bgr)).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_frame_1080p);
+criterion_main!(benches);
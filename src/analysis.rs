@@ -0,0 +1,515 @@
+use std::f64::consts::PI;
+
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+use rayon::prelude::*;
+
+use crate::holo::Viewport;
+use crate::parsing::Expr;
+
+/// Maximale Anzahl an Halbierungen eines Randsegments bei der Windungszahl.
+const MAX_SUBDIVISIONS: u32 = 10;
+
+/// Schrittweite für numerische Ableitungen, falls keine symbolische Ableitung existiert.
+const DIFFERENCE_STEP: f64 = 1e-6;
+
+/// Verschiebung des Gitters relativ zur Zellgröße, damit typische Nullstellen wie
+/// `0` oder `±1` nicht genau auf einer Zellkante liegen.
+const GRID_OFFSET: f64 = 0.1234567;
+
+/// Anzahl der Stützstellen für die Residuenberechnung.
+const RESIDUE_SAMPLES: u32 = 64;
+
+/// Art eines ausgezeichneten Punkts einer Funktion.
+///
+/// # Varianten
+/// - `Zero`: Nullstelle von `f`.
+/// - `Pole`: Polstelle von `f`.
+/// - `CriticalPoint`: Nullstelle von `f'`, an der `f` nicht konform ist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeatureKind {
+    Zero,
+    Pole,
+    CriticalPoint,
+}
+
+impl FeatureKind {
+    /// Gibt den Namen der Art für die Tabellenausgabe zurück.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeatureKind::Zero => "zero",
+            FeatureKind::Pole => "pole",
+            FeatureKind::CriticalPoint => "critical",
+        }
+    }
+}
+
+/// Ein gefundener Nullstellen-, Pol- oder kritischer Punkt.
+///
+/// # Felder
+/// - `kind` (`FeatureKind`): Die Art des Punkts.
+/// - `location` (`Complex<f64>`): Die verfeinerte Position.
+/// - `order` (`u32`): Vielfachheit der Nullstelle bzw. Ordnung des Pols.
+/// - `residue` (`Option<Complex<f64>>`): Das Residuum, nur für Polstellen.
+#[derive(Debug, Clone, Copy)]
+pub struct Feature {
+    pub kind: FeatureKind,
+    pub location: Complex<f64>,
+    pub order: u32,
+    pub residue: Option<Complex<f64>>,
+}
+
+/// Einstellungen der Analyse.
+///
+/// # Felder
+/// - `grid` (`u32`): Anzahl der Gitterzellen pro Achse.
+/// - `samples_per_edge` (`u32`): Anfangszahl der Stützstellen pro Zellkante.
+/// - `newton_iterations` (`u32`): Maximale Anzahl an Newton-Schritten zur Verfeinerung.
+/// - `tolerance` (`f64`): Relative Schrittweite, ab der Newton abbricht.
+/// - `critical_points` (`bool`): Auch kritische Punkte (Nullstellen von `f'`) suchen.
+#[derive(Debug, Clone, Copy)]
+pub struct AnalysisSettings {
+    pub grid: u32,
+    pub samples_per_edge: u32,
+    pub newton_iterations: u32,
+    pub tolerance: f64,
+    pub critical_points: bool,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        AnalysisSettings {
+            grid: 48,
+            samples_per_edge: 16,
+            newton_iterations: 50,
+            tolerance: 1e-12,
+            critical_points: true,
+        }
+    }
+}
+
+/// Eine Funktion zusammen mit ihren ersten beiden Ableitungen.
+///
+/// Symbolische Ableitungen werden bevorzugt; fehlt eine, wird per zentralem
+/// Differenzenquotienten abgeleitet.
+struct Derivatives<'a> {
+    expr: &'a Expr,
+    first: Option<Expr>,
+    second: Option<Expr>,
+}
+
+impl<'a> Derivatives<'a> {
+    fn new(expr: &'a Expr) -> Self {
+        let first = expr.derivative();
+        let second = first.as_ref().and_then(Expr::derivative);
+        Derivatives {
+            expr,
+            first,
+            second,
+        }
+    }
+
+    fn value(&self, z: Complex<f64>) -> Complex<f64> {
+        self.expr.evaluate(z)
+    }
+
+    fn first(&self, z: Complex<f64>) -> Complex<f64> {
+        match &self.first {
+            Some(derivative) => derivative.evaluate(z),
+            None => difference_quotient(|w| self.value(w), z),
+        }
+    }
+
+    fn second(&self, z: Complex<f64>) -> Complex<f64> {
+        match &self.second {
+            Some(derivative) => derivative.evaluate(z),
+            None => difference_quotient(|w| self.first(w), z),
+        }
+    }
+}
+
+/// Zentraler Differenzenquotient von `g` an der Stelle `z`.
+fn difference_quotient(g: impl Fn(Complex<f64>) -> Complex<f64>, z: Complex<f64>) -> Complex<f64> {
+    let h = Complex::new(DIFFERENCE_STEP * z.norm().max(1.0), 0.0);
+    (g(z + h) - g(z - h)) / (2.0 * h)
+}
+
+fn is_finite(z: Complex<f64>) -> bool {
+    z.re.is_finite() && z.im.is_finite()
+}
+
+/// Änderung des Arguments von `g` entlang der Strecke von `start` nach `end`.
+///
+/// Segmente mit großer Argumentänderung werden rekursiv halbiert, damit keine
+/// Umläufe übersehen werden.
+///
+/// # Rückgabewert
+/// Die Argumentänderung oder `None`, wenn `g` auf der Strecke `0` oder nicht endlich ist.
+fn argument_change(
+    g: &impl Fn(Complex<f64>) -> Complex<f64>,
+    start: Complex<f64>,
+    end: Complex<f64>,
+    depth: u32,
+) -> Option<f64> {
+    let (value_start, value_end) = (g(start), g(end));
+    if !is_finite(value_start)
+        || !is_finite(value_end)
+        || value_start.norm() == 0.0
+        || value_end.norm() == 0.0
+    {
+        return None;
+    }
+
+    let change = (value_end / value_start).arg();
+    if change.abs() > PI / 4.0 && depth < MAX_SUBDIVISIONS {
+        let middle = (start + end) / 2.0;
+        Some(
+            argument_change(g, start, middle, depth + 1)?
+                + argument_change(g, middle, end, depth + 1)?,
+        )
+    } else {
+        Some(change)
+    }
+}
+
+/// Berechnet die Windungszahl von `g` entlang des Rands eines Rechtecks.
+///
+/// Nach dem Argumentprinzip entspricht sie der Anzahl der Nullstellen minus der
+/// Anzahl der Pole (jeweils mit Vielfachheit) im Inneren.
+///
+/// # Parameter
+/// - `g` (`&impl Fn(Complex<f64>) -> Complex<f64>`): Die untersuchte Funktion.
+/// - `lower` (`Complex<f64>`): Die linke untere Ecke des Rechtecks.
+/// - `upper` (`Complex<f64>`): Die rechte obere Ecke des Rechtecks.
+/// - `samples_per_edge` (`u32`): Anfangszahl der Stützstellen pro Kante.
+///
+/// # Rückgabewert
+/// Die Windungszahl oder `None`, wenn `g` auf dem Rand verschwindet oder singulär ist.
+pub fn winding_number(
+    g: &impl Fn(Complex<f64>) -> Complex<f64>,
+    lower: Complex<f64>,
+    upper: Complex<f64>,
+    samples_per_edge: u32,
+) -> Option<i32> {
+    let corners = [
+        lower,
+        Complex::new(upper.re, lower.im),
+        upper,
+        Complex::new(lower.re, upper.im),
+        lower,
+    ];
+    let mut total = 0.0;
+    for edge in corners.windows(2) {
+        let step = (edge[1] - edge[0]) / samples_per_edge as f64;
+        for k in 0..samples_per_edge {
+            let start = edge[0] + step * k as f64;
+            total += argument_change(g, start, start + step, 0)?;
+        }
+    }
+    Some((total / (2.0 * PI)).round() as i32)
+}
+
+/// Verfeinert einen Startpunkt mit Newton-Schritten `z ↦ z - step(z)`.
+///
+/// Verlässt die Iteration die (um eine halbe Zelle erweiterte) Zelle oder wird sie
+/// nicht endlich, wird der Startpunkt zurückgegeben.
+fn refine(
+    start: Complex<f64>,
+    lower: Complex<f64>,
+    upper: Complex<f64>,
+    settings: &AnalysisSettings,
+    step: impl Fn(Complex<f64>) -> Complex<f64>,
+) -> Complex<f64> {
+    let mut z = start;
+    for _ in 0..settings.newton_iterations {
+        let delta = step(z);
+        if !is_finite(delta) {
+            break;
+        }
+        z -= delta;
+        if delta.norm() < settings.tolerance * (1.0 + z.norm()) {
+            break;
+        }
+    }
+
+    let margin = (upper.re - lower.re) / 2.0;
+    let inside = z.re >= lower.re - margin
+        && z.re <= upper.re + margin
+        && z.im >= lower.im - margin
+        && z.im <= upper.im + margin;
+    if is_finite(z) && inside {
+        z
+    } else {
+        start
+    }
+}
+
+/// Berechnet das Residuum von `f` im Punkt `pole` über ein Kreisintegral.
+///
+/// # Parameter
+/// - `f` (`impl Fn(Complex<f64>) -> Complex<f64>`): Die Funktion.
+/// - `pole` (`Complex<f64>`): Die Polstelle.
+/// - `radius` (`f64`): Radius des Integrationskreises; er darf keine weitere
+///   Singularität umschließen.
+///
+/// # Rückgabewert
+/// Näherung für `1/(2πi) ∮ f(z) dz`.
+pub fn residue(
+    f: impl Fn(Complex<f64>) -> Complex<f64>,
+    pole: Complex<f64>,
+    radius: f64,
+) -> Complex<f64> {
+    let sum: Complex<f64> = (0..RESIDUE_SAMPLES)
+        .map(|k| {
+            let offset = Complex::from_polar(radius, 2.0 * PI * k as f64 / RESIDUE_SAMPLES as f64);
+            f(pole + offset) * offset
+        })
+        .sum();
+    sum / RESIDUE_SAMPLES as f64
+}
+
+/// Sucht Nullstellen, Polstellen und kritische Punkte einer Funktion im Ausschnitt.
+///
+/// Der Ausschnitt wird in ein Gitter zerlegt. Für jede Zelle bestimmt das
+/// Argumentprinzip, ob sie Nullstellen oder Pole (bzw. Nullstellen von `f'`)
+/// enthält; anschließend werden die Positionen mit dem Newton-Verfahren verfeinert.
+/// Für Polstellen wird zusätzlich das Residuum berechnet.
+///
+/// Verzweigungsschnitte (`log`, `sqrt`) und nicht holomorphe Funktionen (`abs`,
+/// `conj`) können zu Fehlzählungen führen.
+///
+/// # Parameter
+/// - `expr` (`&Expr`): Die untersuchte Funktion.
+/// - `viewport` (`&Viewport`): Der untersuchte Ausschnitt der komplexen Ebene.
+/// - `settings` (`&AnalysisSettings`): Die Einstellungen der Analyse.
+///
+/// # Rückgabewert
+/// Die gefundenen Punkte, sortiert nach Art und Position.
+pub fn analyze(expr: &Expr, viewport: &Viewport, settings: &AnalysisSettings) -> Vec<Feature> {
+    let derivatives = Derivatives::new(expr);
+    let cell = 2.0 * viewport.scale / settings.grid as f64;
+    let shift = viewport.scale + cell - GRID_OFFSET * cell;
+    let origin = viewport.center - Complex::new(shift, shift);
+    let cells = settings.grid + 1;
+
+    let mut features: Vec<Feature> = (0..cells * cells)
+        .into_par_iter()
+        .flat_map_iter(|index| {
+            let lower =
+                origin + Complex::new((index % cells) as f64, (index / cells) as f64) * cell;
+            let upper = lower + Complex::new(cell, cell);
+            let start = (lower + upper) / 2.0;
+            let mut found = Vec::new();
+
+            match winding_number(
+                &|z| derivatives.value(z),
+                lower,
+                upper,
+                settings.samples_per_edge,
+            ) {
+                Some(order) if order > 0 => {
+                    let m = order as f64;
+                    let location = refine(start, lower, upper, settings, |z| {
+                        derivatives.value(z) / derivatives.first(z) * m
+                    });
+                    found.push(Feature {
+                        kind: FeatureKind::Zero,
+                        location,
+                        order: order as u32,
+                        residue: None,
+                    });
+                }
+                Some(order) if order < 0 => {
+                    let m = -order as f64;
+                    // Newton für 1/f: z ↦ z + m · f / f'
+                    let location = refine(start, lower, upper, settings, |z| {
+                        -derivatives.value(z) / derivatives.first(z) * m
+                    });
+                    found.push(Feature {
+                        kind: FeatureKind::Pole,
+                        location,
+                        order: -order as u32,
+                        residue: Some(residue(|z| derivatives.value(z), location, cell / 4.0)),
+                    });
+                }
+                _ => {}
+            }
+
+            if settings.critical_points {
+                let winding = winding_number(
+                    &|z| derivatives.first(z),
+                    lower,
+                    upper,
+                    settings.samples_per_edge,
+                );
+                if let Some(order) = winding.filter(|&order| order > 0) {
+                    let m = order as f64;
+                    let location = refine(start, lower, upper, settings, |z| {
+                        derivatives.first(z) / derivatives.second(z) * m
+                    });
+                    found.push(Feature {
+                        kind: FeatureKind::CriticalPoint,
+                        location,
+                        order: order as u32,
+                        residue: None,
+                    });
+                }
+            }
+
+            found
+        })
+        .filter(|feature| {
+            let offset = feature.location - viewport.center;
+            offset.re.abs() <= viewport.scale && offset.im.abs() <= viewport.scale
+        })
+        .collect();
+
+    features.sort_by(|a, b| {
+        a.kind
+            .cmp(&b.kind)
+            .then(a.location.re.total_cmp(&b.location.re))
+            .then(a.location.im.total_cmp(&b.location.im))
+    });
+
+    // Doppelte Treffer benachbarter Zellen zusammenfassen
+    let mut unique: Vec<Feature> = Vec::with_capacity(features.len());
+    for feature in features {
+        let duplicate = unique.iter().any(|other| {
+            other.kind == feature.kind && (other.location - feature.location).norm() < cell / 2.0
+        });
+        if !duplicate {
+            unique.push(feature);
+        }
+    }
+    unique
+}
+
+/// Formatiert eine komplexe Zahl als `a + bi`.
+fn format_complex(z: Complex<f64>) -> String {
+    let sign = if z.im < 0.0 { '-' } else { '+' };
+    format!("{:.6} {} {:.6}i", z.re, sign, z.im.abs())
+}
+
+/// Formatiert die gefundenen Punkte als Tabelle.
+///
+/// # Parameter
+/// - `features` (`&[Feature]`): Die gefundenen Punkte.
+///
+/// # Rückgabewert
+/// Die Tabelle mit Art, Position, Ordnung und Residuum als String.
+pub fn format_table(features: &[Feature]) -> String {
+    let mut table = format!(
+        "{:<10} {:<32} {:>5}  {}\n",
+        "kind", "location", "order", "residue"
+    );
+    for feature in features {
+        let residue = feature
+            .residue
+            .map(format_complex)
+            .unwrap_or_else(|| "-".to_string());
+        table.push_str(&format!(
+            "{:<10} {:<32} {:>5}  {}\n",
+            feature.kind.name(),
+            format_complex(feature.location),
+            feature.order,
+            residue
+        ));
+    }
+    table
+}
+
+/// Setzt ein Pixel, sofern es innerhalb des Bildes liegt.
+fn put_pixel_checked(img: &mut RgbImage, x: i64, y: i64, color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < img.width() && (y as u32) < img.height() {
+        img.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Markiert die gefundenen Punkte im Ausgabebild.
+///
+/// Nullstellen werden als weißer Kreis, Pole als rotes Kreuz und kritische Punkte
+/// als gelbes Quadrat gezeichnet.
+///
+/// # Parameter
+/// - `img` (`&mut RgbImage`): Das transformierte Bild, dessen Pixel dem Ausschnitt entsprechen.
+/// - `features` (`&[Feature]`): Die zu markierenden Punkte.
+/// - `viewport` (`&Viewport`): Der Ausschnitt, mit dem das Bild erzeugt wurde.
+pub fn mark_features(img: &mut RgbImage, features: &[Feature], viewport: &Viewport) {
+    let (width, height) = img.dimensions();
+    let radius = (width.min(height) as i64 / 80).max(4);
+
+    for feature in features {
+        let (x, y) = viewport.complex_to_pixel(feature.location, width, height);
+        let (x, y) = (x.round() as i64, y.round() as i64);
+        match feature.kind {
+            FeatureKind::Zero => {
+                let color = Rgb([255, 255, 255]);
+                let steps = (radius * 8) as u32;
+                for k in 0..steps {
+                    let angle = 2.0 * PI * k as f64 / steps as f64;
+                    let dx = (radius as f64 * angle.cos()).round() as i64;
+                    let dy = (radius as f64 * angle.sin()).round() as i64;
+                    put_pixel_checked(img, x + dx, y + dy, color);
+                }
+            }
+            FeatureKind::Pole => {
+                let color = Rgb([255, 0, 0]);
+                for d in -radius..=radius {
+                    put_pixel_checked(img, x + d, y + d, color);
+                    put_pixel_checked(img, x + d, y - d, color);
+                }
+            }
+            FeatureKind::CriticalPoint => {
+                let color = Rgb([255, 255, 0]);
+                let half = radius / 2;
+                for d in -half..=half {
+                    put_pixel_checked(img, x + d, y - half, color);
+                    put_pixel_checked(img, x + d, y + half, color);
+                    put_pixel_checked(img, x - half, y + d, color);
+                    put_pixel_checked(img, x + half, y + d, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(features: &[Feature], kind: FeatureKind) -> Vec<Feature> {
+        features
+            .iter()
+            .filter(|f| f.kind == kind)
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_double_zero_and_simple_pole() {
+        let expr = Expr::parse("(z - 0.5)^2 / (z + 0.25)").unwrap();
+        let features = analyze(&expr, &Viewport::default(), &AnalysisSettings::default());
+
+        let zeros = find(&features, FeatureKind::Zero);
+        assert_eq!(zeros.len(), 1, "{:?}", features);
+        assert_eq!(zeros[0].order, 2);
+        assert!((zeros[0].location - Complex::new(0.5, 0.0)).norm() < 1e-4);
+
+        let poles = find(&features, FeatureKind::Pole);
+        assert_eq!(poles.len(), 1, "{:?}", features);
+        assert_eq!(poles[0].order, 1);
+        assert!((poles[0].location - Complex::new(-0.25, 0.0)).norm() < 1e-6);
+        // Residuum von (z - 0.5)^2 / (z + 0.25) in -0.25 ist (-0.75)^2
+        assert!((poles[0].residue.unwrap() - Complex::new(0.5625, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_critical_points_of_cubic() {
+        let expr = Expr::parse("z^3 - 0.75 * z").unwrap();
+        let features = analyze(&expr, &Viewport::default(), &AnalysisSettings::default());
+        let critical = find(&features, FeatureKind::CriticalPoint);
+        assert_eq!(critical.len(), 2, "{:?}", features);
+        assert!((critical[0].location - Complex::new(-0.5, 0.0)).norm() < 1e-6);
+        assert!((critical[1].location - Complex::new(0.5, 0.0)).norm() < 1e-6);
+    }
+}
@@ -0,0 +1,422 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use color_quant::NeuQuant;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, ColorMap};
+use image::{Delay, DynamicImage, ExtendedColorType, Frame, RgbImage, Rgba, RgbaImage};
+use num_complex::Complex;
+use rayon::prelude::*;
+
+use crate::holo::HolomorphicLookup;
+use crate::parsing::{Expr, Parameters};
+
+/// Höchstzahl an Pixeln, mit denen die gemeinsame Farbpalette trainiert wird.
+const QUANTIZE_SAMPLES: usize = 1 << 20;
+
+/// Abtastfaktor für NeuQuant (1 = beste Qualität, 30 = am schnellsten).
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Containerformat einer Animation.
+///
+/// # Varianten
+/// - `Gif`: Animiertes GIF mit höchstens 256 Farben pro Frame.
+/// - `Apng`: Animiertes PNG (verlustfrei).
+/// - `Webp`: Animiertes WebP mit verlustfreien VP8L-Frames.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    Webp,
+}
+
+impl AnimationFormat {
+    /// Bestimmt das Format anhand der Dateiendung (`.gif`, `.png`/`.apng`, `.webp`).
+    ///
+    /// # Parameter
+    /// - `path` (`&Path`): Der Pfad der Ausgabedatei.
+    ///
+    /// # Rückgabewert
+    /// Das Format oder `None`, wenn die Endung unbekannt ist.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            "webp" => Some(AnimationFormat::Webp),
+            _ => None,
+        }
+    }
+}
+
+/// Reihenfolge, in der die Frames abgespielt werden.
+///
+/// # Varianten
+/// - `Loop`: Der Parameter läuft von Anfang bis Ende und springt dann zurück. Der letzte
+///   Frame liegt kurz vor dem Ende, sodass periodische Bewegungen (z. B. `c` auf einem
+///   Kreis) nahtlos schließen.
+/// - `PingPong`: Der Parameter läuft vom Anfang bis zum Ende und wieder zurück.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+    Loop,
+    PingPong,
+}
+
+/// Ein Durchlauf der Parameter `t` und `c` über eine Anzahl von Frames.
+///
+/// `t` läuft linear von `t_start` nach `t_end`, `c` umläuft einmal den Kreis mit
+/// Mittelpunkt `c_center` und Radius `c_radius`.
+///
+/// # Felder
+/// - `frames` (`u32`): Anzahl der berechneten Frames.
+/// - `t_start` (`f64`): Startwert von `t`.
+/// - `t_end` (`f64`): Endwert von `t`.
+/// - `c_center` (`Complex<f64>`): Mittelpunkt des Kreises, auf dem sich `c` bewegt.
+/// - `c_radius` (`f64`): Radius des Kreises; `0` hält `c` fest.
+/// - `playback` (`Playback`): Die Abspielreihenfolge.
+#[derive(Debug, Clone, Copy)]
+pub struct ParameterSweep {
+    pub frames: u32,
+    pub t_start: f64,
+    pub t_end: f64,
+    pub c_center: Complex<f64>,
+    pub c_radius: f64,
+    pub playback: Playback,
+}
+
+impl Default for ParameterSweep {
+    fn default() -> Self {
+        ParameterSweep {
+            frames: 60,
+            t_start: 0.0,
+            t_end: 1.0,
+            c_center: Complex::new(0.0, 0.0),
+            c_radius: 0.0,
+            playback: Playback::Loop,
+        }
+    }
+}
+
+impl ParameterSweep {
+    /// Berechnet die Parameterwerte eines Frames.
+    ///
+    /// # Parameter
+    /// - `frame` (`u32`): Der Index des Frames, `0..frames`.
+    ///
+    /// # Rückgabewert
+    /// Die Werte von `c` und `t` für diesen Frame.
+    pub fn parameters(&self, frame: u32) -> Parameters {
+        let phase = match self.playback {
+            Playback::Loop => frame as f64 / self.frames as f64,
+            Playback::PingPong => frame as f64 / self.frames.saturating_sub(1).max(1) as f64,
+        };
+        Parameters {
+            c: self.c_center + Complex::from_polar(self.c_radius, 2.0 * PI * phase),
+            t: self.t_start + phase * (self.t_end - self.t_start),
+        }
+    }
+
+    /// Rendert alle Frames, wobei die Lookup-Tabelle pro Frame neu berechnet wird.
+    ///
+    /// # Parameter
+    /// - `expr` (`&Expr`): Die Transformation, darf `c` und `t` enthalten.
+    /// - `img` (`&RgbImage`): Das Quellbild.
+    ///
+    /// # Rückgabewert
+    /// Die Frames in Berechnungsreihenfolge oder `None`, falls eine Transformation fehlschlägt.
+    pub fn render(&self, expr: &Expr, img: &RgbImage) -> Option<Vec<RgbaImage>> {
+        let (width, height) = img.dimensions();
+        (0..self.frames)
+            .into_par_iter()
+            .map(|frame| {
+                let params = self.parameters(frame);
+                let lookup =
+                    HolomorphicLookup::new(|z| expr.evaluate_with(z, &params), width, height);
+                let transformed_img = lookup.apply(img)?;
+                Some(DynamicImage::ImageRgb8(transformed_img).to_rgba8())
+            })
+            .collect()
+    }
+
+    /// Ordnet gerenderte Frames in Abspielreihenfolge an.
+    ///
+    /// Bei `PingPong` folgen auf die Frames dieselben Frames rückwärts, ohne den ersten
+    /// und letzten Frame zu wiederholen.
+    ///
+    /// # Parameter
+    /// - `frames` (`Vec<RgbaImage>`): Die Frames aus [`ParameterSweep::render`].
+    ///
+    /// # Rückgabewert
+    /// Die Frames in Abspielreihenfolge.
+    pub fn arrange(&self, mut frames: Vec<RgbaImage>) -> Vec<RgbaImage> {
+        if self.playback == Playback::PingPong && frames.len() > 2 {
+            let backwards: Vec<RgbaImage> =
+                frames[1..frames.len() - 1].iter().rev().cloned().collect();
+            frames.extend(backwards);
+        }
+        frames
+    }
+}
+
+/// Eine mit NeuQuant gelernte Farbpalette, nutzbar für [`imageops::dither`].
+struct Palette(NeuQuant);
+
+impl ColorMap for Palette {
+    type Color = Rgba<u8>;
+
+    fn index_of(&self, color: &Rgba<u8>) -> usize {
+        self.0.index_of(&color.0)
+    }
+
+    fn lookup(&self, index: usize) -> Option<Rgba<u8>> {
+        self.0.lookup(index).map(Rgba)
+    }
+
+    fn has_lookup(&self) -> bool {
+        true
+    }
+
+    fn map_color(&self, color: &mut Rgba<u8>) {
+        self.0.map_pixel(&mut color.0);
+    }
+}
+
+/// Reduziert alle Frames auf eine gemeinsame Farbpalette.
+///
+/// Die Palette wird mit NeuQuant aus Stichproben aller Frames gelernt, sodass die
+/// Farben zwischen den Frames nicht flackern.
+///
+/// # Parameter
+/// - `frames` (`&mut [RgbaImage]`): Die Frames, die verändert werden.
+/// - `colors` (`usize`): Anzahl der Farben der Palette (höchstens 256).
+/// - `dither` (`bool`): Floyd-Steinberg-Dithering anwenden.
+pub fn quantize(frames: &mut [RgbaImage], colors: usize, dither: bool) {
+    let total: usize = frames.iter().map(|frame| frame.pixels().len()).sum();
+    let step = (total / QUANTIZE_SAMPLES).max(1);
+    let samples: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.as_raw().chunks_exact(4).step_by(step))
+        .flatten()
+        .copied()
+        .collect();
+    let palette = Palette(NeuQuant::new(QUANTIZE_SPEED, colors, &samples));
+
+    frames.par_iter_mut().for_each(|frame| {
+        if dither {
+            imageops::dither(frame, &palette);
+        } else {
+            for pixel in frame.pixels_mut() {
+                palette.map_color(pixel);
+            }
+        }
+    });
+}
+
+/// Einstellungen zum Kodieren einer Animation.
+///
+/// # Felder
+/// - `format` (`AnimationFormat`): Das Containerformat.
+/// - `delay_ms` (`u32`): Anzeigedauer eines Frames in Millisekunden.
+/// - `loops` (`u16`): Anzahl der Wiederholungen, `0` für eine Endlosschleife.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationEncoder {
+    pub format: AnimationFormat,
+    pub delay_ms: u32,
+    pub loops: u16,
+}
+
+impl AnimationEncoder {
+    /// Kodiert die Frames als Animation.
+    ///
+    /// # Parameter
+    /// - `frames` (`&[RgbaImage]`): Die Frames in Abspielreihenfolge, alle gleich groß.
+    /// - `writer` (`impl Write`): Das Ziel der kodierten Daten.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn keine Frames vorhanden sind oder das Kodieren
+    /// bzw. Schreiben fehlschlägt.
+    pub fn encode(&self, frames: &[RgbaImage], writer: impl Write) -> Result<(), Box<dyn Error>> {
+        if frames.is_empty() {
+            return Err("an animation needs at least one frame".into());
+        }
+        match self.format {
+            AnimationFormat::Gif => self.encode_gif(frames, writer),
+            AnimationFormat::Apng => self.encode_apng(frames, writer),
+            AnimationFormat::Webp => self.encode_webp(frames, writer),
+        }
+    }
+
+    /// Kodiert die Frames als animiertes GIF.
+    fn encode_gif(&self, frames: &[RgbaImage], writer: impl Write) -> Result<(), Box<dyn Error>> {
+        let mut encoder = GifEncoder::new_with_speed(writer, QUANTIZE_SPEED);
+        encoder.set_repeat(match self.loops {
+            0 => Repeat::Infinite,
+            loops => Repeat::Finite(loops),
+        })?;
+        let delay = Delay::from_numer_denom_ms(self.delay_ms, 1);
+        encoder.encode_frames(
+            frames
+                .iter()
+                .map(|frame| Frame::from_parts(frame.clone(), 0, 0, delay)),
+        )?;
+        Ok(())
+    }
+
+    /// Kodiert die Frames als animiertes PNG.
+    fn encode_apng(&self, frames: &[RgbaImage], writer: impl Write) -> Result<(), Box<dyn Error>> {
+        let (width, height) = frames[0].dimensions();
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, self.loops as u32)?;
+        encoder.set_frame_delay(self.delay_ms.min(u16::MAX as u32) as u16, 1000)?;
+
+        let mut writer = encoder.write_header()?;
+        for frame in frames {
+            writer.write_image_data(frame.as_raw())?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Kodiert die Frames als animiertes WebP.
+    ///
+    /// Der `image`-Encoder schreibt nur Einzelbilder, daher wird jeder Frame verlustfrei
+    /// kodiert und sein `VP8L`-Chunk in einen `ANMF`-Chunk des erweiterten Formats
+    /// (`VP8X` + `ANIM`) übernommen.
+    fn encode_webp(
+        &self,
+        frames: &[RgbaImage],
+        mut writer: impl Write,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = frames[0].dimensions();
+        let mut body = b"WEBP".to_vec();
+
+        // Flags: Animation (0x02) und Alphakanal (0x10), danach die Leinwandgröße
+        let mut header = vec![0x12, 0, 0, 0];
+        header.extend(u24(width - 1));
+        header.extend(u24(height - 1));
+        write_chunk(&mut body, b"VP8X", &header);
+
+        // Hintergrundfarbe (BGRA) und Anzahl der Wiederholungen
+        let mut animation = vec![0, 0, 0, 0];
+        animation.extend(self.loops.to_le_bytes());
+        write_chunk(&mut body, b"ANIM", &animation);
+
+        for frame in frames {
+            let mut still = Vec::new();
+            WebPEncoder::new_lossless(&mut still).encode(
+                frame.as_raw(),
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )?;
+            let bitstream =
+                find_chunk(&still, b"VP8L").ok_or("WebP encoder produced no VP8L data")?;
+
+            let mut anmf = Vec::with_capacity(16 + bitstream.len());
+            anmf.extend(u24(0)); // X-Versatz / 2
+            anmf.extend(u24(0)); // Y-Versatz / 2
+            anmf.extend(u24(width - 1));
+            anmf.extend(u24(height - 1));
+            anmf.extend(u24(self.delay_ms.min(0xFF_FFFF)));
+            anmf.push(0b10); // Nicht mit dem vorherigen Frame mischen, nicht entsorgen
+            anmf.extend_from_slice(bitstream);
+            write_chunk(&mut body, b"ANMF", &anmf);
+        }
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(body.len() as u32).to_le_bytes())?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+}
+
+/// Die unteren 24 Bit eines Werts im Little-Endian-Format.
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+/// Hängt einen RIFF-Chunk (mit Füllbyte auf gerade Länge) an `body` an.
+fn write_chunk(body: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    body.extend_from_slice(fourcc);
+    body.extend((data.len() as u32).to_le_bytes());
+    body.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        body.push(0);
+    }
+}
+
+/// Sucht einen Chunk in einer RIFF-Datei.
+///
+/// # Rückgabewert
+/// Der vollständige Chunk einschließlich Kopf und Füllbyte oder `None`.
+fn find_chunk<'a>(riff: &'a [u8], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut offset = 12; // "RIFF", Größe, "WEBP"
+    while offset + 8 <= riff.len() {
+        let size = u32::from_le_bytes(riff[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let end = (offset + 8 + size + size % 2).min(riff.len());
+        if &riff[offset..offset + 4] == fourcc {
+            return Some(&riff[offset..end]);
+        }
+        offset = end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_closes_circle_seamlessly() {
+        let sweep = ParameterSweep {
+            frames: 8,
+            c_radius: 0.5,
+            ..ParameterSweep::default()
+        };
+        let first = sweep.parameters(0);
+        let after_last = sweep.parameters(sweep.frames);
+        assert!((first.c - after_last.c).norm() < 1e-12);
+        assert!(sweep.parameters(sweep.frames - 1).t < sweep.t_end);
+    }
+
+    #[test]
+    fn test_ping_pong_reaches_end_and_returns() {
+        let sweep = ParameterSweep {
+            frames: 4,
+            playback: Playback::PingPong,
+            ..ParameterSweep::default()
+        };
+        assert_eq!(sweep.parameters(3).t, sweep.t_end);
+
+        let frames: Vec<RgbaImage> = (0..4).map(|i| RgbaImage::new(i + 1, 1)).collect();
+        let widths: Vec<u32> = sweep.arrange(frames).iter().map(|f| f.width()).collect();
+        assert_eq!(widths, vec![1, 2, 3, 4, 3, 2]);
+    }
+
+    #[test]
+    fn test_webp_animation_contains_all_frames() {
+        let frames = vec![RgbaImage::new(4, 3); 3];
+        let encoder = AnimationEncoder {
+            format: AnimationFormat::Webp,
+            delay_ms: 40,
+            loops: 0,
+        };
+        let mut data = Vec::new();
+        encoder.encode(&frames, &mut data).unwrap();
+
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize,
+            data.len() - 8
+        );
+        let anmf_count = data.windows(4).filter(|w| w == b"ANMF").count();
+        assert_eq!(anmf_count, 3);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, ImageFormat};
+use rayon::prelude::*;
+
+use crate::cache::{LookupCache, LookupKey};
+use crate::holo::{HolomorphicLookup, Viewport};
+use crate::metadata::RenderMetadata;
+use crate::output::{encode_image, DEFAULT_JPEG_QUALITY};
+use crate::parsing::Expr;
+
+/// Ergebnis einer Stapelverarbeitung.
+///
+/// # Felder
+/// - `processed` (`usize`): Anzahl der transformierten Dateien.
+/// - `skipped` (`usize`): Anzahl der übersprungenen, bereits vorhandenen Ausgaben.
+/// - `lookups` (`usize`): Anzahl der berechneten Lookup-Tabellen (eine pro Auflösung).
+/// - `failures` (`Vec<(PathBuf, String)>`): Dateien, die nicht verarbeitet werden konnten,
+///   mit Fehlermeldung.
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    pub processed: usize,
+    pub skipped: usize,
+    pub lookups: usize,
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "processed: {}, skipped: {}, failed: {} ({} lookup tables)",
+            self.processed,
+            self.skipped,
+            self.failures.len(),
+            self.lookups
+        )?;
+        for (path, error) in &self.failures {
+            writeln!(f, "  {}: {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sammelt alle Bilddateien eines Verzeichnisses (nicht rekursiv), sortiert nach Namen.
+///
+/// Als Bild gilt jede Datei, deren Endung `image` einem Format zuordnen kann.
+///
+/// # Parameter
+/// - `dir` (`&Path`): Das Eingabeverzeichnis.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Verzeichnis nicht gelesen werden kann.
+pub fn collect_images(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && ImageFormat::from_path(&path).is_ok() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Speichert ein Ergebnis zunächst unter einem temporären Namen und benennt es dann um.
+///
+/// So bleibt nach einem Abbruch keine halbe Datei unter dem endgültigen Namen zurück, die
+/// der nächste Lauf als fertig überspringen würde.
+///
+/// # Parameter
+/// - `img` (`&DynamicImage`): Das Bild.
+/// - `path` (`&Path`): Der Zielpfad, seine Endung bestimmt das Format.
+/// - `metadata` (`&RenderMetadata`): Beschreibung der Berechnung für PNG und JPEG.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Format unbekannt ist oder das Schreiben fehlschlägt.
+fn save_complete(
+    img: &DynamicImage,
+    path: &Path,
+    metadata: &RenderMetadata,
+) -> Result<(), Box<dyn Error>> {
+    let format = ImageFormat::from_path(path)?;
+    let bytes = encode_image(img, format, DEFAULT_JPEG_QUALITY, Some(metadata))?;
+    let partial = path.with_extension(format!("{}.partial", std::process::id()));
+    let result = fs::write(&partial, bytes).and_then(|()| fs::rename(&partial, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    Ok(result?)
+}
+
+/// Transformiert alle Bilder eines Verzeichnisses parallel.
+///
+/// Für jede vorkommende Auflösung wird genau eine Lookup-Tabelle berechnet. Die
+/// Ausgaben behalten Dateinamen, Format und Pixeltyp der Eingaben und tragen (als PNG oder JPEG)
+/// die Funktion und das Quellbild als Metadaten; bereits vorhandene Ausgaben
+/// werden ohne `overwrite` übersprungen, sodass ein abgebrochener Lauf fortgesetzt
+/// werden kann. Mit `cache` werden die Lookup-Tabellen von dort geladen bzw. dort abgelegt.
+///
+/// # Parameter
+/// - `function_str` (`&str`): Die Transformation als String.
+/// - `input_dir` (`&Path`): Das Verzeichnis mit den Eingabebildern.
+/// - `output_dir` (`&Path`): Das Zielverzeichnis, wird bei Bedarf angelegt.
+/// - `overwrite` (`bool`): Vorhandene Ausgaben neu berechnen.
+/// - `cache` (`Option<&LookupCache>`): Optionaler Cache für die Lookup-Tabellen.
+///
+/// # Rückgabewert
+/// Die Zusammenfassung mit allen fehlgeschlagenen Dateien.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Funktion ungültig ist, eines der Verzeichnisse
+/// nicht gelesen bzw. angelegt werden kann oder beide dasselbe Verzeichnis sind. Fehler
+/// einzelner Dateien landen in der Zusammenfassung.
+pub fn run_batch(
+    function_str: &str,
+    input_dir: &Path,
+    output_dir: &Path,
+    overwrite: bool,
+    cache: Option<&LookupCache>,
+) -> Result<BatchSummary, Box<dyn Error>> {
+    let expr = Expr::parse(function_str)?;
+    fs::create_dir_all(output_dir)?;
+    // Sonst würden die Eingaben mit ihren eigenen Ergebnissen überschrieben
+    if fs::canonicalize(input_dir)? == fs::canonicalize(output_dir)? {
+        return Err(format!(
+            "output directory {} is the input directory",
+            output_dir.display()
+        )
+        .into());
+    }
+    let mut summary = BatchSummary::default();
+
+    let mut pending = Vec::new();
+    for input in collect_images(input_dir)? {
+        let output = output_dir.join(input.file_name().unwrap_or_default());
+        if !overwrite && output.exists() {
+            summary.skipped += 1;
+        } else {
+            pending.push((input, output));
+        }
+    }
+
+    // Nur der Dateikopf wird gelesen, um die Auflösungen zu bestimmen
+    let dimensions: Vec<Result<(u32, u32), String>> = pending
+        .par_iter()
+        .map(|(input, _)| image::image_dimensions(input).map_err(|e| e.to_string()))
+        .collect();
+    let resolutions: HashSet<(u32, u32)> = dimensions.iter().flatten().copied().collect();
+    let lookups: HashMap<(u32, u32), HolomorphicLookup> = resolutions
+        .into_par_iter()
+        .map(|(width, height)| {
+            let build = || HolomorphicLookup::new(|z| expr.evaluate(z), width, height);
+            let lookup = match cache {
+                Some(cache) => {
+                    let key = LookupKey::new(&expr, width, height, &Viewport::default());
+                    cache.get_or_build(&key, build)
+                }
+                None => build(),
+            };
+            ((width, height), lookup)
+        })
+        .collect();
+    summary.lookups = lookups.len();
+
+    let results: Vec<Result<(), String>> = pending
+        .par_iter()
+        .zip(&dimensions)
+        .map(|((input, output), dimensions)| {
+            let lookup = &lookups[dimensions.as_ref().map_err(|e| e.clone())?];
+            let img = image::open(input).map_err(|e| e.to_string())?;
+            let transformed_img = lookup
+                .apply_dynamic(&img)
+                .ok_or("image size differs from its header")?;
+            let metadata =
+                RenderMetadata::new(None, Some(function_str)).with_source(&input.to_string_lossy());
+            save_complete(&transformed_img, output, &metadata).map_err(|e| e.to_string())
+        })
+        .collect();
+
+    for ((input, _), result) in pending.into_iter().zip(results) {
+        match result {
+            Ok(()) => summary.processed += 1,
+            Err(error) => summary.failures.push((input, error)),
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_batch_skips_existing_and_reports_failures() {
+        let root = std::env::temp_dir().join(format!("holo_batch_{}", std::process::id()));
+        let (input_dir, output_dir) = (root.join("in"), root.join("out"));
+        fs::create_dir_all(&input_dir).unwrap();
+
+        RgbImage::new(8, 6).save(input_dir.join("a.png")).unwrap();
+        RgbImage::new(8, 6).save(input_dir.join("b.png")).unwrap();
+        RgbImage::new(4, 4).save(input_dir.join("c.png")).unwrap();
+        fs::write(input_dir.join("broken.png"), b"not a png").unwrap();
+        fs::write(input_dir.join("notes.txt"), b"ignored").unwrap();
+
+        let summary = run_batch("z^2", &input_dir, &output_dir, false, None).unwrap();
+        assert_eq!(summary.processed, 3);
+        assert_eq!(summary.lookups, 2);
+        assert_eq!(summary.failures.len(), 1);
+        assert!(output_dir.join("a.png").exists());
+        // Es bleiben nur fertige Ausgaben, keine temporären Dateien
+        assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 3);
+
+        let summary = run_batch("z^2", &input_dir, &output_dir, false, None).unwrap();
+        assert_eq!(summary.processed, 0);
+        assert_eq!(summary.skipped, 3);
+
+        let same = input_dir.join("..").join("in");
+        assert!(run_batch("z^2", &input_dir, &same, true, None).is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,686 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use num_complex::Complex;
+
+use crate::holo::{HolomorphicLookup, Viewport, NO_SOURCE};
+use crate::metadata::{BOUNDARY, INTERPOLATION};
+use crate::parsing::Expr;
+
+/// Kennung am Anfang jeder gespeicherten Lookup-Tabelle.
+const MAGIC: &[u8; 8] = b"HOLOLUT\0";
+
+/// Version des Dateiformats. Sie fließt in den Schlüssel ein, sodass Tabellen eines
+/// älteren Formats oder einer älteren kanonischen Schreibweise der Funktion nach einer
+/// Änderung nicht mehr gefunden werden.
+pub const FORMAT_VERSION: u32 = 3;
+
+/// Version des Programms, die ebenfalls im Kopf steht. Tabellen anderer Versionen werden
+/// nicht verwendet, da sich die Berechnung (z. B. die Randbehandlung) geändert haben kann.
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Standardgröße, ab der die am längsten nicht benutzten Tabellen gelöscht werden (1 GiB).
+pub const DEFAULT_MAX_BYTES: u64 = 1 << 30;
+
+/// Endung der Cache-Dateien.
+const EXTENSION: &str = "lut";
+
+/// Umgebungsvariable, die das Cache-Verzeichnis festlegt.
+pub const CACHE_DIR_VAR: &str = "HOLO_CACHE_DIR";
+
+/// Einstellungen, die eine Lookup-Tabelle eindeutig bestimmen.
+///
+/// # Felder
+/// - `expression` (`String`): Die Funktion in kanonischer Form (siehe `Display` für [`Expr`]).
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+/// - `viewport` (`Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+/// - `interpolation` (`String`): Das Abtastverfahren, z. B. `nearest`.
+/// - `boundary` (`String`): Die Randbehandlung, z. B. `mirror`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupKey {
+    pub expression: String,
+    pub width: u32,
+    pub height: u32,
+    pub viewport: Viewport,
+    pub interpolation: String,
+    pub boundary: String,
+}
+
+impl LookupKey {
+    /// Erstellt den Schlüssel für eine Funktion mit dem aktuellen Abtastverfahren.
+    ///
+    /// # Parameter
+    /// - `expression` (`&Expr`): Die Funktion.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt.
+    pub fn new(expression: &Expr, width: u32, height: u32, viewport: &Viewport) -> Self {
+        LookupKey {
+            expression: expression.to_string(),
+            width,
+            height,
+            viewport: *viewport,
+            interpolation: INTERPOLATION.to_string(),
+            boundary: BOUNDARY.to_string(),
+        }
+    }
+
+    /// Kodiert den Dateikopf: Kennung, Format- und Programmversion und alle Einstellungen.
+    fn header(&self) -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        header.extend(FORMAT_VERSION.to_le_bytes());
+        header.extend((PROGRAM_VERSION.len() as u32).to_le_bytes());
+        header.extend(PROGRAM_VERSION.as_bytes());
+        header.extend(self.width.to_le_bytes());
+        header.extend(self.height.to_le_bytes());
+        header.extend(self.viewport.center.re.to_le_bytes());
+        header.extend(self.viewport.center.im.to_le_bytes());
+        header.extend(self.viewport.scale.to_le_bytes());
+        for text in [&self.expression, &self.interpolation, &self.boundary] {
+            header.extend((text.len() as u32).to_le_bytes());
+            header.extend(text.as_bytes());
+        }
+        header
+    }
+
+    /// Liest einen Dateikopf.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Kennung fehlt, die Format- oder Programmversion
+    /// nicht übereinstimmt oder die Daten abgeschnitten sind.
+    fn read_header(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a holo lookup table"));
+        }
+        let version = read_u32(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported lookup table format version {}",
+                version
+            )));
+        }
+        let program = read_string(reader)?;
+        if program != PROGRAM_VERSION {
+            return Err(invalid_data(&format!(
+                "lookup table was written by holo {}",
+                program
+            )));
+        }
+        let width = read_u32(reader)?;
+        let height = read_u32(reader)?;
+        let re = read_f64(reader)?;
+        let im = read_f64(reader)?;
+        let scale = read_f64(reader)?;
+        Ok(LookupKey {
+            width,
+            height,
+            viewport: Viewport {
+                center: Complex::new(re, im),
+                scale,
+            },
+            expression: read_string(reader)?,
+            interpolation: read_string(reader)?,
+            boundary: read_string(reader)?,
+        })
+    }
+
+    /// Berechnet den 64-Bit-FNV-1a-Hash des Dateikopfs.
+    pub fn hash(&self) -> u64 {
+        self.header()
+            .iter()
+            .fold(0xcbf29ce484222325, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+
+    /// Gibt den Dateinamen im Cache-Verzeichnis zurück.
+    pub fn file_name(&self) -> String {
+        format!("{:016x}.{}", self.hash(), EXTENSION)
+    }
+}
+
+/// Erzeugt einen Fehler für ungültige Dateiinhalte.
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Liest eine vorzeichenlose 32-Bit-Zahl (Little-Endian).
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Liest eine 64-Bit-Gleitkommazahl (Little-Endian).
+fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+/// Höchstlänge der Texte im Dateikopf, damit beschädigte Längenangaben keinen großen
+/// Speicher anfordern.
+const MAX_TEXT_LENGTH: u64 = 1 << 16;
+
+/// Liest einen UTF-8-String mit vorangestellter Länge.
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let length = read_u32(reader)? as u64;
+    if length > MAX_TEXT_LENGTH {
+        return Err(invalid_data("text in lookup table header is too long"));
+    }
+    let mut bytes = Vec::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("invalid UTF-8 in lookup table header"))
+}
+
+/// Schreibt eine Lookup-Tabelle im Cache-Format.
+///
+/// Auf den unkomprimierten Kopf (siehe [`LookupKey`]) folgt die Tabelle als
+/// zlib-komprimierte Folge von 32-Bit-Indizes (Little-Endian).
+///
+/// # Parameter
+/// - `writer` (`impl Write`): Das Ziel.
+/// - `key` (`&LookupKey`): Die Einstellungen der Tabelle.
+/// - `lookup` (`&HolomorphicLookup`): Die Tabelle.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Schreiben fehlschlägt.
+pub fn write_lookup(
+    mut writer: impl Write,
+    key: &LookupKey,
+    lookup: &HolomorphicLookup,
+) -> io::Result<()> {
+    writer.write_all(&key.header())?;
+    let mut encoder = ZlibEncoder::new(writer, Compression::fast());
+    for index in &lookup.lookup {
+        encoder.write_all(&index.to_le_bytes())?;
+    }
+    encoder.finish()?.flush()
+}
+
+/// Liest eine Lookup-Tabelle im Cache-Format.
+///
+/// Der Kopf wird mit `key` verglichen, bevor Speicher für die Tabelle angefordert wird;
+/// dessen Größe ergibt sich daher aus den erwarteten und nicht aus den gespeicherten
+/// Dimensionen.
+///
+/// # Parameter
+/// - `reader` (`impl Read`): Die Quelle.
+/// - `key` (`&LookupKey`): Die erwarteten Einstellungen.
+///
+/// # Rückgabewert
+/// Die Tabelle.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Daten kein gültiges Cache-Format haben, beschädigt
+/// sind, auf Pixel außerhalb des Bildes verweisen oder zu anderen Einstellungen gehören.
+pub fn read_lookup(mut reader: impl Read, key: &LookupKey) -> io::Result<HolomorphicLookup> {
+    if LookupKey::read_header(&mut reader)? != *key {
+        return Err(invalid_data("lookup table belongs to other settings"));
+    }
+    let length = key.width as usize * key.height as usize;
+    let mut bytes = vec![0; length * 4];
+    let mut decoder = ZlibDecoder::new(reader);
+    decoder.read_exact(&mut bytes)?;
+    // Bis zum Ende lesen, damit die Prüfsumme des Datenstroms kontrolliert wird
+    if decoder.read(&mut [0])? != 0 {
+        return Err(invalid_data("lookup table is longer than expected"));
+    }
+    let lookup: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|index| u32::from_le_bytes([index[0], index[1], index[2], index[3]]))
+        .collect();
+    if lookup
+        .iter()
+        .any(|&index| index != NO_SOURCE && index as usize >= length)
+    {
+        return Err(invalid_data("lookup table index outside the image"));
+    }
+    Ok(HolomorphicLookup {
+        lookup,
+        width: key.width,
+        height: key.height,
+    })
+}
+
+/// Eintrag im Cache-Verzeichnis.
+///
+/// # Felder
+/// - `path` (`PathBuf`): Der Pfad der Datei.
+/// - `key` (`LookupKey`): Die Einstellungen aus dem Dateikopf.
+/// - `size` (`u64`): Die Dateigröße in Bytes.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub key: LookupKey,
+    pub size: u64,
+}
+
+/// Verzeichnis mit gespeicherten Lookup-Tabellen.
+///
+/// Jede Tabelle liegt in einer eigenen Datei, deren Name der Hash ihrer Einstellungen ist.
+/// Beim Lesen wird zusätzlich der vollständige Kopf verglichen, sodass Hash-Kollisionen
+/// und beschädigte Dateien lediglich zu einer Neuberechnung führen. Übersteigt das
+/// Verzeichnis `max_bytes`, werden beim Speichern die am längsten nicht benutzten Tabellen
+/// gelöscht.
+///
+/// # Felder
+/// - `dir` (`PathBuf`): Das Cache-Verzeichnis, wird beim ersten Speichern angelegt.
+/// - `max_bytes` (`u64`): Die Höchstgröße aller Tabellen zusammen.
+#[derive(Debug, Clone)]
+pub struct LookupCache {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+}
+
+impl LookupCache {
+    /// Erstellt einen Cache im angegebenen Verzeichnis mit der Höchstgröße
+    /// [`DEFAULT_MAX_BYTES`].
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        LookupCache {
+            dir: dir.into(),
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+
+    /// Öffnet den Cache im Standardverzeichnis, siehe [`LookupCache::default_dir`].
+    pub fn open_default() -> Self {
+        Self::new(Self::default_dir())
+    }
+
+    /// Bestimmt das Standardverzeichnis des Caches.
+    ///
+    /// # Rückgabewert
+    /// `$HOLO_CACHE_DIR`, sonst `holo` in `$XDG_CACHE_HOME`, `%LOCALAPPDATA%` bzw.
+    /// `~/.cache` und zuletzt im temporären Verzeichnis.
+    pub fn default_dir() -> PathBuf {
+        if let Some(dir) = env::var_os(CACHE_DIR_VAR) {
+            return PathBuf::from(dir);
+        }
+        let base = env::var_os("XDG_CACHE_HOME")
+            .or_else(|| env::var_os("LOCALAPPDATA"))
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .unwrap_or_else(env::temp_dir);
+        base.join("holo")
+    }
+
+    /// Gibt den Pfad der Datei für die angegebenen Einstellungen zurück.
+    pub fn path(&self, key: &LookupKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+
+    /// Lädt eine gespeicherte Lookup-Tabelle.
+    ///
+    /// Die Änderungszeit der Datei wird dabei aktualisiert; sie dient beim Aufräumen als
+    /// Zeitpunkt der letzten Benutzung.
+    ///
+    /// # Rückgabewert
+    /// Die Tabelle oder `None`, wenn sie fehlt, beschädigt ist oder zu anderen
+    /// Einstellungen gehört.
+    pub fn get(&self, key: &LookupKey) -> Option<HolomorphicLookup> {
+        let path = self.path(key);
+        let lookup = read_lookup(BufReader::new(File::open(&path).ok()?), key).ok()?;
+        // Fehlen Schreibrechte, bleibt die Tabelle trotzdem benutzbar
+        if let Ok(file) = File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(lookup)
+    }
+
+    /// Speichert eine Lookup-Tabelle.
+    ///
+    /// Die Datei wird zunächst unter einem temporären Namen geschrieben und dann
+    /// umbenannt, sodass parallel laufende Prozesse nie eine halbe Datei lesen.
+    ///
+    /// # Rückgabewert
+    /// Der Pfad der gespeicherten Datei.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Verzeichnis nicht angelegt oder die Datei nicht
+    /// geschrieben werden kann.
+    pub fn store(&self, key: &LookupKey, lookup: &HolomorphicLookup) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(key);
+        let partial = path.with_extension(format!("{}.{}", EXTENSION, std::process::id()));
+        let result = File::create(&partial)
+            .and_then(|file| write_lookup(BufWriter::new(file), key, lookup))
+            .and_then(|()| fs::rename(&partial, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        result?;
+        self.evict(&path)?;
+        Ok(path)
+    }
+
+    /// Löscht die am längsten nicht benutzten Tabellen, bis das Verzeichnis höchstens
+    /// `max_bytes` groß ist.
+    ///
+    /// # Parameter
+    /// - `keep` (`&Path`): Die gerade gespeicherte Tabelle, die nie gelöscht wird.
+    ///
+    /// # Rückgabewert
+    /// Die Anzahl der gelöschten Dateien.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Verzeichnis nicht gelesen werden kann.
+    pub fn evict(&self, keep: &Path) -> io::Result<usize> {
+        let mut files = Vec::new();
+        for path in self.files()? {
+            // Gleichzeitig gelöschte Dateien überspringen
+            if let Ok(metadata) = fs::metadata(&path) {
+                let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((used, metadata.len(), path));
+            }
+        }
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        files.sort();
+        let mut removed = 0;
+        for (_, size, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path != keep && fs::remove_file(&path).is_ok() {
+                total -= size;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Lädt eine Lookup-Tabelle oder berechnet und speichert sie, falls sie fehlt.
+    ///
+    /// Kann die Tabelle nicht gespeichert werden, wird eine Warnung ausgegeben und die
+    /// berechnete Tabelle trotzdem verwendet.
+    ///
+    /// # Parameter
+    /// - `key` (`&LookupKey`): Die Einstellungen der Tabelle.
+    /// - `build` (`impl FnOnce() -> HolomorphicLookup`): Berechnet die Tabelle.
+    pub fn get_or_build(
+        &self,
+        key: &LookupKey,
+        build: impl FnOnce() -> HolomorphicLookup,
+    ) -> HolomorphicLookup {
+        if let Some(lookup) = self.get(key) {
+            return lookup;
+        }
+        let lookup = build();
+        if let Err(error) = self.store(key, &lookup) {
+            eprintln!("warning: could not cache lookup table: {}", error);
+        }
+        lookup
+    }
+
+    /// Listet die gespeicherten Tabellen, sortiert nach Funktion und Auflösung.
+    ///
+    /// Es werden nur die Dateiköpfe gelesen; Dateien in einem anderen Format werden
+    /// übersprungen.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Verzeichnis nicht gelesen werden kann. Ein
+    /// fehlendes Verzeichnis ergibt eine leere Liste.
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for path in self.files()? {
+            let Ok(file) = File::open(&path) else {
+                continue;
+            };
+            let size = file.metadata()?.len();
+            if let Ok(key) = LookupKey::read_header(&mut BufReader::new(file)) {
+                entries.push(CacheEntry { path, key, size });
+            }
+        }
+        entries.sort_by(|a, b| {
+            (&a.key.expression, a.key.width, a.key.height).cmp(&(
+                &b.key.expression,
+                b.key.width,
+                b.key.height,
+            ))
+        });
+        Ok(entries)
+    }
+
+    /// Löscht alle gespeicherten Tabellen.
+    ///
+    /// # Rückgabewert
+    /// Die Anzahl der gelöschten Dateien.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn eine Datei nicht gelöscht werden kann.
+    pub fn clear(&self) -> io::Result<usize> {
+        let files = self.files()?;
+        for path in &files {
+            fs::remove_file(path)?;
+        }
+        Ok(files.len())
+    }
+
+    /// Sammelt alle Cache-Dateien des Verzeichnisses.
+    fn files(&self) -> io::Result<Vec<PathBuf>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        let mut files = Vec::new();
+        for entry in dir {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Formatiert die Einträge des Caches als Tabelle.
+///
+/// # Parameter
+/// - `entries` (`&[CacheEntry]`): Die Einträge.
+///
+/// # Rückgabewert
+/// Eine Zeile pro Tabelle mit Hash, Auflösung, Ausschnitt, Größe und Funktion sowie
+/// eine Summenzeile.
+pub fn format_entries(entries: &[CacheEntry]) -> String {
+    let mut table = format!(
+        "{:<16}  {:>11}  {:>17}  {:>9}  {}\n",
+        "key", "size", "viewport", "bytes", "function"
+    );
+    for entry in entries {
+        let key = &entry.key;
+        let viewport = format!(
+            "{},{} x{}",
+            key.viewport.center.re, key.viewport.center.im, key.viewport.scale
+        );
+        table.push_str(&format!(
+            "{:016x}  {:>11}  {:>17}  {:>9}  {}\n",
+            key.hash(),
+            format!("{}x{}", key.width, key.height),
+            viewport,
+            entry.size,
+            key.expression
+        ));
+    }
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    table.push_str(&format!(
+        "{} lookup tables, {} bytes\n",
+        entries.len(),
+        total
+    ));
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_roundtrip_and_key_mismatch() {
+        let expression = Expr::parse("z^2 + 1").unwrap();
+        let key = LookupKey::new(&expression, 9, 7, &Viewport::default());
+        let lookup = HolomorphicLookup::new(|z| expression.evaluate(z), 9, 7);
+
+        let mut bytes = Vec::new();
+        write_lookup(&mut bytes, &key, &lookup).unwrap();
+        let restored = read_lookup(bytes.as_slice(), &key).unwrap();
+        assert_eq!(restored.lookup, lookup.lookup);
+        assert_eq!((restored.width, restored.height), (9, 7));
+
+        // Gleichwertige Schreibweisen ergeben denselben Schlüssel, andere Einstellungen nicht
+        let same = LookupKey::new(
+            &Expr::parse("(z ^ 2) + 1").unwrap(),
+            9,
+            7,
+            &Viewport::default(),
+        );
+        assert_eq!(same.hash(), key.hash());
+        let zoomed = Viewport {
+            scale: 2.0,
+            ..Viewport::default()
+        };
+        assert_ne!(
+            LookupKey::new(&expression, 9, 7, &zoomed).hash(),
+            key.hash()
+        );
+        assert_ne!(
+            LookupKey::new(&expression, 7, 9, &Viewport::default()).hash(),
+            key.hash()
+        );
+
+        // Abweichende Schlüssel werden vor dem Dekomprimieren abgewiesen
+        let huge = LookupKey {
+            width: u32::MAX,
+            height: u32::MAX,
+            ..key.clone()
+        };
+        let mut huge_bytes = huge.header();
+        huge_bytes.extend(b"not zlib");
+        assert_eq!(
+            read_lookup(huge_bytes.as_slice(), &key)
+                .err()
+                .map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+
+        let mut other_version = key.header();
+        other_version[16..16 + PROGRAM_VERSION.len()].fill(b'9');
+        assert!(read_lookup(other_version.as_slice(), &key).is_err());
+
+        bytes[8] = FORMAT_VERSION as u8 + 1;
+        assert!(read_lookup(bytes.as_slice(), &key).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_tables_are_rejected() {
+        let expression = Expr::parse("z").unwrap();
+        let key = LookupKey::new(&expression, 4, 3, &Viewport::default());
+        let read = |lookup: Vec<u32>| {
+            let table = HolomorphicLookup {
+                lookup,
+                width: 4,
+                height: 3,
+            };
+            let mut bytes = Vec::new();
+            write_lookup(&mut bytes, &key, &table).unwrap();
+            bytes
+        };
+
+        let mut indices: Vec<u32> = (0..12).collect();
+        indices[5] = NO_SOURCE;
+        assert!(read_lookup(read(indices.clone()).as_slice(), &key).is_ok());
+
+        indices[7] = 12;
+        assert_eq!(
+            read_lookup(read(indices).as_slice(), &key)
+                .err()
+                .map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+
+        // Eine falsche Prüfsumme am Ende des Datenstroms fällt ebenfalls auf
+        let mut bytes = read((0..12).collect());
+        *bytes.last_mut().unwrap() ^= 0xff;
+        assert!(read_lookup(bytes.as_slice(), &key).is_err());
+    }
+
+    #[test]
+    fn test_cache_builds_once_lists_and_clears() {
+        let dir = std::env::temp_dir().join(format!("holo_cache_{}", std::process::id()));
+        let cache = LookupCache::new(&dir);
+        let expression = Expr::parse("1 / z").unwrap();
+        let key = LookupKey::new(&expression, 6, 4, &Viewport::default());
+
+        let builds = Cell::new(0);
+        let build = || {
+            builds.set(builds.get() + 1);
+            HolomorphicLookup::new(|z| expression.evaluate(z), 6, 4)
+        };
+        let first = cache.get_or_build(&key, build);
+        let second = cache.get_or_build(&key, build);
+        assert_eq!(builds.get(), 1);
+        assert_eq!(first.lookup, second.lookup);
+
+        // Eine beschädigte Datei führt zur Neuberechnung
+        fs::write(cache.path(&key), b"HOLOLUT\0garbage").unwrap();
+        cache.get_or_build(&key, build);
+        assert_eq!(builds.get(), 2);
+
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, key);
+        assert!(format_entries(&entries).contains("6x4"));
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("holo_evict_{}", std::process::id()));
+        let mut cache = LookupCache::new(&dir);
+        let keys: Vec<LookupKey> = ["z", "z^2", "z^3"]
+            .iter()
+            .map(|f| LookupKey::new(&Expr::parse(f).unwrap(), 8, 8, &Viewport::default()))
+            .collect();
+        let lookup = HolomorphicLookup::new(|z| z, 8, 8);
+        for key in &keys[..2] {
+            cache.store(key, &lookup).unwrap();
+        }
+        let size = fs::metadata(cache.path(&keys[0])).unwrap().len();
+        // Die erste Tabelle als älteste markieren, die zweite als zuletzt benutzt
+        File::options()
+            .append(true)
+            .open(cache.path(&keys[0]))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        cache.max_bytes = 2 * size + size / 2;
+        cache.store(&keys[2], &lookup).unwrap();
+        assert!(cache.get(&keys[0]).is_none());
+        assert!(cache.get(&keys[1]).is_some());
+        assert!(cache.get(&keys[2]).is_some());
+
+        // Die gerade gespeicherte Tabelle bleibt auch bei zu kleiner Höchstgröße erhalten
+        cache.max_bytes = 0;
+        cache.store(&keys[0], &lookup).unwrap();
+        assert_eq!(cache.entries().unwrap().len(), 1);
+        assert!(cache.get(&keys[0]).is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
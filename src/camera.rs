@@ -0,0 +1,275 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use opencv::prelude::*;
+use opencv::videoio::{
+    VideoCapture, VideoWriter, CAP_ANY, CAP_PROP_FOURCC, CAP_PROP_FPS, CAP_PROP_FRAME_HEIGHT,
+    CAP_PROP_FRAME_WIDTH,
+};
+
+use crate::webcam::capture_frame;
+
+/// Anzahl der Geräteindizes, die `holo cameras` durchsucht.
+pub const MAX_CAMERAS: i32 = 10;
+
+/// Eine Kamera, angegeben über ihren Index oder einen Gerätepfad.
+///
+/// # Varianten
+/// - `Index(i32)`: Der Index des Geräts, `0` ist die Standardkamera.
+/// - `Path(String)`: Ein Gerätepfad wie `/dev/video2`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraSource {
+    Index(i32),
+    Path(String),
+}
+
+impl Default for CameraSource {
+    fn default() -> Self {
+        CameraSource::Index(0)
+    }
+}
+
+impl fmt::Display for CameraSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CameraSource::Index(index) => write!(f, "{}", index),
+            CameraSource::Path(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// Benutzerdefinierte Parser-Funktion für `--camera`.
+///
+/// # Parameter
+/// - `s` (`&str`): Ein Geräteindex oder ein Gerätepfad.
+///
+/// # Rückgabewert
+/// Gibt die Kamera zurück oder eine Fehlermeldung bei einer leeren Angabe bzw. einem
+/// negativen Index.
+pub fn parse_camera(s: &str) -> Result<CameraSource, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(String::from("Camera must be an index or a device path"));
+    }
+    match s.parse::<i32>() {
+        Ok(index) if index < 0 => Err(String::from("Camera index must not be negative")),
+        Ok(index) => Ok(CameraSource::Index(index)),
+        Err(_) => Ok(CameraSource::Path(s.to_string())),
+    }
+}
+
+/// Pixelformat, das von der Kamera angefordert wird.
+///
+/// # Varianten
+/// - `Mjpg`: Komprimiertes Motion-JPEG, erlaubt meist höhere Auflösungen und Bildraten.
+/// - `Yuyv`: Unkomprimiertes YUV 4:2:2, ohne Kompressionsartefakte.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Mjpg,
+    Yuyv,
+}
+
+impl PixelFormat {
+    /// Gibt den FourCC-Code des Formats zurück.
+    pub fn fourcc(self) -> [char; 4] {
+        match self {
+            PixelFormat::Mjpg => ['M', 'J', 'P', 'G'],
+            PixelFormat::Yuyv => ['Y', 'U', 'Y', 'V'],
+        }
+    }
+}
+
+/// Gewünschte Aufnahmeeinstellungen einer Kamera.
+///
+/// # Felder
+/// - `width` (`u32`): Die gewünschte Breite.
+/// - `height` (`u32`): Die gewünschte Höhe.
+/// - `fps` (`Option<f64>`): Die gewünschte Bildrate, ohne Angabe die der Kamera.
+/// - `pixel_format` (`Option<PixelFormat>`): Das gewünschte Pixelformat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureSettings {
+    pub width: u32,
+    pub height: u32,
+    pub fps: Option<f64>,
+    pub pixel_format: Option<PixelFormat>,
+}
+
+/// Von einer Kamera tatsächlich gelieferte Einstellungen.
+///
+/// # Felder
+/// - `source` (`CameraSource`): Die Kamera.
+/// - `backend` (`String`): Das verwendete OpenCV-Backend, z. B. `V4L2`.
+/// - `width` (`u32`): Die Breite der Frames.
+/// - `height` (`u32`): Die Höhe der Frames.
+/// - `fps` (`f64`): Die gemeldete Bildrate, `0` falls unbekannt.
+/// - `fourcc` (`String`): Das gemeldete Pixelformat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraInfo {
+    pub source: CameraSource,
+    pub backend: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub fourcc: String,
+}
+
+/// Wandelt einen FourCC-Code, wie ihn OpenCV meldet, in seine vier Zeichen um.
+///
+/// # Parameter
+/// - `code` (`i32`): Der Code, das erste Zeichen liegt im niedrigsten Byte.
+///
+/// # Rückgabewert
+/// Die Zeichen oder `?`, falls der Code leer ist oder nicht druckbare Zeichen enthält.
+pub fn fourcc_to_string(code: i32) -> String {
+    let bytes = (code as u32).to_le_bytes();
+    if code == 0 || !bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        return String::from("?");
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Öffnet eine Kamera ohne weitere Einstellungen.
+fn open(source: &CameraSource) -> opencv::Result<VideoCapture> {
+    match source {
+        CameraSource::Index(index) => VideoCapture::new(*index, CAP_ANY),
+        CameraSource::Path(path) => VideoCapture::from_file(path, CAP_ANY),
+    }
+}
+
+/// Liest die Einstellungen aus, die eine geöffnete Kamera meldet.
+fn describe(cap: &VideoCapture, source: &CameraSource) -> opencv::Result<CameraInfo> {
+    Ok(CameraInfo {
+        source: source.clone(),
+        backend: cap.get_backend_name().unwrap_or_default(),
+        width: cap.get(CAP_PROP_FRAME_WIDTH)? as u32,
+        height: cap.get(CAP_PROP_FRAME_HEIGHT)? as u32,
+        fps: cap.get(CAP_PROP_FPS)?,
+        fourcc: fourcc_to_string(cap.get(CAP_PROP_FOURCC)? as i32),
+    })
+}
+
+/// Öffnet eine Kamera und handelt Pixelformat, Größe und Bildrate aus.
+///
+/// Viele Kameras liefern nicht die angeforderte Größe, sondern die nächstliegende
+/// unterstützte. Deshalb wird ein erstes Frame gelesen und dessen tatsächliche Größe
+/// zurückgegeben; die Lookup-Tabelle muss für diese Größe berechnet werden.
+///
+/// # Parameter
+/// - `source` (`&CameraSource`): Die Kamera.
+/// - `settings` (`&CaptureSettings`): Die gewünschten Einstellungen.
+///
+/// # Rückgabewert
+/// Die geöffnete Kamera und die tatsächlich gelieferten Einstellungen.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Kamera nicht geöffnet werden kann oder kein Frame
+/// liefert.
+pub fn open_camera(
+    source: &CameraSource,
+    settings: &CaptureSettings,
+) -> Result<(VideoCapture, CameraInfo), Box<dyn std::error::Error>> {
+    let mut cap = open(source)?;
+    if !cap.is_opened()? {
+        return Err(format!("failed to open camera {}", source).into());
+    }
+    // Das Pixelformat muss vor der Größe gesetzt werden, sonst verwirft V4L2 die Größe
+    if let Some(format) = settings.pixel_format {
+        let [c1, c2, c3, c4] = format.fourcc();
+        cap.set(CAP_PROP_FOURCC, VideoWriter::fourcc(c1, c2, c3, c4)? as f64)?;
+    }
+    cap.set(CAP_PROP_FRAME_WIDTH, settings.width as f64)?;
+    cap.set(CAP_PROP_FRAME_HEIGHT, settings.height as f64)?;
+    if let Some(fps) = settings.fps {
+        cap.set(CAP_PROP_FPS, fps)?;
+    }
+
+    let mut info = describe(&cap, source)?;
+    // Die gemeldete Größe stimmt nicht bei jedem Backend, maßgeblich ist das erste Frame
+    let frame = capture_frame(&mut cap)
+        .ok_or_else(|| format!("camera {} did not deliver a frame", source))?;
+    info.width = frame.cols() as u32;
+    info.height = frame.rows() as u32;
+    Ok((cap, info))
+}
+
+/// Sucht nach angeschlossenen Kameras.
+///
+/// # Parameter
+/// - `max_index` (`i32`): Die Anzahl der Geräteindizes, die ausprobiert werden.
+///
+/// # Rückgabewert
+/// Die Kameras, die sich öffnen lassen, mit ihren Standardeinstellungen.
+pub fn list_cameras(max_index: i32) -> Vec<CameraInfo> {
+    (0..max_index)
+        .map(CameraSource::Index)
+        .filter_map(|source| {
+            let cap = open(&source).ok()?;
+            if !cap.is_opened().ok()? {
+                return None;
+            }
+            describe(&cap, &source).ok()
+        })
+        .collect()
+}
+
+/// Formatiert gefundene Kameras als Tabelle für `holo cameras`.
+///
+/// # Parameter
+/// - `cameras` (`&[CameraInfo]`): Die Kameras.
+///
+/// # Rückgabewert
+/// Eine Zeile je Kamera oder ein Hinweis, falls keine gefunden wurde.
+pub fn format_cameras(cameras: &[CameraInfo]) -> String {
+    if cameras.is_empty() {
+        return String::from("no cameras found\n");
+    }
+    cameras
+        .iter()
+        .map(|camera| {
+            let line = format!(
+                "{:>3}  {:>4}x{:<4} {:>5.1} fps  {:<4}  {}",
+                camera.source.to_string(),
+                camera.width,
+                camera.height,
+                camera.fps,
+                camera.fourcc,
+                camera.backend
+            );
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_camera_index_or_path() {
+        assert_eq!(parse_camera("2"), Ok(CameraSource::Index(2)));
+        assert_eq!(
+            parse_camera("/dev/video2"),
+            Ok(CameraSource::Path("/dev/video2".to_string()))
+        );
+        assert!(parse_camera("-1").is_err());
+        assert!(parse_camera(" ").is_err());
+    }
+
+    #[test]
+    fn test_fourcc_to_string() {
+        let code = i32::from_le_bytes(*b"MJPG");
+        assert_eq!(fourcc_to_string(code), "MJPG");
+        assert_eq!(fourcc_to_string(0), "?");
+        assert_eq!(fourcc_to_string(-1), "?");
+
+        let listing = format_cameras(&[CameraInfo {
+            source: CameraSource::Index(0),
+            backend: "V4L2".to_string(),
+            width: 1280,
+            height: 720,
+            fps: 30.0,
+            fourcc: "YUYV".to_string(),
+        }]);
+        assert_eq!(listing, "  0  1280x720   30.0 fps  YUYV  V4L2\n");
+    }
+}
@@ -1,4 +1,17 @@
-use clap::{command, Parser, ValueEnum};
+use std::time::Duration;
+
+use clap::{command, Args, Parser, Subcommand, ValueEnum};
+use image::ImageFormat;
+
+use crate::animation::{AnimationFormat, Playback};
+use crate::cache::LookupCache;
+use crate::camera::{parse_camera, CameraSource, CaptureSettings, PixelFormat};
+use crate::compare::ViewMode;
+use crate::fractal::FractalKind;
+use crate::headless::FrameLimit;
+use crate::morph::Interpolation;
+use crate::output::{is_stdio, ImageOutput, DEFAULT_JPEG_QUALITY};
+use crate::video::{is_video_path, VideoOutput, DEFAULT_RECORDING_TEMPLATE};
 
 /// Benutzerdefinierte Parser-Funktion, um Dimensionen im Format `width,height` zu parsen.
 ///
@@ -23,6 +36,97 @@ fn parse_dimensions(s: &str) -> Result<(u32, u32), String> {
     Ok((width, height))
 }
 
+/// Benutzerdefinierte Parser-Funktion für komplexe Zahlen im Format `re,im`.
+///
+/// # Parameter
+/// - `s` (`&str`): Die Eingabe im Format `re,im`.
+///
+/// # Rückgabewert
+/// Gibt Real- und Imaginärteil als Tupel `(f64, f64)` zurück oder eine Fehlermeldung.
+fn parse_complex(s: &str) -> Result<(f64, f64), String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 2 {
+        return Err(String::from("Complex numbers must be in format re,im"));
+    }
+    let re = parts[0]
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| "Invalid real part")?;
+    let im = parts[1]
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| "Invalid imaginary part")?;
+    Ok((re, im))
+}
+
+/// Benutzerdefinierte Parser-Funktion für Bildformate, angegeben als Dateiendung.
+///
+/// # Parameter
+/// - `s` (`&str`): Die Endung, z. B. `png`, `jpg` oder `tiff`.
+///
+/// # Rückgabewert
+/// Gibt das zugehörige `ImageFormat` zurück oder eine Fehlermeldung.
+fn parse_image_format(s: &str) -> Result<ImageFormat, String> {
+    ImageFormat::from_extension(s).ok_or_else(|| format!("Unknown image format: {}", s))
+}
+
+/// Benutzerdefinierte Parser-Funktion für Wertebereiche im Format `start,end`.
+///
+/// # Parameter
+/// - `s` (`&str`): Die Eingabe im Format `start,end`.
+///
+/// # Rückgabewert
+/// Gibt Start- und Endwert als Tupel `(f64, f64)` zurück oder eine Fehlermeldung.
+fn parse_range(s: &str) -> Result<(f64, f64), String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 2 {
+        return Err(String::from("Ranges must be in format start,end"));
+    }
+    let start = parts[0]
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| "Invalid start value")?;
+    let end = parts[1]
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| "Invalid end value")?;
+    Ok((start, end))
+}
+
+/// Benutzerdefinierte Parser-Funktion für `--duration` in Sekunden.
+///
+/// # Parameter
+/// - `s` (`&str`): Die Dauer in Sekunden, z. B. `2.5`.
+///
+/// # Rückgabewert
+/// Gibt die Dauer zurück oder eine Fehlermeldung, wenn sie keine endliche, positive Zahl
+/// ist oder nicht als `Duration` darstellbar ist.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let seconds = s.trim().parse::<f64>().map_err(|_| "Invalid duration")?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(String::from(
+            "Duration must be a positive number of seconds",
+        ));
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|error| error.to_string())
+}
+
+/// Benutzerdefinierte Parser-Funktion für `--fps` der Videoausgabe.
+///
+/// # Parameter
+/// - `s` (`&str`): Die Bildrate in Bildern pro Sekunde, z. B. `29.97`.
+///
+/// # Rückgabewert
+/// Gibt die Bildrate zurück oder eine Fehlermeldung, wenn sie keine endliche, positive
+/// Zahl ist.
+fn parse_frame_rate(s: &str) -> Result<f64, String> {
+    let fps = s.trim().parse::<f64>().map_err(|_| "Invalid frame rate")?;
+    if !fps.is_finite() || fps <= 0.0 {
+        return Err(String::from("Frame rate must be a positive number"));
+    }
+    Ok(fps)
+}
+
 /// Aufzählung gängiger Bildschirmauflösungen.
 ///
 /// Diese Enum definiert verschiedene vordefinierte Bildschirmauflösungen und
@@ -77,27 +181,165 @@ impl Resolution {
 /// Definiert die Kommandozeilenargumente für das Programm.
 ///
 /// Diese Struktur verwendet die `clap`-Bibliothek, um Argumente aus der Kommandozeile zu parsen.
-/// Die Optionen ermöglichen die Angabe von Transformationen, Bilddateien und Auflösungen.
+/// Ohne Unterbefehl wird die angegebene Funktion auf ein Bild oder die Webcam angewendet,
+/// Unterbefehle stellen spezielle Modi wie den Droste-Effekt bereit.
 ///
 /// # Felder
-/// - `function` (`String`): Die mathematische Funktion, die auf die Bilddaten angewendet wird.
-/// - `image` (`Option<String>`): Der Pfad zur Bilddatei, die verarbeitet werden soll. Wenn keine Bilddatei angegeben wird, wird die Webcam verwendet.
-/// - `resolution` (`Option<Resolution>`): Eine vordefinierte Auflösung, die benutzerdefinierte Dimensionen überschreibt.
-/// - `dimensions` (`Option<(u32, u32)>`): Benutzerdefinierte Dimensionen im Format `width,height`.
+/// - `command` (`Option<Command>`): Ein optionaler Unterbefehl.
+/// - `function` (`Option<String>`): Die mathematische Funktion, die auf die Bilddaten angewendet wird.
+///   Pflichtangabe, sofern kein Unterbefehl verwendet wird.
+/// - `source` (`SourceArgs`): Die Angaben zur Bildquelle.
+/// - `tile_size` (`Option<u32>`): Kantenlänge der Kacheln, um sehr große Bilder mit
+///   begrenztem Speicher kachelweise zu berechnen.
+/// - `cache` (`LookupCacheArgs`): Ob der Lookup-Cache verwendet wird.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The function string to apply
     #[arg(
         value_name = "FUNCTION",
-        help = "Function to apply to the file contents"
+        help = "Function to apply to the file contents",
+        required = true
     )]
-    pub function: String,
+    pub function: Option<String>,
+
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    /// Render the image tile by tile with bounded memory (PNG or TIFF in and out), tile
+    /// edge in pixels, a multiple of 16. PNG and uncompressed TIFF files with large strips
+    /// are unpacked into an uncompressed temporary file first, compressed TIFF files need
+    /// strips of at most 4 megapixels
+    #[arg(long, value_name = "PIXELS", requires = "image")]
+    pub tile_size: Option<u32>,
+
+    #[command(flatten)]
+    pub cache: LookupCacheArgs,
+}
+
+impl Cli {
+    /// Die Ziel-Bitrate der Videoausgabe aus `--bitrate`, gleich in welchem Befehl.
+    pub fn bitrate(&self) -> Option<u32> {
+        match &self.command {
+            None => self.source.encoding.bitrate,
+            Some(Command::Droste(args)) => args.source.encoding.bitrate,
+            Some(Command::Hyperbolic(args)) => args.source.encoding.bitrate,
+            Some(Command::Fractal(args)) => args.source.encoding.bitrate,
+            Some(Command::Morph(args)) => args.encoding.bitrate,
+            Some(_) => None,
+        }
+    }
+}
+
+/// Gemeinsamer Schalter für den Lookup-Cache.
+///
+/// # Felder
+/// - `no_cache` (`bool`): Lookup-Tabellen weder aus dem Cache laden noch dort speichern.
+#[derive(Args, Debug)]
+pub struct LookupCacheArgs {
+    /// Neither load lookup tables from nor store them in the on-disk cache
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+impl LookupCacheArgs {
+    /// Öffnet den Standard-Cache, sofern er nicht mit `--no-cache` abgeschaltet ist.
+    pub fn open(&self) -> Option<LookupCache> {
+        (!self.no_cache).then(LookupCache::open_default)
+    }
+}
+
+/// Gemeinsame Angabe des Bildformats für alle Befehle, die Bilder speichern.
+///
+/// # Felder
+/// - `output_format` (`Option<ImageFormat>`): Bildformat unabhängig von der Endung, z. B. für
+///   die Standardausgabe.
+#[derive(Args, Debug)]
+pub struct ImageFormatArgs {
+    /// Image format given as extension (png, jpg, webp, tiff, exr, ...), default for
+    /// standard output: png
+    #[arg(long, value_parser = parse_image_format)]
+    pub output_format: Option<ImageFormat>,
+}
 
+/// Gemeinsame Einstellungen des Video-Encoders für alle Befehle, die Videos schreiben.
+///
+/// # Felder
+/// - `codec` (`String`): FourCC-Code des Codecs für die Videoausgabe.
+/// - `fps` (`Option<f64>`): Bildrate der Videoausgabe, standardmäßig die der Quelle.
+/// - `bitrate` (`Option<u32>`): Ziel-Bitrate der Videoausgabe in kbit/s.
+#[derive(Args, Debug)]
+pub struct VideoEncodingArgs {
+    /// FourCC code of the output codec, e.g. mp4v, avc1, MJPG
+    #[arg(long, default_value = "mp4v", requires = "output")]
+    pub codec: String,
+
+    /// Output frame rate, defaults to the frame rate of the source (30 for morph)
+    #[arg(long, value_parser = parse_frame_rate, requires = "output")]
+    pub fps: Option<f64>,
+
+    /// Output bitrate in kbit/s (FFmpeg backend only)
+    #[arg(long, requires = "output")]
+    pub bitrate: Option<u32>,
+}
+
+impl VideoEncodingArgs {
+    /// Stellt die Einstellungen für eine Videodatei zusammen.
+    ///
+    /// # Parameter
+    /// - `path` (`String`): Der Pfad der Ausgabedatei.
+    pub fn video_output(&self, path: String) -> VideoOutput {
+        VideoOutput {
+            path,
+            codec: self.codec.clone(),
+            fps: self.fps,
+        }
+    }
+}
+
+/// Gemeinsame Angaben zur Bildquelle (Bilddatei oder Webcam).
+///
+/// # Felder
+/// - `image` (`Option<String>`): Der Pfad zur Bilddatei, die verarbeitet werden soll. Wenn keine Bilddatei angegeben wird, wird die Webcam verwendet.
+/// - `resolution` (`Option<Resolution>`): Eine vordefinierte Auflösung, die benutzerdefinierte Dimensionen überschreibt.
+/// - `dimensions` (`Option<(u32, u32)>`): Benutzerdefinierte Dimensionen im Format `width,height`.
+/// - `video` (`Option<String>`): Eine Videodatei bzw. Stream-URL (oder jede andere Quelle, die
+///   OpenCV öffnen kann).
+/// - `output` (`Option<String>`): Ausgabepfad bzw. Namensvorlage für Bilder oder Videodatei, in die
+///   die transformierten Frames geschrieben werden.
+/// - `quality` (`u8`): JPEG-Qualität für Bildausgaben.
+/// - `format` (`ImageFormatArgs`): Bildformat unabhängig von der Endung.
+/// - `encoding` (`VideoEncodingArgs`): Codec, Bildrate und Bitrate der Videoausgabe.
+/// - `hud` (`bool`): Head-up-Display mit Funktion, Frame-Zeiten und Ausschnitt im
+///   Vorschaufenster anzeigen.
+/// - `view` (`ViewMode`): Vergleichsansicht von Original und transformiertem Bild.
+/// - `presets` (`Option<String>`): Datei mit Funktionen, die im Vorschaufenster per
+///   Zifferntaste gewählt werden.
+/// - `camera` (`Option<CameraSource>`): Kamera als Index oder Gerätepfad, standardmäßig `0`.
+/// - `camera_fps` (`Option<f64>`): Bildrate, die von der Kamera angefordert wird.
+/// - `pixel_format` (`Option<PixelFormat>`): Pixelformat, das von der Kamera angefordert wird.
+/// - `serve` (`Option<String>`): Adresse, unter der die transformierten Frames als MJPEG-Stream
+///   ausgeliefert werden.
+/// - `headless` (`bool`): Frames ohne Fenster verarbeiten, z. B. auf Servern oder in CI.
+/// - `frames` (`Option<u64>`): Höchstzahl der Frames ohne Fenster.
+/// - `duration` (`Option<Duration>`): Höchstdauer ohne Fenster.
+/// - `synthetic` (`Option<String>`): Testbild oder Bilddatei als künstliche Live-Quelle.
+#[derive(Args, Debug)]
+pub struct SourceArgs {
     /// The filename to process (supports file completion in some shells)
     #[arg(
         value_name = "IMAGE_FILENAME",
-        help = "Path to the file to process",
+        help = "Path to the file to process, - for standard input",
         value_hint = clap::ValueHint::FilePath,
         short,
         long
@@ -111,4 +353,787 @@ pub struct Cli {
     /// Custom dimensions in the format width,height
     #[arg(short, long, value_parser = parse_dimensions)]
     pub dimensions: Option<(u32, u32)>,
+
+    /// Video file or stream URL (rtsp://, http://, ...) to transform instead of an image or
+    /// the webcam
+    #[arg(
+        long,
+        value_name = "VIDEO_FILENAME",
+        value_hint = clap::ValueHint::FilePath,
+        conflicts_with = "image"
+    )]
+    pub video: Option<String>,
+
+    /// Output image path or name template ({stem}, {expr}, {ts}, {ext}), - for standard
+    /// output, or the video file for transformed video or webcam frames
+    #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+    pub output: Option<String>,
+
+    /// JPEG quality of saved images (1-100)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_JPEG_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub quality: u8,
+
+    #[command(flatten)]
+    pub format: ImageFormatArgs,
+
+    #[command(flatten)]
+    pub encoding: VideoEncodingArgs,
+
+    /// Show function, frame times and viewport in the preview window (toggle with H)
+    #[arg(long)]
+    pub hud: bool,
+
+    /// Show the original next to the transformed image (cycle with V in the preview window)
+    #[arg(long, value_enum, default_value_t = ViewMode::Transformed)]
+    pub view: ViewMode,
+
+    /// File with functions selected by the number keys in the preview window, one per line
+    /// (name = function), default: $HOLO_PRESETS or ~/.config/holo/presets.txt
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub presets: Option<String>,
+
+    /// Camera to open, given as index or device path (see `holo cameras`), default: 0
+    #[arg(
+        long,
+        value_name = "INDEX|PATH",
+        value_parser = parse_camera,
+        conflicts_with_all = ["image", "video"]
+    )]
+    pub camera: Option<CameraSource>,
+
+    /// Frame rate to request from the camera
+    #[arg(long, conflicts_with_all = ["image", "video"])]
+    pub camera_fps: Option<f64>,
+
+    /// Pixel format to request from the camera
+    #[arg(long, value_enum, conflicts_with_all = ["image", "video"])]
+    pub pixel_format: Option<PixelFormat>,
+
+    /// Serve the transformed frames as MJPEG stream over HTTP at this address, e.g.
+    /// 0.0.0.0:8080 (stream at /stream, single frame at /snapshot.jpg)
+    #[arg(long, value_name = "ADDR", conflicts_with = "image")]
+    pub serve: Option<String>,
+
+    /// Run the live pipeline without a window, writing frames to --output and/or --serve
+    #[arg(long, conflicts_with = "image")]
+    pub headless: bool,
+
+    /// Stop after this many frames (headless mode)
+    #[arg(long, value_name = "N", requires = "headless")]
+    pub frames: Option<u64>,
+
+    /// Stop after this many seconds (headless mode)
+    #[arg(long, value_name = "SECONDS", value_parser = parse_duration, requires = "headless")]
+    pub duration: Option<Duration>,
+
+    /// Play a test pattern (pattern) or an image file as live source instead of the webcam
+    #[arg(
+        long,
+        value_name = "pattern|IMAGE",
+        value_hint = clap::ValueHint::FilePath,
+        conflicts_with_all = ["image", "video", "camera"]
+    )]
+    pub synthetic: Option<String>,
+}
+
+impl SourceArgs {
+    /// Bestimmt Breite und Höhe für Webcam oder Ausgabe anhand von Auflösung oder Dimensionen.
+    ///
+    /// # Rückgabewert
+    /// Die gewünschte Größe als Tupel `(u32, u32)`, standardmäßig `640x480`.
+    pub fn target_dimensions(&self) -> (u32, u32) {
+        if let Some(res) = &self.resolution {
+            res.to_dimensions()
+        } else {
+            self.dimensions.unwrap_or((640, 480)) // Standard-Dimensionen
+        }
+    }
+
+    /// Stellt die Aufnahmeeinstellungen für die Kamera zusammen.
+    ///
+    /// # Rückgabewert
+    /// Die `CaptureSettings` mit der Größe aus [`SourceArgs::target_dimensions`].
+    pub fn capture_settings(&self) -> CaptureSettings {
+        let (width, height) = self.target_dimensions();
+        CaptureSettings {
+            width,
+            height,
+            fps: self.camera_fps,
+            pixel_format: self.pixel_format,
+        }
+    }
+
+    /// Prüft Kombinationen von Angaben, die sich nicht mit clap ausdrücken lassen.
+    ///
+    /// # Fehler
+    /// Gibt eine Fehlermeldung zurück, wenn `--headless` weder eine Ausgabe noch eine Grenze
+    /// hat und damit endlos ohne Ergebnis liefe, oder wenn `--output` nicht zur Quelle passt:
+    /// Bilddateien werden als Bild gespeichert, Videos, Webcam und künstliche Quellen als
+    /// Videodatei.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(output) = &self.output {
+            if self.image.is_some() && is_video_path(output) {
+                return Err(format!(
+                    "--output {} is a video file, but --image writes an image, \
+                     use an image extension such as .png or {{ext}}",
+                    output
+                ));
+            }
+            if self.image.is_none() && (is_stdio(output) || ImageFormat::from_path(output).is_ok())
+            {
+                return Err(format!(
+                    "--output {} is an image, but video, webcam and synthetic frames are \
+                     written to a video file such as out.mp4",
+                    output
+                ));
+            }
+        }
+        if self.headless
+            && self.output.is_none()
+            && self.serve.is_none()
+            && !self.frame_limit().is_limited()
+        {
+            return Err(
+                "--headless needs --output, --serve, --frames or --duration, \
+                 otherwise it runs forever without a result"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Stellt die Grenzen für einen Lauf ohne Fenster zusammen.
+    ///
+    /// # Rückgabewert
+    /// Das `FrameLimit` aus `--frames` und `--duration`.
+    pub fn frame_limit(&self) -> FrameLimit {
+        FrameLimit {
+            frames: self.frames,
+            duration: self.duration,
+        }
+    }
+
+    /// Stellt die Einstellungen für die Videoausgabe zusammen.
+    ///
+    /// # Rückgabewert
+    /// Die `VideoOutput`-Einstellungen oder `None`, wenn `--output` nicht angegeben ist.
+    pub fn video_output(&self) -> Option<VideoOutput> {
+        self.output.as_ref().map(|_| self.recording_output())
+    }
+
+    /// Stellt die Einstellungen für Aufzeichnungen aus dem Vorschaufenster zusammen.
+    ///
+    /// # Rückgabewert
+    /// Die `VideoOutput`-Einstellungen; ohne `--output` ist der Pfad
+    /// [`DEFAULT_RECORDING_TEMPLATE`], dessen Platzhalter noch eingesetzt werden müssen.
+    pub fn recording_output(&self) -> VideoOutput {
+        self.encoding.video_output(
+            self.output
+                .clone()
+                .unwrap_or_else(|| DEFAULT_RECORDING_TEMPLATE.to_string()),
+        )
+    }
+
+    /// Stellt die Einstellungen für gespeicherte Bilder zusammen.
+    ///
+    /// # Rückgabewert
+    /// Die `ImageOutput`-Einstellungen, ohne `--output` mit der Standardvorlage.
+    pub fn image_output(&self) -> ImageOutput {
+        ImageOutput::new(
+            self.output.as_deref(),
+            self.quality,
+            self.format.output_format,
+        )
+    }
+}
+
+/// Unterbefehle für spezielle Darstellungsmodi.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Render an Escher-style Droste spiral from the input
+    Droste(DrosteArgs),
+    /// Render a hyperbolic {p,q} tiling of the Poincaré disk
+    Hyperbolic(HyperbolicArgs),
+    /// Render an escape-time fractal (Julia, Mandelbrot, Newton) of a function
+    Fractal(FractalArgs),
+    /// Locate zeros, poles and critical points of a function
+    Analyze(AnalyzeArgs),
+    /// Check whether a function is holomorphic (conformal) over a viewport
+    Check(CheckArgs),
+    /// Render an animated GIF, APNG or WebP while sweeping `t` and `c`
+    RenderAnimation(AnimationArgs),
+    /// Morph between two functions and write a video or an image sequence
+    Morph(MorphArgs),
+    /// Transform every image in a directory in parallel
+    Batch(BatchArgs),
+    /// Re-render an image from the settings stored in its metadata
+    Reproduce(ReproduceArgs),
+    /// Inspect or clear the on-disk lookup table cache
+    Cache(CacheArgs),
+    /// List the connected cameras with their default size, frame rate and pixel format
+    Cameras,
+}
+
+/// Argumente für den Droste-Modus.
+///
+/// # Felder
+/// - `source` (`SourceArgs`): Die Angaben zur Bildquelle.
+/// - `inner` (`f64`): Innerer Radius des rekursiven Lochs in normierten Koordinaten.
+/// - `outer` (`f64`): Äußerer Radius des wiederholten Rings.
+/// - `twist` (`i32`): Anzahl der Spiralarme, `0` für einen geraden Zoom.
+/// - `mask` (`Option<String>`): Optionales Maskenbild für das Loch (Alphakanal oder Helligkeit).
+/// - `levels` (`u32`): Maximale Rekursionstiefe für transparente Pixel.
+#[derive(Args, Debug)]
+pub struct DrosteArgs {
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    /// Inner radius of the recursive hole (normalized coordinates)
+    #[arg(long, default_value_t = 0.2)]
+    pub inner: f64,
+
+    /// Outer radius of the repeated annulus (normalized coordinates)
+    #[arg(long, default_value_t = 1.0)]
+    pub outer: f64,
+
+    /// Number of spiral strands, 0 for a plain zoom
+    #[arg(long, default_value_t = 1, allow_negative_numbers = true)]
+    pub twist: i32,
+
+    /// Mask image marking the hole (alpha channel, or luminance if opaque)
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    pub mask: Option<String>,
+
+    /// Maximum recursion depth for transparent pixels
+    #[arg(long, default_value_t = 8)]
+    pub levels: u32,
+}
+
+/// Argumente für die hyperbolische Parkettierung.
+///
+/// # Felder
+/// - `source` (`SourceArgs`): Die Angaben zur Bildquelle.
+/// - `p` (`u32`): Anzahl der Ecken eines Polygons.
+/// - `q` (`u32`): Anzahl der Polygone pro Ecke.
+/// - `scale` (`f64`): Größe der Textur relativ zum zentralen Polygon.
+/// - `rotation` (`f64`): Drehung der Textur in Grad.
+/// - `offset` (`(f64, f64)`): Verschiebung der Textur im Format `re,im`.
+/// - `mirror` (`bool`): Textur kaleidoskopartig in jedem Dreieck spiegeln.
+/// - `iterations` (`u32`): Maximale Anzahl an Faltungsschritten.
+#[derive(Args, Debug)]
+pub struct HyperbolicArgs {
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    /// Number of vertices of each polygon
+    #[arg(short, long, default_value_t = 7)]
+    pub p: u32,
+
+    /// Number of polygons meeting at each vertex
+    #[arg(short, long, default_value_t = 3)]
+    pub q: u32,
+
+    /// Texture size relative to the inradius of the central polygon
+    #[arg(long, default_value_t = 1.0)]
+    pub scale: f64,
+
+    /// Texture rotation in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub rotation: f64,
+
+    /// Texture offset in the format re,im
+    #[arg(long, value_parser = parse_complex, default_value = "0,0", allow_hyphen_values = true)]
+    pub offset: (f64, f64),
+
+    /// Mirror the texture in every triangle instead of keeping tile orientation
+    #[arg(long)]
+    pub mirror: bool,
+
+    /// Maximum number of folding steps
+    #[arg(long, default_value_t = 64)]
+    pub iterations: u32,
+}
+
+/// Argumente für Escape-Time-Fraktale.
+///
+/// # Felder
+/// - `function` (`String`): Die iterierte Funktion, darf `z` und `c` enthalten.
+/// - `source` (`SourceArgs`): Bildquelle für die Orbit-Trap-Texturierung bzw. Ausgabegröße.
+/// - `kind` (`FractalKind`): Die Art des Fraktals.
+/// - `c` (`(f64, f64)`): Der Parameter `c` für Julia- und Newton-Fraktale.
+/// - `iterations` (`u32`): Maximale Anzahl an Iterationen.
+/// - `bailout` (`f64`): Fluchtradius.
+/// - `center` (`(f64, f64)`): Mittelpunkt des Ausschnitts.
+/// - `scale` (`f64`): Halber Durchmesser des Ausschnitts.
+/// - `texture` (`bool`): Eingabebild bzw. Webcam per Orbit-Trap abtasten statt einzufärben.
+/// - `trap_size` (`f64`): Halbe Kantenlänge des Orbit-Traps.
+#[derive(Args, Debug)]
+pub struct FractalArgs {
+    /// Function to iterate, may use `z` and the parameter `c`
+    #[arg(value_name = "FUNCTION")]
+    pub function: String,
+
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    /// Kind of fractal to render
+    #[arg(short, long, value_enum, default_value_t = FractalKind::Julia)]
+    pub kind: FractalKind,
+
+    /// Parameter c in the format re,im (Julia and Newton)
+    #[arg(short, value_parser = parse_complex, default_value = "-0.8,0.156", allow_hyphen_values = true)]
+    pub c: (f64, f64),
+
+    /// Maximum number of iterations
+    #[arg(long, default_value_t = 100)]
+    pub iterations: u32,
+
+    /// Bailout radius for escaping points
+    #[arg(long, default_value_t = 16.0)]
+    pub bailout: f64,
+
+    /// Center of the viewport in the format re,im
+    #[arg(long, value_parser = parse_complex, default_value = "0,0", allow_hyphen_values = true)]
+    pub center: (f64, f64),
+
+    /// Half extent of the viewport
+    #[arg(long, default_value_t = 1.5)]
+    pub scale: f64,
+
+    /// Sample the input image at the first orbit point inside the trap instead of colouring
+    #[arg(long)]
+    pub texture: bool,
+
+    /// Half edge length of the square orbit trap around the origin
+    #[arg(long, default_value_t = 1.0)]
+    pub trap_size: f64,
+}
+
+/// Argumente für die Analyse von Nullstellen, Polen und kritischen Punkten.
+///
+/// # Felder
+/// - `function` (`String`): Die untersuchte Funktion.
+/// - `center` (`(f64, f64)`): Mittelpunkt des untersuchten Ausschnitts.
+/// - `scale` (`f64`): Halber Durchmesser des Ausschnitts.
+/// - `grid` (`u32`): Anzahl der Gitterzellen pro Achse.
+/// - `no_critical` (`bool`): Keine kritischen Punkte suchen.
+/// - `image` (`Option<String>`): Bild, das transformiert und markiert wird.
+/// - `mark` (`bool`): Gefundene Punkte im transformierten Bild markieren.
+/// - `output` (`Option<String>`): Ausgabepfad bzw. Namensvorlage für das markierte Bild.
+/// - `quality` (`u8`): JPEG-Qualität des markierten Bildes.
+/// - `format` (`ImageFormatArgs`): Bildformat unabhängig von der Endung.
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Function to analyze
+    #[arg(value_name = "FUNCTION")]
+    pub function: String,
+
+    /// Center of the viewport in the format re,im
+    #[arg(long, value_parser = parse_complex, default_value = "0,0", allow_hyphen_values = true)]
+    pub center: (f64, f64),
+
+    /// Half extent of the viewport
+    #[arg(long, default_value_t = 1.0)]
+    pub scale: f64,
+
+    /// Number of grid cells per axis
+    #[arg(long, default_value_t = 48)]
+    pub grid: u32,
+
+    /// Skip the search for critical points
+    #[arg(long)]
+    pub no_critical: bool,
+
+    /// Image to transform and annotate
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub image: Option<String>,
+
+    /// Mark the located points on the transformed image
+    #[arg(long, requires = "image")]
+    pub mark: bool,
+
+    /// Output path or name template ({stem}, {expr}, {ts}, {ext}) for the marked image, - for
+    /// standard output
+    #[arg(short, long, value_hint = clap::ValueHint::AnyPath, requires = "mark")]
+    pub output: Option<String>,
+
+    /// JPEG quality of the marked image (1-100)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_JPEG_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub quality: u8,
+
+    #[command(flatten)]
+    pub format: ImageFormatArgs,
+}
+
+impl AnalyzeArgs {
+    /// Stellt die Einstellungen für das markierte Bild zusammen.
+    pub fn image_output(&self) -> ImageOutput {
+        ImageOutput::new(
+            self.output.as_deref(),
+            self.quality,
+            self.format.output_format,
+        )
+    }
+}
+
+/// Argumente für die Holomorphieprüfung.
+///
+/// # Felder
+/// - `function` (`String`): Die untersuchte Funktion.
+/// - `center` (`(f64, f64)`): Mittelpunkt des untersuchten Ausschnitts.
+/// - `scale` (`f64`): Halber Durchmesser des Ausschnitts.
+/// - `grid` (`u32`): Anzahl der Stützstellen pro Achse.
+/// - `heatmap` (`bool`): Den Konformitätsfehler als Heatmap speichern.
+/// - `dimensions` (`(u32, u32)`): Größe der Heatmap im Format `width,height`.
+/// - `output` (`Option<String>`): Ausgabepfad bzw. Namensvorlage für die Heatmap.
+/// - `quality` (`u8`): JPEG-Qualität der Heatmap.
+/// - `format` (`ImageFormatArgs`): Bildformat unabhängig von der Endung.
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    /// Function to check
+    #[arg(value_name = "FUNCTION")]
+    pub function: String,
+
+    /// Center of the viewport in the format re,im
+    #[arg(long, value_parser = parse_complex, default_value = "0,0", allow_hyphen_values = true)]
+    pub center: (f64, f64),
+
+    /// Half extent of the viewport
+    #[arg(long, default_value_t = 1.0)]
+    pub scale: f64,
+
+    /// Number of samples per axis
+    #[arg(long, default_value_t = 64)]
+    pub grid: u32,
+
+    /// Save a heat map of the conformality error
+    #[arg(long)]
+    pub heatmap: bool,
+
+    /// Heat map dimensions in the format width,height
+    #[arg(short, long, value_parser = parse_dimensions, default_value = "640,480")]
+    pub dimensions: (u32, u32),
+
+    /// Output path or name template ({stem}, {expr}, {ts}, {ext}) for the heat map, - for
+    /// standard output
+    #[arg(short, long, value_hint = clap::ValueHint::AnyPath, requires = "heatmap")]
+    pub output: Option<String>,
+
+    /// JPEG quality of the heat map (1-100)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_JPEG_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    pub quality: u8,
+
+    #[command(flatten)]
+    pub format: ImageFormatArgs,
+}
+
+impl CheckArgs {
+    /// Stellt die Einstellungen für die Heatmap zusammen.
+    pub fn image_output(&self) -> ImageOutput {
+        ImageOutput::new(
+            self.output.as_deref(),
+            self.quality,
+            self.format.output_format,
+        )
+    }
+}
+
+/// Argumente für das Rendern von Animationen.
+///
+/// # Felder
+/// - `function` (`String`): Die Transformation, darf `z`, `c` und `t` enthalten.
+/// - `image` (`String`): Das Quellbild.
+/// - `dimensions` (`Option<(u32, u32)>`): Größe der Animation, das Quellbild wird skaliert.
+/// - `output` (`String`): Die Ausgabedatei.
+/// - `format` (`Option<AnimationFormat>`): Containerformat, sonst anhand der Dateiendung.
+/// - `frames` (`u32`): Anzahl der berechneten Frames.
+/// - `delay` (`u32`): Anzeigedauer eines Frames in Millisekunden.
+/// - `time` (`(f64, f64)`): Wertebereich von `t`.
+/// - `c` (`(f64, f64)`): Mittelpunkt des Kreises, auf dem sich `c` bewegt.
+/// - `radius` (`f64`): Radius des Kreises, `0` hält `c` fest.
+/// - `playback` (`Playback`): Abspielreihenfolge.
+/// - `loops` (`u16`): Anzahl der Wiederholungen, `0` für eine Endlosschleife.
+/// - `colors` (`Option<u16>`): Größe der gemeinsamen Farbpalette.
+/// - `dither` (`bool`): Beim Reduzieren der Farben Dithering anwenden.
+#[derive(Args, Debug)]
+pub struct AnimationArgs {
+    /// Function to animate, may use `z`, the parameter `c` and the time `t`
+    #[arg(value_name = "FUNCTION")]
+    pub function: String,
+
+    /// Path to the source image
+    #[arg(
+        short,
+        long,
+        value_name = "IMAGE_FILENAME",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub image: String,
+
+    /// Animation dimensions in the format width,height (the source image is resized)
+    #[arg(short, long, value_parser = parse_dimensions)]
+    pub dimensions: Option<(u32, u32)>,
+
+    /// Output file (.gif, .png/.apng or .webp)
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    pub output: String,
+
+    /// Container format, inferred from the output extension if omitted
+    #[arg(long, value_enum)]
+    pub format: Option<AnimationFormat>,
+
+    /// Number of rendered frames
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..))]
+    pub frames: u32,
+
+    /// Frame delay in milliseconds
+    #[arg(long, default_value_t = 40)]
+    pub delay: u32,
+
+    /// Range of the time variable t in the format start,end
+    #[arg(long, value_parser = parse_range, default_value = "0,1", allow_hyphen_values = true)]
+    pub time: (f64, f64),
+
+    /// Center of the circle the parameter c moves on, in the format re,im
+    #[arg(short, value_parser = parse_complex, default_value = "0,0", allow_hyphen_values = true)]
+    pub c: (f64, f64),
+
+    /// Radius of the circle the parameter c moves on, 0 keeps c fixed
+    #[arg(long, default_value_t = 0.0)]
+    pub radius: f64,
+
+    /// Playback order of the frames
+    #[arg(long, value_enum, default_value_t = Playback::Loop)]
+    pub playback: Playback,
+
+    /// Number of loops, 0 repeats forever
+    #[arg(long, default_value_t = 0)]
+    pub loops: u16,
+
+    /// Quantise all frames to a shared palette of this many colours (GIF default: 256)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub colors: Option<u16>,
+
+    /// Apply Floyd-Steinberg dithering when quantising
+    #[arg(long)]
+    pub dither: bool,
+}
+
+/// Argumente für die Überblendung zwischen zwei Funktionen.
+///
+/// # Felder
+/// - `from` (`String`): Die Funktion am Anfang der Überblendung.
+/// - `to` (`String`): Die Funktion am Ende der Überblendung.
+/// - `image` (`String`): Das Quellbild.
+/// - `dimensions` (`Option<(u32, u32)>`): Größe der Frames, das Quellbild wird skaliert.
+/// - `output` (`String`): Videodatei oder Verzeichnis für eine Bildsequenz.
+/// - `interpolation` (`Interpolation`): Die Art der Überblendung.
+/// - `frames` (`u32`): Anzahl der Frames einschließlich Anfang und Ende.
+/// - `encoding` (`VideoEncodingArgs`): Codec, Bildrate und Bitrate der Videoausgabe.
+#[derive(Args, Debug)]
+pub struct MorphArgs {
+    /// Function at the start of the morph
+    #[arg(value_name = "FROM")]
+    pub from: String,
+
+    /// Function at the end of the morph
+    #[arg(value_name = "TO")]
+    pub to: String,
+
+    /// Path to the source image
+    #[arg(
+        short,
+        long,
+        value_name = "IMAGE_FILENAME",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    pub image: String,
+
+    /// Frame dimensions in the format width,height (the source image is resized)
+    #[arg(short, long, value_parser = parse_dimensions)]
+    pub dimensions: Option<(u32, u32)>,
+
+    /// Output video file, or a directory (no extension) for a PNG image sequence
+    #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+    pub output: String,
+
+    /// How the two functions are blended
+    #[arg(long, value_enum, default_value_t = Interpolation::Homotopy)]
+    pub interpolation: Interpolation,
+
+    /// Number of frames including both ends
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(2..))]
+    pub frames: u32,
+
+    #[command(flatten)]
+    pub encoding: VideoEncodingArgs,
+}
+
+/// Argumente für die Stapelverarbeitung eines Verzeichnisses.
+///
+/// # Felder
+/// - `function` (`String`): Die Transformation.
+/// - `input_dir` (`String`): Verzeichnis mit den Eingabebildern.
+/// - `output_dir` (`String`): Zielverzeichnis, Dateinamen und Formate bleiben erhalten.
+/// - `overwrite` (`bool`): Bereits vorhandene Ausgaben neu berechnen.
+/// - `cache` (`LookupCacheArgs`): Ob der Lookup-Cache verwendet wird.
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// Function to apply to every image
+    #[arg(value_name = "FUNCTION")]
+    pub function: String,
+
+    /// Directory containing the input images
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    pub input_dir: String,
+
+    /// Directory for the transformed images (names and formats are kept)
+    #[arg(long, value_hint = clap::ValueHint::DirPath)]
+    pub output_dir: String,
+
+    /// Reprocess images whose output already exists
+    #[arg(long)]
+    pub overwrite: bool,
+
+    #[command(flatten)]
+    pub cache: LookupCacheArgs,
+}
+
+/// Argumente für das erneute Berechnen eines gespeicherten Bildes.
+///
+/// # Felder
+/// - `image` (`String`): Ein von diesem Programm gespeichertes PNG- oder JPEG-Bild.
+/// - `source` (`Option<String>`): Ersetzt den gespeicherten Pfad des Quellbildes.
+/// - `output` (`Option<String>`): Ausgabepfad bzw. Namensvorlage für das neue Bild.
+/// - `format` (`ImageFormatArgs`): Bildformat unabhängig von der Endung.
+#[derive(Args, Debug)]
+pub struct ReproduceArgs {
+    /// PNG or JPEG image written by holo
+    #[arg(value_name = "IMAGE", value_hint = clap::ValueHint::FilePath)]
+    pub image: String,
+
+    /// Source image to use instead of the path stored in the metadata
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    pub source: Option<String>,
+
+    /// Output path or name template ({stem}, {expr}, {ts}, {ext}), - for standard output
+    #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+    pub output: Option<String>,
+
+    #[command(flatten)]
+    pub format: ImageFormatArgs,
+}
+
+/// Argumente für die Verwaltung des Lookup-Caches.
+///
+/// # Felder
+/// - `action` (`CacheAction`): Die auszuführende Aktion.
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+/// Aktionen für den Lookup-Cache.
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// List the cached lookup tables with their settings and sizes
+    List,
+    /// Delete all cached lookup tables
+    Clear,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headless_needs_output_or_limit() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().source.validate();
+        assert!(parse(&["holo", "z", "--headless"]).is_err());
+        assert!(parse(&["holo", "z", "--headless", "--frames", "10"]).is_ok());
+        assert!(parse(&["holo", "z", "--headless", "--serve", "0.0.0.0:8080"]).is_ok());
+        assert!(parse(&["holo", "z", "--headless", "-o", "out.mp4"]).is_ok());
+        assert!(parse(&["holo", "z"]).is_ok());
+        assert!(Cli::try_parse_from(["holo", "z", "--headless", "--duration", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_video_encoding_is_shared() {
+        let cli = Cli::try_parse_from(["holo", "z", "-o", "out.mp4", "--bitrate", "4000"]).unwrap();
+        assert_eq!(cli.bitrate(), Some(4000));
+        assert_eq!(cli.source.recording_output().codec, "mp4v");
+
+        let args = ["holo", "morph", "z", "z^2", "-i", "in.png", "-o", "out.avi"];
+        let cli =
+            Cli::try_parse_from([&args[..], &["--codec", "MJPG", "--bitrate", "800"]].concat())
+                .unwrap();
+        assert_eq!(cli.bitrate(), Some(800));
+        let Some(Command::Morph(morph)) = cli.command else {
+            panic!("expected morph");
+        };
+        let video = morph.encoding.video_output(morph.output.clone());
+        assert_eq!(
+            (video.path.as_str(), video.codec.as_str()),
+            ("out.avi", "MJPG")
+        );
+
+        assert!(Cli::try_parse_from(["holo", "z", "--codec", "avc1"]).is_err());
+        for fps in ["0", "-5", "inf"] {
+            let args = ["holo", "z", "-o", "out.mp4", "--fps", fps];
+            assert!(Cli::try_parse_from(args).is_err(), "{}", fps);
+        }
+    }
+
+    #[test]
+    fn test_no_cache_is_shared() {
+        assert!(
+            Cli::try_parse_from(["holo", "z", "--no-cache"])
+                .unwrap()
+                .cache
+                .no_cache
+        );
+        let cli = Cli::try_parse_from([
+            "holo",
+            "batch",
+            "z",
+            "--input-dir",
+            "in",
+            "--output-dir",
+            "out",
+            "--no-cache",
+        ])
+        .unwrap();
+        let Some(Command::Batch(batch)) = cli.command else {
+            panic!("expected batch");
+        };
+        assert!(batch.cache.no_cache && batch.cache.open().is_none());
+    }
+
+    #[test]
+    fn test_output_must_match_the_source() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().source.validate();
+        let error = parse(&["holo", "z", "--video", "in.mp4", "-o", "frame_{n}.png"]).unwrap_err();
+        assert!(error.contains("frame_{n}.png"), "{}", error);
+        assert!(parse(&["holo", "z", "-o", "-"]).is_err());
+        assert!(parse(&["holo", "z", "--synthetic", "grid", "-o", "out.JPG"]).is_err());
+        assert!(parse(&["holo", "z", "--video", "in.mp4", "-o", "out_{expr}.mkv"]).is_ok());
+        assert!(parse(&["holo", "z", "-o", "webcam.avi"]).is_ok());
+
+        assert!(parse(&["holo", "z", "-i", "in.png", "-o", "out.MP4"]).is_err());
+        assert!(parse(&["holo", "z", "-i", "in.png", "-o", "out.{ext}"]).is_ok());
+        assert!(parse(&["holo", "z", "-i", "in.png", "-o", "-"]).is_ok());
+    }
 }
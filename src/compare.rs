@@ -0,0 +1,405 @@
+use clap::ValueEnum;
+use image::{imageops, Rgb, RgbImage};
+
+use crate::holo::HolomorphicLookup;
+use crate::overlay::{draw_panel, fill_rect, panel_size, text_scale};
+
+/// Verkleinerungsfaktor des Quellbildes im Bild-im-Bild-Modus.
+const PIP_FACTOR: u32 = 4;
+
+/// Abstand in Pixeln, innerhalb dessen die Trennlinie mit der Maus gegriffen wird.
+const DIVIDER_GRAB: f32 = 6.0;
+
+/// Farbe der Trennlinie und des Rahmens um das Vorschaubild.
+pub const FRAME_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Farbe der Markierungen der Pixelsonde.
+pub const PROBE_COLOR: Rgb<u8> = Rgb([255, 220, 0]);
+
+/// Darstellung von Original und transformiertem Bild.
+///
+/// # Varianten
+/// - `Transformed`: Nur das transformierte Bild.
+/// - `SideBySide`: Original links, transformiertes Bild rechts; das Ergebnis ist doppelt so breit.
+/// - `Split`: Links der Trennlinie das Original, rechts das transformierte Bild.
+/// - `Pip`: Das transformierte Bild mit einer verkleinerten Ansicht des Originals oben rechts.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    #[default]
+    Transformed,
+    SideBySide,
+    Split,
+    Pip,
+}
+
+impl ViewMode {
+    /// Gibt die nächste Darstellung zurück, nach `Pip` wieder `Transformed`.
+    pub fn next(self) -> Self {
+        match self {
+            ViewMode::Transformed => ViewMode::SideBySide,
+            ViewMode::SideBySide => ViewMode::Split,
+            ViewMode::Split => ViewMode::Pip,
+            ViewMode::Pip => ViewMode::Transformed,
+        }
+    }
+}
+
+/// Teilbild einer Vergleichsansicht.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Source,
+    Transformed,
+}
+
+/// Setzt Original und transformiertes Bild zu einer Vergleichsansicht zusammen.
+///
+/// Alle Positionen beziehen sich entweder auf das zusammengesetzte Bild oder, als Paar mit
+/// einem [`Pane`], auf die Pixel des Originals bzw. des transformierten Bildes.
+///
+/// # Felder
+/// - `mode` (`ViewMode`): Die aktuelle Darstellung.
+/// - `divider` (`f32`): Position der Trennlinie im Modus `Split` als Anteil der Breite.
+/// - `probe` (`bool`): Ob die Pixelsonde unter dem Mauszeiger angezeigt wird.
+/// - `dragging` (`bool`): Ob die Trennlinie gerade gezogen wird.
+/// - `pressed` (`bool`): Ob die Maustaste beim letzten Aufruf gedrückt war.
+#[derive(Debug, Clone)]
+pub struct CompareView {
+    pub mode: ViewMode,
+    pub divider: f32,
+    pub probe: bool,
+    dragging: bool,
+    pressed: bool,
+}
+
+impl CompareView {
+    /// Erstellt eine Ansicht mit mittiger Trennlinie.
+    pub fn new(mode: ViewMode) -> Self {
+        CompareView {
+            mode,
+            divider: 0.5,
+            probe: false,
+            dragging: false,
+            pressed: false,
+        }
+    }
+
+    /// Berechnet die Größe des zusammengesetzten Bildes für Frames der Größe `width x height`.
+    pub fn size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.mode {
+            ViewMode::SideBySide => (2 * width, height),
+            _ => (width, height),
+        }
+    }
+
+    /// Gibt die X-Koordinate der Trennlinie zurück.
+    fn divider_x(&self, width: u32) -> u32 {
+        ((self.divider * width as f32) as u32).min(width)
+    }
+
+    /// Gibt Position und Größe der verkleinerten Ansicht im Modus `Pip` zurück.
+    fn pip_rect(width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (thumb_width, thumb_height) = (width / PIP_FACTOR, height / PIP_FACTOR);
+        let margin = (width / 64).max(2);
+        (
+            width.saturating_sub(thumb_width + margin),
+            margin,
+            thumb_width,
+            thumb_height,
+        )
+    }
+
+    /// Ermittelt, welches Teilbild an einer Position des zusammengesetzten Bildes liegt.
+    ///
+    /// # Parameter
+    /// - `x` (`f32`): Die X-Koordinate im zusammengesetzten Bild.
+    /// - `y` (`f32`): Die Y-Koordinate im zusammengesetzten Bild.
+    /// - `width` (`u32`): Die Breite der Frames.
+    /// - `height` (`u32`): Die Höhe der Frames.
+    ///
+    /// # Rückgabewert
+    /// Das Teilbild und die Position in dessen Pixeln oder `None` außerhalb des Bildes.
+    pub fn locate(&self, x: f32, y: f32, width: u32, height: u32) -> Option<(Pane, f32, f32)> {
+        let (total_width, total_height) = self.size(width, height);
+        if x < 0.0 || y < 0.0 || x >= total_width as f32 || y >= total_height as f32 {
+            return None;
+        }
+        Some(match self.mode {
+            ViewMode::Transformed => (Pane::Transformed, x, y),
+            ViewMode::SideBySide if x < width as f32 => (Pane::Source, x, y),
+            ViewMode::SideBySide => (Pane::Transformed, x - width as f32, y),
+            ViewMode::Split if x < self.divider_x(width) as f32 => (Pane::Source, x, y),
+            ViewMode::Split => (Pane::Transformed, x, y),
+            ViewMode::Pip => {
+                let (left, top, thumb_width, thumb_height) = Self::pip_rect(width, height);
+                let (dx, dy) = (x - left as f32, y - top as f32);
+                if dx >= 0.0 && dy >= 0.0 && dx < thumb_width as f32 && dy < thumb_height as f32 {
+                    let factor = PIP_FACTOR as f32;
+                    (Pane::Source, dx * factor, dy * factor)
+                } else {
+                    (Pane::Transformed, x, y)
+                }
+            }
+        })
+    }
+
+    /// Rechnet einen Pixel eines Teilbildes in eine Position des zusammengesetzten Bildes um.
+    ///
+    /// # Rückgabewert
+    /// Die Position oder `None`, wenn das Teilbild in der aktuellen Darstellung fehlt bzw. der
+    /// Pixel im Modus `Split` auf der Seite des anderen Teilbildes liegt.
+    pub fn position(
+        &self,
+        pane: Pane,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Option<(u32, u32)> {
+        match (self.mode, pane) {
+            (ViewMode::Transformed, Pane::Source) => None,
+            (ViewMode::Split, Pane::Source) if x >= self.divider_x(width) => None,
+            (ViewMode::Split, Pane::Transformed) if x < self.divider_x(width) => None,
+            (ViewMode::SideBySide, Pane::Transformed) => Some((x + width, y)),
+            (ViewMode::Pip, Pane::Source) => {
+                let (left, top, _, _) = Self::pip_rect(width, height);
+                Some((left + x / PIP_FACTOR, top + y / PIP_FACTOR))
+            }
+            _ => Some((x, y)),
+        }
+    }
+
+    /// Verschiebt die Trennlinie im Modus `Split`, solange sie mit der Maus gezogen wird.
+    ///
+    /// Die Linie wird nur gegriffen, wenn die Maustaste in ihrer Nähe gedrückt wird; andernfalls
+    /// bleibt die Maus für das Verschieben des Ausschnitts frei.
+    ///
+    /// # Parameter
+    /// - `position` (`Option<(f32, f32)>`): Die Mausposition im zusammengesetzten Bild.
+    /// - `pressed` (`bool`): Ob die linke Maustaste gedrückt ist.
+    /// - `width` (`u32`): Die Breite der Frames.
+    ///
+    /// # Rückgabewert
+    /// `true`, solange die Trennlinie gezogen wird.
+    pub fn drag_divider(
+        &mut self,
+        position: Option<(f32, f32)>,
+        pressed: bool,
+        width: u32,
+    ) -> bool {
+        let was_pressed = std::mem::replace(&mut self.pressed, pressed);
+        if !pressed || self.mode != ViewMode::Split {
+            self.dragging = false;
+            return false;
+        }
+        let Some((x, _)) = position else {
+            return self.dragging;
+        };
+        if !was_pressed {
+            self.dragging = (x - self.divider_x(width) as f32).abs() <= DIVIDER_GRAB;
+        }
+        if self.dragging {
+            self.divider = (x / width as f32).clamp(0.0, 1.0);
+        }
+        self.dragging
+    }
+
+    /// Setzt Original und transformiertes Bild zusammen.
+    ///
+    /// # Parameter
+    /// - `source` (`&RgbImage`): Das Original, es wird bei Bedarf auf die Größe des
+    ///   transformierten Bildes skaliert.
+    /// - `transformed` (`RgbImage`): Das transformierte Bild.
+    ///
+    /// # Rückgabewert
+    /// Das zusammengesetzte Bild der Größe [`CompareView::size`].
+    pub fn compose(&self, source: &RgbImage, transformed: RgbImage) -> RgbImage {
+        let (width, height) = transformed.dimensions();
+        if self.mode == ViewMode::Transformed {
+            return transformed;
+        }
+        let resized;
+        let source = if source.dimensions() == (width, height) {
+            source
+        } else {
+            resized = imageops::resize(source, width, height, imageops::FilterType::Triangle);
+            &resized
+        };
+        match self.mode {
+            ViewMode::Transformed => transformed,
+            ViewMode::SideBySide => {
+                let mut composed = RgbImage::new(2 * width, height);
+                imageops::replace(&mut composed, source, 0, 0);
+                imageops::replace(&mut composed, &transformed, width as i64, 0);
+                composed
+            }
+            ViewMode::Split => {
+                let mut composed = transformed;
+                let divider = self.divider_x(width);
+                let left = imageops::crop_imm(source, 0, 0, divider, height).to_image();
+                imageops::replace(&mut composed, &left, 0, 0);
+                let line = text_scale(width);
+                let x = divider
+                    .saturating_sub(line / 2)
+                    .min(width.saturating_sub(line));
+                fill_rect(&mut composed, x, 0, line, height, |_| FRAME_COLOR);
+                composed
+            }
+            ViewMode::Pip => {
+                let mut composed = transformed;
+                let (left, top, thumb_width, thumb_height) = Self::pip_rect(width, height);
+                let thumbnail = imageops::resize(
+                    source,
+                    thumb_width,
+                    thumb_height,
+                    imageops::FilterType::Triangle,
+                );
+                // Einen Pixel breiter Rahmen um die verkleinerte Ansicht
+                fill_rect(
+                    &mut composed,
+                    left.saturating_sub(1),
+                    top.saturating_sub(1),
+                    thumb_width + 2,
+                    thumb_height + 2,
+                    |_| FRAME_COLOR,
+                );
+                imageops::replace(&mut composed, &thumbnail, left as i64, top as i64);
+                composed
+            }
+        }
+    }
+
+    /// Markiert einen Pixel des transformierten Bildes und seinen Quellpixel.
+    ///
+    /// Neben dem Mauszeiger erscheinen die Koordinaten des Quellpixels; ist das Original
+    /// sichtbar, wird der Quellpixel dort ebenfalls markiert.
+    ///
+    /// # Parameter
+    /// - `img` (`&mut RgbImage`): Das zusammengesetzte Bild aus [`CompareView::compose`].
+    /// - `lookup` (`&HolomorphicLookup`): Die angewendete Lookup-Tabelle.
+    /// - `x` (`u32`): Die X-Koordinate im transformierten Bild.
+    /// - `y` (`u32`): Die Y-Koordinate im transformierten Bild.
+    pub fn draw_probe(&self, img: &mut RgbImage, lookup: &HolomorphicLookup, x: u32, y: u32) {
+        let (width, height) = (lookup.width, lookup.height);
+        let scale = text_scale(width);
+        let source = lookup.get(x, y).map(|index| (index % width, index / width));
+        if let Some((source_x, source_y)) = source {
+            if let Some((px, py)) = self.position(Pane::Source, source_x, source_y, width, height) {
+                draw_marker(img, px, py, scale);
+            }
+        }
+        let Some((px, py)) = self.position(Pane::Transformed, x, y, width, height) else {
+            return;
+        };
+        draw_marker(img, px, py, scale);
+        let text = match source {
+            Some((source_x, source_y)) => format!("({}, {}) <- ({}, {})", x, y, source_x, source_y),
+            None => format!("({}, {}) <- none", x, y),
+        };
+        // Beschriftung rechts unterhalb des Zeigers, am Bildrand nach innen versetzt
+        let (panel_width, panel_height) = panel_size(&text, scale);
+        let offset = 8 * scale;
+        let label_x = (px + offset).min(img.width().saturating_sub(panel_width));
+        let label_y = (py + offset).min(img.height().saturating_sub(panel_height));
+        draw_panel(img, label_x, label_y, &text, PROBE_COLOR, scale);
+    }
+}
+
+/// Zeichnet ein Fadenkreuz, dessen Mitte frei bleibt, damit der markierte Pixel sichtbar ist.
+fn draw_marker(img: &mut RgbImage, x: u32, y: u32, scale: u32) {
+    let (gap, arm) = (2 * scale, 5 * scale);
+    let left = x.saturating_sub(gap + arm);
+    let top = y.saturating_sub(gap + arm);
+    fill_rect(img, left, y, x.saturating_sub(gap) - left, scale, |_| {
+        PROBE_COLOR
+    });
+    fill_rect(img, x + gap + 1, y, arm, scale, |_| PROBE_COLOR);
+    fill_rect(img, x, top, scale, y.saturating_sub(gap) - top, |_| {
+        PROBE_COLOR
+    });
+    fill_rect(img, x, y + gap + 1, scale, arm, |_| PROBE_COLOR);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_and_locate_panes() {
+        let source = RgbImage::from_pixel(40, 20, Rgb([10, 20, 30]));
+        let transformed = RgbImage::from_pixel(40, 20, Rgb([200, 0, 0]));
+        let mut view = CompareView::new(ViewMode::SideBySide);
+
+        let composed = view.compose(&source, transformed.clone());
+        assert_eq!(composed.dimensions(), (80, 20));
+        assert_eq!(composed.get_pixel(5, 5), source.get_pixel(0, 0));
+        assert_eq!(composed.get_pixel(45, 5), transformed.get_pixel(0, 0));
+        assert_eq!(
+            view.locate(45.0, 5.0, 40, 20),
+            Some((Pane::Transformed, 5.0, 5.0))
+        );
+        assert_eq!(
+            view.position(Pane::Transformed, 5, 5, 40, 20),
+            Some((45, 5))
+        );
+
+        // Die Trennlinie wird nur in ihrer Nähe gegriffen
+        view.mode = ViewMode::Split;
+        assert!(!view.drag_divider(Some((5.0, 5.0)), true, 40));
+        assert!(!view.drag_divider(Some((20.0, 5.0)), true, 40));
+        view.drag_divider(None, false, 40);
+        assert!(view.drag_divider(Some((21.0, 5.0)), true, 40));
+        assert!(view.drag_divider(Some((30.0, 5.0)), true, 40));
+        assert_eq!(view.divider, 0.75);
+        let composed = view.compose(&source, transformed.clone());
+        assert_eq!(composed.dimensions(), (40, 20));
+        assert_eq!(composed.get_pixel(25, 5), source.get_pixel(0, 0));
+        assert_eq!(composed.get_pixel(35, 5), transformed.get_pixel(0, 0));
+        assert_eq!(
+            view.locate(25.0, 5.0, 40, 20),
+            Some((Pane::Source, 25.0, 5.0))
+        );
+
+        view.mode = ViewMode::Pip;
+        let composed = view.compose(&source, transformed);
+        let (left, top) = view.position(Pane::Source, 0, 0, 40, 20).unwrap();
+        assert_eq!(composed.get_pixel(left, top), source.get_pixel(0, 0));
+        assert_eq!(
+            view.locate(left as f32 + 1.0, top as f32, 40, 20),
+            Some((Pane::Source, 4.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_probe_marks_source_pixel() {
+        let lookup = HolomorphicLookup::new(|z| -z, 64, 48);
+        let view = CompareView::new(ViewMode::SideBySide);
+        let mut img = RgbImage::new(128, 48);
+        view.draw_probe(&mut img, &lookup, 10, 10);
+
+        let index = lookup.get(10, 10).unwrap();
+        let (source_x, source_y) = (index % 64, index / 64);
+        // Arme des Fadenkreuzes um den Quellpixel, die Mitte bleibt frei
+        assert_eq!(img.get_pixel(source_x + 4, source_y), &PROBE_COLOR);
+        assert_eq!(img.get_pixel(source_x, source_y), &Rgb([0, 0, 0]));
+        assert_eq!(img.get_pixel(64 + 10, 10 + 4), &PROBE_COLOR);
+
+        // Im Modus `Split` ist der Quellpixel rechts der Trennlinie verdeckt
+        let mut view = CompareView::new(ViewMode::Split);
+        let lookup = HolomorphicLookup::new(|z| 1.0 - z, 128, 128);
+        let index = lookup.get(100, 30).unwrap();
+        let (source_x, source_y) = (index % 128, index / 128);
+        assert!(source_x > 16 && source_x < 64);
+        assert!(view
+            .position(Pane::Source, source_x, source_y, 128, 128)
+            .is_some());
+        view.divider = 0.125;
+        assert_eq!(
+            view.position(Pane::Source, source_x, source_y, 128, 128),
+            None
+        );
+        let mut img = RgbImage::new(128, 128);
+        view.draw_probe(&mut img, &lookup, 100, 30);
+        assert_eq!(img.get_pixel(source_x + 4, source_y), &Rgb([0, 0, 0]));
+        assert_eq!(img.get_pixel(100, 30 + 4), &PROBE_COLOR);
+    }
+}
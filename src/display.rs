@@ -21,15 +21,41 @@ use minifb::Window;
 /// Diese Funktion kann paniken, wenn:
 /// - Das Fenster-Update mit dem Puffer fehlschlägt (`unwrap()` wird verwendet).
 pub fn display_image(window: &mut Window, img: &RgbImage) {
-    let buffer: Vec<u32> = img
-        .pixels()
+    let buffer = rgb_to_buffer(img);
+    display_buffer(window, &buffer, img.width(), img.height());
+}
+
+/// Wandelt ein `RgbImage` in einen `0RGB`-Puffer um, wie ihn [`display_buffer`] erwartet.
+///
+/// # Parameter
+/// - `img` (`&RgbImage`): Das Bild im RGB-Format.
+///
+/// # Rückgabewert
+/// Ein Eintrag je Pixel, zeilenweise.
+pub fn rgb_to_buffer(img: &RgbImage) -> Vec<u32> {
+    img.pixels()
         .map(|p| {
             let (r, g, b) = (p[0] as u32, p[1] as u32, p[2] as u32);
             (r << 16) | (g << 8) | b
         })
-        .collect();
+        .collect()
+}
 
+/// Zeigt einen fertigen `0RGB`-Puffer in einem Minifb-Fenster an.
+///
+/// Gegenstück zu [`display_image`] für Puffer, die direkt beschrieben wurden, z. B. mit
+/// [`crate::holo::HolomorphicLookup::apply_bgr`].
+///
+/// # Parameter
+/// - `window` (`&mut Window`): Das Fenster, in dem der Puffer angezeigt wird.
+/// - `buffer` (`&[u32]`): Ein Eintrag je Pixel, zeilenweise.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+///
+/// # Fehler
+/// Diese Funktion kann paniken, wenn das Fenster-Update mit dem Puffer fehlschlägt.
+pub fn display_buffer(window: &mut Window, buffer: &[u32], width: u32, height: u32) {
     window
-        .update_with_buffer(&buffer, img.width() as usize, img.height() as usize)
+        .update_with_buffer(buffer, width as usize, height as usize)
         .unwrap();
 }
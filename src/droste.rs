@@ -0,0 +1,187 @@
+use std::f64::consts::PI;
+
+use image::{imageops, DynamicImage, GrayImage};
+use num_complex::Complex;
+
+use crate::holo::{complex_to_index, HolomorphicLookup, Viewport};
+
+/// Alphawert, ab dem ein Pixel der Maske als undurchsichtig gilt.
+pub const MASK_THRESHOLD: u8 = 128;
+
+/// Parameter des Droste-Effekts (Escher, „Bildgalerie“).
+///
+/// Das Quellbild wird im log-polaren Raum periodisch fortgesetzt: Der Ring zwischen
+/// `inner_radius` und `outer_radius` wiederholt sich nach innen und außen unendlich oft.
+/// Mit `twist` wird das Gitter gedreht, sodass aus den Ringen eine Spirale entsteht.
+///
+/// # Felder
+/// - `inner_radius` (`f64`): Radius des rekursiven Lochs in normierten Koordinaten.
+/// - `outer_radius` (`f64`): Äußerer Radius des Bildausschnitts, der wiederholt wird.
+/// - `twist` (`i32`): Anzahl der Spiralarme; `0` ergibt einen geraden Zoom ohne Spirale.
+/// - `max_levels` (`u32`): Maximale Anzahl an Rekursionsstufen bei transparenten Pixeln.
+#[derive(Debug, Clone, Copy)]
+pub struct DrosteParams {
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+    pub twist: i32,
+    pub max_levels: u32,
+}
+
+impl Default for DrosteParams {
+    fn default() -> Self {
+        DrosteParams {
+            inner_radius: 0.2,
+            outer_radius: 1.0,
+            twist: 1,
+            max_levels: 8,
+        }
+    }
+}
+
+impl DrosteParams {
+    /// Prüft, ob die Radien einen gültigen Ring beschreiben.
+    ///
+    /// # Fehler
+    /// Gibt eine Fehlermeldung zurück, wenn `0 < inner_radius < outer_radius` verletzt ist.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.inner_radius > 0.0 && self.inner_radius < self.outer_radius {
+            Ok(())
+        } else {
+            Err(format!(
+                "Droste radii must satisfy 0 < inner < outer (got inner={}, outer={})",
+                self.inner_radius, self.outer_radius
+            ))
+        }
+    }
+
+    /// Bildet einen Punkt der Ausgabe auf den zugehörigen Punkt im Ring des Quellbildes ab.
+    ///
+    /// Im log-polaren Raum `u = log(z)` wird mit `c = 1 - i * twist * ln(r2/r1) / (2π)`
+    /// multipliziert. Eine volle Umdrehung in der Ausgabe entspricht damit genau einer
+    /// Umdrehung plus `twist` Zoomstufen im Quellbild, was die Spirale nahtlos schließt.
+    /// Anschließend wird der Realteil periodisch auf `[ln r1, ln r2)` reduziert.
+    ///
+    /// # Parameter
+    /// - `z` (`Complex<f64>`): Der Punkt der Ausgabe in normierten Koordinaten.
+    ///
+    /// # Rückgabewert
+    /// Der Punkt im Ring `inner_radius <= |w| < outer_radius`.
+    pub fn map(&self, z: Complex<f64>) -> Complex<f64> {
+        let log_inner = self.inner_radius.ln();
+        let period = (self.outer_radius / self.inner_radius).ln();
+        let c = Complex::new(1.0, -(self.twist as f64) * period / (2.0 * PI));
+
+        let v = z.ln() * c;
+        let re = log_inner + (v.re - log_inner).rem_euclid(period);
+        Complex::new(re, v.im).exp()
+    }
+
+    /// Berechnet die Lookup-Tabelle für den Droste-Effekt.
+    ///
+    /// Ist eine Maske angegeben, werden transparente Stellen des Quellbildes (das
+    /// rekursive Loch) durch die nächste, um `outer_radius / inner_radius` vergrößerte
+    /// Kopie ersetzt, bis ein undurchsichtiges Pixel gefunden ist oder `max_levels`
+    /// erreicht wird.
+    ///
+    /// # Parameter
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+    /// - `mask` (`Option<&GrayImage>`): Optionale Maske, Werte unter [`MASK_THRESHOLD`] gelten
+    ///   als transparent. Sie wird bei Bedarf auf die Bildgröße skaliert.
+    ///
+    /// # Rückgabewert
+    /// Die vorab berechnete `HolomorphicLookup`.
+    pub fn lookup(
+        &self,
+        width: u32,
+        height: u32,
+        viewport: &Viewport,
+        mask: Option<&GrayImage>,
+    ) -> HolomorphicLookup {
+        let mask = mask.map(|m| {
+            if m.dimensions() == (width, height) {
+                m.clone()
+            } else {
+                imageops::resize(m, width, height, imageops::FilterType::Nearest)
+            }
+        });
+        let zoom = self.outer_radius / self.inner_radius;
+
+        HolomorphicLookup::new(
+            |z| {
+                let mut w = self.map(viewport.center + z * viewport.scale);
+                if let Some(mask) = &mask {
+                    for _ in 0..self.max_levels {
+                        let index = complex_to_index(w, width, height);
+                        if mask.as_raw()[index as usize] >= MASK_THRESHOLD {
+                            break;
+                        }
+                        // Transparentes Pixel: in der nächstgrößeren Kopie weitersuchen
+                        w *= zoom;
+                    }
+                }
+                w
+            },
+            width,
+            height,
+        )
+    }
+}
+
+/// Erzeugt eine Maske für das rekursive Loch aus einem Bild.
+///
+/// Besitzt das Bild einen Alphakanal, wird dieser verwendet, ansonsten die Helligkeit
+/// (schwarz = Loch).
+///
+/// # Parameter
+/// - `img` (`&DynamicImage`): Das Bild, aus dem die Maske gewonnen wird.
+///
+/// # Rückgabewert
+/// Die Maske als Graustufenbild.
+pub fn mask_from_image(img: &DynamicImage) -> GrayImage {
+    if img.color().has_alpha() {
+        let rgba = img.to_rgba8();
+        GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+            image::Luma([rgba.get_pixel(x, y)[3]])
+        })
+    } else {
+        img.to_luma8()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_lands_in_annulus() {
+        let params = DrosteParams::default();
+        for &(re, im) in &[(0.9, 0.1), (0.01, -0.02), (-3.0, 2.0), (0.3, 0.3)] {
+            let w = params.map(Complex::new(re, im));
+            assert!(w.norm() >= params.inner_radius - 1e-9, "{:?}", w);
+            assert!(w.norm() < params.outer_radius + 1e-9, "{:?}", w);
+        }
+    }
+
+    #[test]
+    fn test_map_is_self_similar_without_twist() {
+        let params = DrosteParams {
+            twist: 0,
+            ..DrosteParams::default()
+        };
+        let z = Complex::new(0.5, 0.25);
+        let zoomed = z * (params.inner_radius / params.outer_radius);
+        assert!((params.map(z) - params.map(zoomed)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_radii() {
+        let params = DrosteParams {
+            inner_radius: 1.0,
+            outer_radius: 0.5,
+            ..DrosteParams::default()
+        };
+        assert!(params.validate().is_err());
+    }
+}
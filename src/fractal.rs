@@ -0,0 +1,369 @@
+use std::f64::consts::PI;
+
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+use rayon::prelude::*;
+
+use crate::holo::{complex_to_index, HolomorphicLookup, Viewport, NO_SOURCE};
+use crate::parsing::{Expr, Parameters};
+
+/// Anzahl der Iterationen, nach der sich die Farbpalette wiederholt.
+pub const COLOR_CYCLE: f64 = 32.0;
+
+/// Schrittweite für die numerische Ableitung im Newton-Verfahren.
+const DERIVATIVE_STEP: f64 = 1e-6;
+
+/// Art des Fraktals, das durch Iteration von `f(z)` entsteht.
+///
+/// # Varianten
+/// - `Julia`: Iteration von `z ↦ f(z)` mit festem `c`, Startwert ist der Bildpunkt.
+/// - `Mandelbrot`: Iteration von `z ↦ f(z)` ab `z = 0`, der Bildpunkt ist `c`.
+/// - `Newton`: Newton-Verfahren `z ↦ z - f(z) / f'(z)` zur Nullstellensuche von `f`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalKind {
+    Julia,
+    Mandelbrot,
+    Newton,
+}
+
+/// Ergebnis der Iteration eines einzelnen Startpunkts.
+///
+/// # Felder
+/// - `iterations` (`u32`): Anzahl der durchgeführten Iterationen.
+/// - `point` (`Complex<f64>`): Der letzte Punkt der Orbit.
+/// - `previous` (`Complex<f64>`): Der vorletzte Punkt der Orbit.
+/// - `finished` (`bool`): `true`, wenn der Punkt entkommen (Julia, Mandelbrot) bzw.
+///   konvergiert (Newton) ist.
+/// - `trap` (`Option<Complex<f64>>`): Der erste Orbitpunkt innerhalb der Falle,
+///   normiert auf `[-1, 1]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    pub iterations: u32,
+    pub point: Complex<f64>,
+    pub previous: Complex<f64>,
+    pub finished: bool,
+    pub trap: Option<Complex<f64>>,
+}
+
+/// Einstellungen des Escape-Time-Renderers.
+///
+/// # Felder
+/// - `kind` (`FractalKind`): Die Art des Fraktals.
+/// - `c` (`Complex<f64>`): Der Parameter `c` für Julia- und Newton-Fraktale.
+/// - `max_iterations` (`u32`): Maximale Anzahl an Iterationen.
+/// - `bailout` (`f64`): Fluchtradius, ab dem ein Punkt als entkommen gilt.
+/// - `tolerance` (`f64`): Schrittweite, ab der das Newton-Verfahren als konvergiert gilt.
+/// - `trap_size` (`f64`): Halbe Kantenlänge des quadratischen Orbit-Traps um den Ursprung.
+#[derive(Debug, Clone, Copy)]
+pub struct EscapeTime {
+    pub kind: FractalKind,
+    pub c: Complex<f64>,
+    pub max_iterations: u32,
+    pub bailout: f64,
+    pub tolerance: f64,
+    pub trap_size: f64,
+}
+
+impl Default for EscapeTime {
+    fn default() -> Self {
+        EscapeTime {
+            kind: FractalKind::Julia,
+            c: Complex::new(-0.8, 0.156),
+            max_iterations: 100,
+            bailout: 16.0,
+            tolerance: 1e-9,
+            trap_size: 1.0,
+        }
+    }
+}
+
+impl EscapeTime {
+    /// Iteriert einen Startpunkt, bis er entkommt, konvergiert oder `max_iterations` erreicht.
+    ///
+    /// # Parameter
+    /// - `expr` (`&Expr`): Die Funktion `f`, die von `z` und `c` abhängen darf.
+    /// - `point` (`Complex<f64>`): Der Bildpunkt in der komplexen Ebene.
+    ///
+    /// # Rückgabewert
+    /// Die `Orbit` des Startpunkts.
+    pub fn iterate(&self, expr: &Expr, point: Complex<f64>) -> Orbit {
+        let (mut z, params) = match self.kind {
+            FractalKind::Julia | FractalKind::Newton => (
+                point,
+                Parameters {
+                    c: self.c,
+                    ..Parameters::default()
+                },
+            ),
+            FractalKind::Mandelbrot => (
+                Complex::new(0.0, 0.0),
+                Parameters {
+                    c: point,
+                    ..Parameters::default()
+                },
+            ),
+        };
+        let mut orbit = Orbit {
+            iterations: 0,
+            point: z,
+            previous: z,
+            finished: false,
+            trap: None,
+        };
+
+        while orbit.iterations < self.max_iterations {
+            let next = match self.kind {
+                FractalKind::Julia | FractalKind::Mandelbrot => expr.evaluate_with(z, &params),
+                FractalKind::Newton => {
+                    let h = Complex::new(DERIVATIVE_STEP, 0.0);
+                    let derivative = (expr.evaluate_with(z + h, &params)
+                        - expr.evaluate_with(z - h, &params))
+                        / (2.0 * h);
+                    z - expr.evaluate_with(z, &params) / derivative
+                }
+            };
+            if !next.re.is_finite() || !next.im.is_finite() {
+                break;
+            }
+
+            orbit.previous = z;
+            orbit.point = next;
+            orbit.iterations += 1;
+            z = next;
+
+            if orbit.trap.is_none() && z.re.abs() <= self.trap_size && z.im.abs() <= self.trap_size
+            {
+                orbit.trap = Some(z / self.trap_size);
+            }
+
+            let finished = match self.kind {
+                FractalKind::Julia | FractalKind::Mandelbrot => z.norm() > self.bailout,
+                FractalKind::Newton => (z - orbit.previous).norm() < self.tolerance,
+            };
+            if finished {
+                orbit.finished = true;
+                break;
+            }
+        }
+
+        orbit
+    }
+
+    /// Berechnet die geglättete Iterationszahl eines entkommenen Punkts.
+    ///
+    /// Der Grad der Funktion wird aus dem Verhältnis `ln|z_n| / ln|z_{n-1}|` geschätzt,
+    /// sodass die Glättung auch für andere Funktionen als `z^2 + c` funktioniert.
+    ///
+    /// # Parameter
+    /// - `orbit` (`&Orbit`): Die Orbit eines entkommenen Punkts.
+    ///
+    /// # Rückgabewert
+    /// Die kontinuierliche Iterationszahl.
+    pub fn smooth_iterations(&self, orbit: &Orbit) -> f64 {
+        let log_point = orbit.point.norm().ln();
+        let log_previous = orbit.previous.norm().ln();
+        let degree = if log_previous > 0.0 {
+            (log_point / log_previous).max(1.0 + 1e-6)
+        } else {
+            2.0
+        };
+        let ratio = (log_point / self.bailout.ln()).max(f64::MIN_POSITIVE);
+        orbit.iterations as f64 + 1.0 - ratio.ln() / degree.ln()
+    }
+
+    /// Bestimmt die Farbe eines Bildpunkts.
+    ///
+    /// Entkommene Punkte werden anhand der geglätteten Iterationszahl eingefärbt,
+    /// Newton-Punkte anhand des Arguments der gefundenen Nullstelle und der benötigten
+    /// Iterationen. Punkte, die nicht entkommen bzw. nicht konvergieren, sind schwarz.
+    ///
+    /// # Parameter
+    /// - `orbit` (`&Orbit`): Die Orbit des Bildpunkts.
+    ///
+    /// # Rückgabewert
+    /// Die Farbe des Pixels.
+    pub fn color(&self, orbit: &Orbit) -> Rgb<u8> {
+        if !orbit.finished {
+            return Rgb([0, 0, 0]);
+        }
+        match self.kind {
+            FractalKind::Julia | FractalKind::Mandelbrot => {
+                palette(self.smooth_iterations(orbit) / COLOR_CYCLE, 1.0)
+            }
+            FractalKind::Newton => {
+                let hue = orbit.point.arg() / (2.0 * PI);
+                let brightness = 1.0 - orbit.iterations as f64 / self.max_iterations as f64;
+                palette(hue, brightness.sqrt())
+            }
+        }
+    }
+
+    /// Rendert das Fraktal als Farbbild.
+    ///
+    /// # Parameter
+    /// - `expr` (`&Expr`): Die iterierte Funktion.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+    ///
+    /// # Rückgabewert
+    /// Das gerenderte Bild.
+    pub fn render(&self, expr: &Expr, width: u32, height: u32, viewport: &Viewport) -> RgbImage {
+        let mut img = RgbImage::new(width, height);
+        img.par_chunks_mut(width as usize * 3)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, pixel) in row.chunks_mut(3).enumerate() {
+                    let point = viewport.pixel_to_complex(x as u32, y as u32, width, height);
+                    let color = self.color(&self.iterate(expr, point));
+                    pixel.copy_from_slice(&color.0);
+                }
+            });
+        img
+    }
+
+    /// Berechnet eine Lookup-Tabelle für Orbit-Trap-Texturierung.
+    ///
+    /// Jeder Bildpunkt wird iteriert; der erste Orbitpunkt, der in das Quadrat
+    /// `[-trap_size, trap_size]²` fällt, bestimmt die Stelle im Eingabebild.
+    /// Punkte, deren Orbit die Falle nie trifft, erhalten keinen Quellpixel.
+    ///
+    /// # Parameter
+    /// - `expr` (`&Expr`): Die iterierte Funktion.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+    ///
+    /// # Rückgabewert
+    /// Die vorab berechnete `HolomorphicLookup`.
+    pub fn trap_lookup(
+        &self,
+        expr: &Expr,
+        width: u32,
+        height: u32,
+        viewport: &Viewport,
+    ) -> HolomorphicLookup {
+        let lookup = (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                (0..width).map(move |x| {
+                    let point = viewport.pixel_to_complex(x, y, width, height);
+                    match self.iterate(expr, point).trap {
+                        Some(trap) => complex_to_index(trap, width, height),
+                        None => NO_SOURCE,
+                    }
+                })
+            })
+            .collect();
+        HolomorphicLookup {
+            lookup,
+            width,
+            height,
+        }
+    }
+}
+
+/// Kosinus-Farbverlauf für die Einfärbung.
+///
+/// # Parameter
+/// - `t` (`f64`): Position im Farbverlauf, periodisch mit Periode 1.
+/// - `brightness` (`f64`): Helligkeitsfaktor zwischen 0 und 1.
+///
+/// # Rückgabewert
+/// Die Farbe an der Position `t`.
+fn palette(t: f64, brightness: f64) -> Rgb<u8> {
+    let channel = |phase: f64| {
+        let value = 0.5 + 0.5 * (2.0 * PI * (t + phase)).cos();
+        (value * brightness.clamp(0.0, 1.0) * 255.0) as u8
+    };
+    Rgb([channel(0.0), channel(0.33), channel(0.67)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape_time(kind: FractalKind, c: Complex<f64>) -> EscapeTime {
+        EscapeTime {
+            kind,
+            c,
+            ..EscapeTime::default()
+        }
+    }
+
+    #[test]
+    fn test_escape_time_iterations() {
+        let expr = Expr::parse("z^2 + c").unwrap();
+        let zero = Complex::new(0.0, 0.0);
+
+        // 2 -> 6 -> 38: entkommt im dritten Schritt
+        let mandelbrot = escape_time(FractalKind::Mandelbrot, zero);
+        let orbit = mandelbrot.iterate(&expr, Complex::new(2.0, 0.0));
+        assert!(orbit.finished);
+        assert_eq!(orbit.iterations, 3);
+        assert_eq!(orbit.point, Complex::new(38.0, 0.0));
+        assert_eq!(orbit.previous, Complex::new(6.0, 0.0));
+        assert!(mandelbrot.smooth_iterations(&orbit) > 2.0);
+
+        let orbit = mandelbrot.iterate(&expr, zero);
+        assert!(!orbit.finished);
+        assert_eq!(orbit.iterations, mandelbrot.max_iterations);
+        assert_eq!(mandelbrot.color(&orbit), Rgb([0, 0, 0]));
+
+        // 3 -> 9 -> 81
+        let julia = escape_time(FractalKind::Julia, zero);
+        let orbit = julia.iterate(&expr, Complex::new(3.0, 0.0));
+        assert!(orbit.finished);
+        assert_eq!(orbit.iterations, 2);
+        assert!(!julia.iterate(&expr, Complex::new(0.5, 0.0)).finished);
+    }
+
+    #[test]
+    fn test_newton_converges_to_roots() {
+        let expr = Expr::parse("z^3 - 1").unwrap();
+        let newton = escape_time(FractalKind::Newton, Complex::new(0.0, 0.0));
+        let roots = (0..3).map(|k| Complex::from_polar(1.0, 2.0 * PI * k as f64 / 3.0));
+        let starts = [
+            Complex::new(1.2, 0.1),
+            Complex::new(-0.5, 0.9),
+            Complex::new(-0.5, -0.9),
+        ];
+        for (start, root) in starts.into_iter().zip(roots) {
+            let orbit = newton.iterate(&expr, start);
+            assert!(orbit.finished, "{} did not converge", start);
+            assert!(
+                (orbit.point - root).norm() < 1e-6,
+                "{} -> {}",
+                start,
+                orbit.point
+            );
+            assert!(orbit.iterations < newton.max_iterations);
+        }
+    }
+
+    #[test]
+    fn test_trap_lookup_uses_viewport() {
+        // Mit f(z) = z ist der Orbit konstant, die Falle trifft genau das Quadrat selbst
+        let expr = Expr::parse("z").unwrap();
+        let fractal = EscapeTime {
+            trap_size: 0.5,
+            ..escape_time(FractalKind::Julia, Complex::new(0.0, 0.0))
+        };
+        let viewport = Viewport {
+            center: Complex::new(0.25, 0.0),
+            scale: 1.0,
+        };
+        let (width, height) = (32, 16);
+        let lookup = fractal.trap_lookup(&expr, width, height, &viewport);
+        for (x, y) in [(20, 8), (12, 6), (2, 2), (31, 15)] {
+            let point = viewport.pixel_to_complex(x, y, width, height);
+            let inside = point.re.abs() <= 0.5 && point.im.abs() <= 0.5;
+            let expected =
+                inside.then(|| complex_to_index(point / fractal.trap_size, width, height));
+            assert_eq!(lookup.get(x, y), expected, "pixel ({}, {})", x, y);
+        }
+        assert!(lookup.get(20, 8).is_some());
+        assert!(lookup.get(2, 2).is_none());
+    }
+}
@@ -0,0 +1,459 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::{imageops, Rgb, RgbImage};
+
+use crate::holo::HolomorphicLookup;
+use crate::output::open_image;
+use crate::pipeline::{FrameSource, Pipeline, PipelineFrame};
+use crate::serve::MjpegServer;
+use crate::video::Recording;
+
+/// Angabe für `--synthetic`, die statt einer Bilddatei das Testbild wählt.
+pub const TEST_PATTERN: &str = "pattern";
+
+/// Größe des Testbildes, wenn weder Auflösung noch Dimensionen angegeben sind.
+pub const PATTERN_SIZE: (u32, u32) = (640, 480);
+
+/// Abstand der Gitterlinien im Testbild in Pixeln.
+const GRID_SPACING: u32 = 32;
+
+/// Wartezeit auf ein Frame, nach der die Grenzen erneut geprüft werden.
+const FRAME_WAIT: Duration = Duration::from_millis(20);
+
+/// Eine künstliche Bildquelle, mit der sich die Pipeline ohne Kamera betreiben lässt.
+///
+/// # Varianten
+/// - `Pattern { width, height }`: Ein bewegtes Testbild aus Farbverlauf, Gitter und einem
+///   wandernden Balken.
+/// - `Image(RgbImage)`: Ein Standbild, das wie ein Video wiederholt wird.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntheticSource {
+    Pattern { width: u32, height: u32 },
+    Image(RgbImage),
+}
+
+impl SyntheticSource {
+    /// Öffnet eine künstliche Bildquelle.
+    ///
+    /// # Parameter
+    /// - `spec` (`&str`): [`TEST_PATTERN`] oder der Pfad einer Bilddatei.
+    /// - `size` (`Option<(u32, u32)>`): Die gewünschte Größe; ohne Angabe hat das Testbild die
+    ///   Größe [`PATTERN_SIZE`] und ein Bild seine eigene.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Bilddatei nicht geladen werden kann.
+    pub fn open(spec: &str, size: Option<(u32, u32)>) -> Result<Self, Box<dyn Error>> {
+        if spec == TEST_PATTERN {
+            let (width, height) = size.unwrap_or(PATTERN_SIZE);
+            return Ok(SyntheticSource::Pattern { width, height });
+        }
+        let img = open_image(spec)?.to_rgb8();
+        Ok(SyntheticSource::Image(match size {
+            Some((width, height)) if (width, height) != img.dimensions() => {
+                imageops::resize(&img, width, height, imageops::FilterType::Triangle)
+            }
+            _ => img,
+        }))
+    }
+
+    /// Gibt Breite und Höhe der Frames zurück.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            SyntheticSource::Pattern { width, height } => (*width, *height),
+            SyntheticSource::Image(img) => img.dimensions(),
+        }
+    }
+
+    /// Erzeugt ein Frame.
+    ///
+    /// # Parameter
+    /// - `index` (`u64`): Die laufende Nummer des Frames, sie bewegt das Testbild.
+    ///
+    /// # Rückgabewert
+    /// Das Frame in der Größe von [`SyntheticSource::dimensions`].
+    pub fn frame(&self, index: u64) -> RgbImage {
+        match self {
+            SyntheticSource::Pattern { width, height } => {
+                let (width, height) = (*width, *height);
+                let shift = (index * 4 % 256) as u32;
+                let bar = (index * 8 % width.max(1) as u64) as u32;
+                RgbImage::from_fn(width, height, |x, y| {
+                    if x % GRID_SPACING == 0 || y % GRID_SPACING == 0 {
+                        return Rgb([255, 255, 255]);
+                    }
+                    let red = ((x * 255 / width.max(1) + shift) % 256) as u8;
+                    let green = (y * 255 / height.max(1)) as u8;
+                    let blue = if x.wrapping_sub(bar) < width / 16 + 1 {
+                        255
+                    } else {
+                        64
+                    };
+                    Rgb([red, green, blue])
+                })
+            }
+            SyntheticSource::Image(img) => img.clone(),
+        }
+    }
+
+    /// Stellt die Quelle für die Pipeline zusammen.
+    ///
+    /// # Parameter
+    /// - `fps` (`f64`): Die Bildrate, in der Frames erzeugt werden.
+    /// - `paced` (`bool`): Frames im Takt von `fps` erzeugen statt so schnell wie möglich.
+    /// - `name` (`&str`): Name der Quelle für Dateinamen.
+    ///
+    /// # Rückgabewert
+    /// Eine endlose Quelle, deren Frames erst im Erfassungs-Thread gezeichnet werden.
+    pub fn into_frames(
+        self,
+        fps: f64,
+        paced: bool,
+        name: &str,
+    ) -> FrameSource<
+        u64,
+        impl FnMut() -> Option<u64> + Send,
+        impl Fn(&u64) -> Option<RgbImage> + Send,
+    > {
+        let mut index = 0;
+        FrameSource {
+            capture: move || {
+                index += 1;
+                Some(index - 1)
+            },
+            convert: move |index: &u64| Some(self.frame(*index)),
+            bgr: None,
+            fps,
+            frame_time: paced.then(|| Duration::from_secs_f64(1.0 / fps)),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Grenzen für einen Lauf ohne Fenster.
+///
+/// # Felder
+/// - `frames` (`Option<u64>`): Höchstzahl der verarbeiteten Frames.
+/// - `duration` (`Option<Duration>`): Höchstdauer des Laufs.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameLimit {
+    pub frames: Option<u64>,
+    pub duration: Option<Duration>,
+}
+
+impl FrameLimit {
+    /// Prüft, ob eine der Grenzen erreicht ist.
+    ///
+    /// # Parameter
+    /// - `frames` (`u64`): Die Anzahl der bisher verarbeiteten Frames.
+    /// - `elapsed` (`Duration`): Die bisherige Laufzeit.
+    pub fn reached(&self, frames: u64, elapsed: Duration) -> bool {
+        self.frames.is_some_and(|limit| frames >= limit)
+            || self.duration.is_some_and(|limit| elapsed >= limit)
+    }
+
+    /// Ob mindestens eine Grenze gesetzt ist.
+    pub fn is_limited(&self) -> bool {
+        self.frames.is_some() || self.duration.is_some()
+    }
+
+    /// Rechnet die Grenzen in eine Höchstzahl an Frames einer Videodatei um.
+    ///
+    /// `duration` gilt dabei als Spielzeit der Datei, nicht als Laufzeit.
+    ///
+    /// # Parameter
+    /// - `fps` (`f64`): Die Bildrate der Datei.
+    ///
+    /// # Rückgabewert
+    /// Die kleinere der beiden Grenzen oder `None`, wenn keine gesetzt ist.
+    pub fn max_frames(&self, fps: f64) -> Option<u64> {
+        let played = self
+            .duration
+            .map(|duration| (duration.as_secs_f64() * fps).ceil() as u64);
+        match (self.frames, played) {
+            (Some(frames), Some(played)) => Some(frames.min(played)),
+            (frames, played) => frames.or(played),
+        }
+    }
+}
+
+/// Ergebnis eines Laufs ohne Fenster.
+///
+/// # Felder
+/// - `frames` (`u64`): Anzahl der verarbeiteten Frames.
+/// - `dropped` (`u64`): Anzahl der von der Pipeline verworfenen Frames.
+/// - `elapsed` (`Duration`): Die Laufzeit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HeadlessSummary {
+    pub frames: u64,
+    pub dropped: u64,
+    pub elapsed: Duration,
+}
+
+impl HeadlessSummary {
+    /// Die mittlere Bildrate des Laufs.
+    pub fn fps(&self) -> f64 {
+        self.frames as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Betreibt die Pipeline aus Erfassung und Transformation ohne Fenster.
+///
+/// Jedes transformierte Frame wird an `sink` übergeben, z. B. um es aufzuzeichnen oder per
+/// HTTP auszuliefern. Der Lauf endet, wenn die Quelle erschöpft oder eine Grenze aus `limit`
+/// erreicht ist.
+///
+/// # Parameter
+/// - `frames` (`FrameSource<T, C, V>`): Die Bildquelle.
+/// - `lookup` (`Arc<HolomorphicLookup>`): Die Lookup-Tabelle.
+/// - `limit` (`&FrameLimit`): Die Grenzen des Laufs.
+/// - `sink` (`impl FnMut(&PipelineFrame) -> Result<(), Box<dyn Error>>`): Verarbeitet die
+///   transformierten Frames.
+///
+/// # Fehler
+/// Gibt den ersten Fehler von `sink` zurück.
+pub fn run_headless<T, C, V>(
+    frames: FrameSource<T, C, V>,
+    lookup: Arc<HolomorphicLookup>,
+    limit: &FrameLimit,
+    mut sink: impl FnMut(&PipelineFrame) -> Result<(), Box<dyn Error>>,
+) -> Result<HeadlessSummary, Box<dyn Error>>
+where
+    T: Send,
+    C: FnMut() -> Option<T> + Send,
+    V: Fn(&T) -> Option<RgbImage> + Send,
+{
+    let start = Instant::now();
+    thread::scope(|scope| {
+        let mut pipeline = Pipeline::spawn(
+            scope,
+            frames.capture,
+            frames.convert,
+            frames.bgr,
+            lookup,
+            frames.frame_time,
+        );
+        let mut summary = HeadlessSummary::default();
+        while !limit.reached(summary.frames, start.elapsed()) {
+            match pipeline.latest(FRAME_WAIT) {
+                Some(frame) => {
+                    sink(&frame)?;
+                    summary.frames += 1;
+                }
+                None if pipeline.is_finished() => break,
+                None => {}
+            }
+        }
+        summary.dropped = pipeline.stats().dropped;
+        summary.elapsed = start.elapsed();
+        Ok(summary)
+    })
+}
+
+/// Betreibt die Pipeline ohne Fenster und gibt jedes transformierte Frame an Aufzeichnung
+/// und Server weiter.
+///
+/// # Parameter
+/// - `frames` (`FrameSource<T, C, V>`): Die Bildquelle.
+/// - `lookup` (`Arc<HolomorphicLookup>`): Die Lookup-Tabelle.
+/// - `limit` (`&FrameLimit`): Die Grenzen des Laufs.
+/// - `recording` (`Option<&mut Recording>`): Die laufende Aufzeichnung, sie bleibt offen.
+/// - `server` (`Option<&MjpegServer>`): Der Server für den MJPEG-Stream.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn ein Frame nicht aufgezeichnet werden kann.
+pub fn run_headless_outputs<T, C, V>(
+    frames: FrameSource<T, C, V>,
+    lookup: Arc<HolomorphicLookup>,
+    limit: &FrameLimit,
+    mut recording: Option<&mut Recording>,
+    server: Option<&MjpegServer>,
+) -> Result<HeadlessSummary, Box<dyn Error>>
+where
+    T: Send,
+    C: FnMut() -> Option<T> + Send,
+    V: Fn(&T) -> Option<RgbImage> + Send,
+{
+    run_headless(frames, lookup, limit, |frame| {
+        let transformed = frame.transformed();
+        if let Some(recording) = recording.as_mut() {
+            recording.write(&transformed)?;
+        }
+        if let Some(server) = server {
+            server.publish(&transformed);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serve::STREAM_PATH;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Eine Quelle, die ihre laufende Nummer in Rot und Grün der Frames kodiert.
+    fn numbered_frames(
+        count: u64,
+        width: u32,
+        height: u32,
+    ) -> FrameSource<
+        u64,
+        impl FnMut() -> Option<u64> + Send,
+        impl Fn(&u64) -> Option<RgbImage> + Send,
+    > {
+        let mut index = 0;
+        FrameSource {
+            capture: move || {
+                index += 1;
+                (index <= count).then_some(index)
+            },
+            convert: move |index: &u64| {
+                let pixel = Rgb([*index as u8, (*index >> 8) as u8, 0]);
+                Some(RgbImage::from_pixel(width, height, pixel))
+            },
+            bgr: None,
+            fps: 30.0,
+            frame_time: None,
+            name: "numbered".to_string(),
+        }
+    }
+
+    /// Liest die laufende Nummer eines Frames aus [`numbered_frames`].
+    fn frame_number(img: &RgbImage) -> u64 {
+        let pixel = img.get_pixel(0, 0);
+        pixel[0] as u64 | (pixel[1] as u64) << 8
+    }
+
+    /// Zerlegt einen MJPEG-Stream in seine vollständig empfangenen JPEG-Bilder.
+    fn jpeg_parts(mut bytes: &[u8]) -> Vec<&[u8]> {
+        const LENGTH: &[u8] = b"Content-Length: ";
+        let mut parts = Vec::new();
+        while let Some(start) = bytes.windows(LENGTH.len()).position(|w| w == LENGTH) {
+            let rest = &bytes[start + LENGTH.len()..];
+            let Some(end) = rest.windows(4).position(|w| w == b"\r\n\r\n") else {
+                break;
+            };
+            let length: usize = std::str::from_utf8(&rest[..end]).unwrap().parse().unwrap();
+            let body = &rest[end + 4..];
+            if body.len() < length {
+                break;
+            }
+            parts.push(&body[..length]);
+            bytes = &body[length..];
+        }
+        parts
+    }
+
+    #[test]
+    fn test_headless_loop_keeps_frame_order() {
+        let source = SyntheticSource::open(TEST_PATTERN, Some((48, 32))).unwrap();
+        assert_eq!(source.dimensions(), (48, 32));
+        assert_ne!(source.frame(0), source.frame(1));
+
+        let lookup = Arc::new(HolomorphicLookup::new(|z| z * z, 48, 32));
+        let limit = FrameLimit {
+            frames: Some(5),
+            duration: None,
+        };
+        let mut received = Vec::new();
+        let summary = run_headless(numbered_frames(1000, 48, 32), lookup, &limit, |frame| {
+            let source = frame.source().unwrap();
+            assert_eq!(
+                Some(&*frame.transformed()),
+                frame.lookup.apply(source).as_ref()
+            );
+            received.push(frame_number(source));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(summary.frames, 5);
+        assert_eq!(received.len(), 5);
+        // Verworfene Frames fehlen, die übrigen kommen in Aufnahmereihenfolge an
+        assert!(
+            received.windows(2).all(|pair| pair[0] < pair[1]),
+            "{:?}",
+            received
+        );
+
+        let limit = FrameLimit {
+            frames: None,
+            duration: Some(Duration::ZERO),
+        };
+        let lookup = Arc::new(HolomorphicLookup::new(|z| z, 48, 32));
+        let summary = run_headless(
+            source.into_frames(30.0, true, TEST_PATTERN),
+            lookup,
+            &limit,
+            |_| Ok(()),
+        )
+        .unwrap();
+        assert_eq!(summary.frames, 0);
+    }
+
+    #[test]
+    fn test_frame_limit_as_file_frames() {
+        let limit = FrameLimit {
+            frames: Some(100),
+            duration: Some(Duration::from_secs(2)),
+        };
+        assert_eq!(limit.max_frames(25.0), Some(50));
+        let limit = FrameLimit {
+            frames: Some(10),
+            duration: None,
+        };
+        assert_eq!(limit.max_frames(25.0), Some(10));
+        let limit = FrameLimit {
+            frames: None,
+            duration: Some(Duration::from_millis(100)),
+        };
+        assert_eq!(limit.max_frames(25.0), Some(3));
+        assert_eq!(FrameLimit::default().max_frames(25.0), None);
+    }
+
+    #[test]
+    fn test_headless_outputs_serve_frames() {
+        let server = MjpegServer::bind("127.0.0.1:0", 90).unwrap();
+        let mut stream = TcpStream::connect(server.addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\n\r\n", STREAM_PATH).unwrap();
+        // Erst starten, wenn der Server den Client angenommen hat
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let client = thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = stream.read_to_end(&mut bytes);
+            bytes
+        });
+
+        let lookup = Arc::new(HolomorphicLookup::new(|z| z, 48, 32));
+        let limit = FrameLimit {
+            frames: Some(20),
+            duration: None,
+        };
+        let frames = SyntheticSource::open(TEST_PATTERN, Some((48, 32)))
+            .unwrap()
+            .into_frames(100.0, true, TEST_PATTERN);
+        let summary = run_headless_outputs(frames, lookup, &limit, None, Some(&server)).unwrap();
+        assert_eq!(summary.frames, 20);
+        // Das Beenden des Servers schließt den Stream
+        drop(server);
+
+        let bytes = client.join().unwrap();
+        let parts = jpeg_parts(&bytes);
+        assert!(!parts.is_empty());
+        for jpeg in parts {
+            let frame = image::load_from_memory(jpeg).unwrap();
+            assert_eq!((frame.width(), frame.height()), (48, 32));
+        }
+    }
+}
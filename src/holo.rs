@@ -1,4 +1,4 @@
-use image::{Rgb, RgbImage};
+use image::{imageops, DynamicImage, ImageBuffer, Pixel, RgbImage};
 use num_complex::Complex;
 use opencv::core::Mat;
 
@@ -9,9 +9,110 @@ use crate::webcam::mat_to_rgb_image;
 /// wird es als Singularität betrachtet.
 pub const SINGULARITY_THRESHOLD: f64 = 1e6;
 
-/// Standardpixel (Fallback), das verwendet wird, wenn eine Singularität auftritt
-/// oder eine Abbildung fehlschlägt.
-pub const FALLBACK_PIXEL: Rgb<u8> = Rgb([0, 0, 0]); // Schwarz
+/// Markierung in der Lookup-Tabelle für Pixel ohne Quellpixel, z. B. bei einer
+/// Singularität. Solche Pixel behalten beim Anwenden den Wert `0` in allen Kanälen
+/// (schwarz bzw. vollständig transparent).
+pub const NO_SOURCE: u32 = u32::MAX;
+
+/// Ausschnitt der komplexen Ebene, der auf das Bild abgebildet wird.
+///
+/// Die normierten Pixelkoordinaten (`[-1, 1]` je Achse) werden mit `scale` skaliert
+/// und um `center` verschoben.
+///
+/// # Felder
+/// - `center` (`Complex<f64>`): Der Mittelpunkt des Ausschnitts.
+/// - `scale` (`f64`): Der halbe Durchmesser des Ausschnitts entlang beider Achsen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub center: Complex<f64>,
+    pub scale: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport {
+            center: Complex::new(0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /// Rechnet Pixelkoordinaten in Punkte der komplexen Ebene innerhalb des Ausschnitts um.
+    ///
+    /// # Parameter
+    /// - `x` (`u32`): Die X-Koordinate des Pixels.
+    /// - `y` (`u32`): Die Y-Koordinate des Pixels.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// Der Punkt der komplexen Ebene, der dem Pixel entspricht.
+    pub fn pixel_to_complex(&self, x: u32, y: u32, width: u32, height: u32) -> Complex<f64> {
+        self.center + pixel_to_complex(x, y, width, height) * self.scale
+    }
+
+    /// Rechnet einen Punkt der komplexen Ebene in (nicht gerundete) Pixelkoordinaten um.
+    ///
+    /// # Parameter
+    /// - `z` (`Complex<f64>`): Der Punkt der komplexen Ebene.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// Die Pixelkoordinaten `(x, y)`; sie können außerhalb des Bildes liegen.
+    pub fn complex_to_pixel(&self, z: Complex<f64>, width: u32, height: u32) -> (f64, f64) {
+        let center_x = width as f64 / 2.0;
+        let center_y = height as f64 / 2.0;
+        let normalized = (z - self.center) / self.scale;
+        (
+            normalized.re * center_x + center_x,
+            normalized.im * center_y + center_y,
+        )
+    }
+
+    /// Vergrößert oder verkleinert den Ausschnitt um einen festen Bildpunkt.
+    ///
+    /// Der Punkt der komplexen Ebene unter `(x, y)` bleibt dabei an derselben Stelle im Bild.
+    ///
+    /// # Parameter
+    /// - `x` (`f64`): Die X-Koordinate des festen Bildpunkts, z. B. die Mausposition.
+    /// - `y` (`f64`): Die Y-Koordinate des festen Bildpunkts.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `factor` (`f64`): Faktor für `scale`, Werte unter `1` vergrößern.
+    ///
+    /// # Rückgabewert
+    /// Der neue Ausschnitt.
+    pub fn zoomed(&self, x: f64, y: f64, width: u32, height: u32, factor: f64) -> Self {
+        let center_x = width as f64 / 2.0;
+        let center_y = height as f64 / 2.0;
+        let anchor = self.center
+            + Complex::new((x - center_x) / center_x, (y - center_y) / center_y) * self.scale;
+        Viewport {
+            center: anchor + (self.center - anchor) * factor,
+            scale: self.scale * factor,
+        }
+    }
+
+    /// Verschiebt den Ausschnitt so, dass der Bildinhalt um `(dx, dy)` Pixel mitwandert.
+    ///
+    /// # Parameter
+    /// - `dx` (`f64`): Verschiebung in X-Richtung in Pixeln.
+    /// - `dy` (`f64`): Verschiebung in Y-Richtung in Pixeln.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// Der neue Ausschnitt.
+    pub fn panned(&self, dx: f64, dy: f64, width: u32, height: u32) -> Self {
+        let offset = Complex::new(dx / (width as f64 / 2.0), dy / (height as f64 / 2.0));
+        Viewport {
+            center: self.center - offset * self.scale,
+            scale: self.scale,
+        }
+    }
+}
 
 /// Eine Struktur zur Vorberechnung und Speicherung einer Lookup-Tabelle
 /// für holomorphe Transformationen.
@@ -46,65 +147,65 @@ impl HolomorphicLookup {
     /// Gibt eine neue Instanz von `HolomorphicLookup` zurück, die die vorab berechnete
     /// Transformation enthält.
     pub fn new(f: impl Fn(Complex<f64>) -> Complex<f64>, width: u32, height: u32) -> Self {
-        let center_x = width as f64 / 2.0;
-        let center_y = height as f64 / 2.0;
+        Self::new_partial(|z| Some(f(z)), width, height)
+    }
+
+    /// Erstellt eine holomorphe Lookup-Tabelle für einen Ausschnitt der komplexen Ebene.
+    ///
+    /// Wie [`HolomorphicLookup::new`], jedoch wird `f` an den Punkten des Ausschnitts
+    /// `viewport` ausgewertet. Die Funktionswerte werden weiterhin in normierten
+    /// Koordinaten des Quellbildes interpretiert.
+    ///
+    /// # Parameter
+    /// - `f` (`impl Fn(Complex<f64>) -> Complex<f64>`): Die Transformation.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+    ///
+    /// # Rückgabewert
+    /// Gibt eine neue Instanz von `HolomorphicLookup` zurück.
+    pub fn with_viewport(
+        f: impl Fn(Complex<f64>) -> Complex<f64>,
+        width: u32,
+        height: u32,
+        viewport: &Viewport,
+    ) -> Self {
+        Self::new(|z| f(viewport.center + z * viewport.scale), width, height)
+    }
 
+    /// Erstellt eine Lookup-Tabelle für eine nur teilweise definierte Abbildung.
+    ///
+    /// Wie [`HolomorphicLookup::new`], jedoch darf `f` für einzelne Punkte `None`
+    /// zurückgeben. Diese Pixel erhalten keinen Quellpixel ([`NO_SOURCE`]) und bleiben
+    /// beim Anwenden schwarz bzw. transparent.
+    ///
+    /// # Parameter
+    /// - `f` (`impl Fn(Complex<f64>) -> Option<Complex<f64>>`): Die Abbildung, `None` für
+    ///   Punkte außerhalb ihres Definitionsbereichs.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// Gibt eine neue Instanz von `HolomorphicLookup` zurück.
+    pub fn new_partial(
+        f: impl Fn(Complex<f64>) -> Option<Complex<f64>>,
+        width: u32,
+        height: u32,
+    ) -> Self {
         // Initialisiere die Lookup-Tabelle
         let mut lookup = Vec::with_capacity((width * height) as usize);
 
         for y in 0..height {
             for x in 0..width {
                 // Transformation der Pixelkoordinaten in komplexe Zahlen
-                let cx = (x as f64 - center_x) / center_x;
-                let cy = (y as f64 - center_y) / center_y;
-                let complex_pos = Complex::new(cx, cy);
-
-                // Anwenden der holomorphen Funktion
-                let result = f(complex_pos);
-
-                // Überprüfen auf Singularitäten
-                if result.re.abs() > SINGULARITY_THRESHOLD
-                    || result.im.abs() > SINGULARITY_THRESHOLD
-                {
-                    lookup.push((height - 1) * width); // Platzhalterindex für Singularitäten
-                    continue;
-                }
-
-                // Rücktransformation der Ergebnisse in Bildkoordinaten
-                let orig_x = result.re * center_x + center_x;
-                let orig_y = result.im * center_y + center_y;
-
-                // Handle quadrant wrapping
-                let orig_x_extended = (orig_x + width as f64) % (width as f64 * 2.0);
-                let orig_y_extended = (orig_y + height as f64) % (height as f64 * 2.0);
-
-                let (final_x, final_y) =
-                    if orig_x_extended < width as f64 && orig_y_extended < height as f64 {
-                        (orig_x_extended, orig_y_extended)
-                    } else if orig_x_extended >= width as f64 && orig_y_extended < height as f64 {
-                        (
-                            width as f64 - (orig_x_extended % width as f64),
-                            orig_y_extended,
-                        )
-                    } else if orig_x_extended < width as f64 && orig_y_extended >= height as f64 {
-                        (
-                            orig_x_extended,
-                            height as f64 - (orig_y_extended % height as f64),
-                        )
-                    } else {
-                        (
-                            width as f64 - (orig_x_extended % width as f64),
-                            height as f64 - (orig_y_extended % height as f64),
-                        )
-                    };
-
-                // Clamp coordinates and convert to index
-                let final_x = final_x.clamp(0.0, width as f64 - 1.0) as u32;
-                let final_y = final_y.clamp(0.0, height as f64 - 1.0) as u32;
-
-                // Calculate and store the index for the transformed coordinates
-                let pixel_index = final_y * width + final_x;
-                lookup.push(pixel_index);
+                let complex_pos = pixel_to_complex(x, y, width, height);
+
+                // Anwenden der Funktion und Rücktransformation in einen Pixelindex
+                let index = match f(complex_pos) {
+                    Some(result) => complex_to_index(result, width, height),
+                    None => NO_SOURCE,
+                };
+                lookup.push(index);
             }
         }
 
@@ -123,11 +224,12 @@ impl HolomorphicLookup {
     ///
     /// # Rückgabewert
     /// Gibt den transformierten Index (`Option<u32>`) zurück oder `None`,
-    /// falls die Koordinaten außerhalb der Bildgrenzen liegen.
+    /// falls die Koordinaten außerhalb der Bildgrenzen liegen oder das Pixel
+    /// keinen Quellpixel besitzt.
     pub fn get(&self, x: u32, y: u32) -> Option<u32> {
         if x < self.width && y < self.height {
             let index = (y * self.width + x) as usize;
-            self.lookup.get(index).cloned()
+            self.lookup.get(index).cloned().filter(|&i| i != NO_SOURCE)
         } else {
             None
         }
@@ -135,34 +237,201 @@ impl HolomorphicLookup {
 
     /// Wendet die Lookup-Tabelle an, um ein Bild zu transformieren.
     ///
+    /// Die Pixel werden unverändert kopiert, daher bleiben Alphakanal und Bittiefe
+    /// (z. B. `Rgba8`, `Rgb16`, `Rgba16`, `Rgb32F`) erhalten. Pixel ohne Quellpixel
+    /// erhalten den Wert `0` in allen Kanälen.
+    ///
     /// # Parameter
-    /// - `img` (`&RgbImage`): Das Eingabebild, das transformiert werden soll.
+    /// - `img` (`&ImageBuffer<P, Vec<P::Subpixel>>`): Das Eingabebild, das transformiert
+    ///   werden soll.
     ///
     /// # Rückgabewert
-    /// Gibt das transformierte Bild (`Option<RgbImage>`) zurück oder `None`,
-    /// falls die Transformation fehlschlägt.
-    pub fn apply(&self, img: &RgbImage) -> Option<RgbImage> {
-        let mut transformed_img = RgbImage::new(self.width, self.height);
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if let Some(mapped_index) = self.get(x, y) {
-                    let orig_x = mapped_index % self.width;
-                    let orig_y = mapped_index / self.width;
-                    let pixel = img.get_pixel(orig_x, orig_y);
-                    transformed_img.put_pixel(x, y, *pixel);
-                } else {
-                    transformed_img.put_pixel(x, y, FALLBACK_PIXEL);
-                }
+    /// Gibt das transformierte Bild zurück oder `None`, falls die Größe des Bildes
+    /// nicht zur Lookup-Tabelle passt.
+    pub fn apply<P: Pixel>(
+        &self,
+        img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    ) -> Option<ImageBuffer<P, Vec<P::Subpixel>>> {
+        if img.dimensions() != (self.width, self.height) {
+            return None;
+        }
+        let channels = P::CHANNEL_COUNT as usize;
+        let source = img.as_raw();
+        let mut transformed_img = ImageBuffer::new(self.width, self.height);
+
+        // Direkt auf den Rohdaten kopieren, ohne Grenzprüfung je Pixel
+        for (pixel, &index) in transformed_img.chunks_exact_mut(channels).zip(&self.lookup) {
+            if index != NO_SOURCE {
+                let start = index as usize * channels;
+                pixel.copy_from_slice(&source[start..start + channels]);
             }
         }
 
         Some(transformed_img)
     }
+
+    /// Wendet die Lookup-Tabelle direkt auf BGR-Rohdaten an und schreibt das Ergebnis in
+    /// einen Fensterpuffer.
+    ///
+    /// Die Bytes eines OpenCV-Frames (siehe [`crate::webcam::bgr_bytes`]) werden in einem
+    /// Durchlauf umsortiert und als `0RGB`-Werte geschrieben, wie sie `minifb` erwartet.
+    /// Es entsteht kein `RgbImage` als Zwischenschritt. Pixel ohne Quellpixel werden schwarz.
+    ///
+    /// # Parameter
+    /// - `bgr` (`&[u8]`): Die Pixel zeilenweise ohne Lücken, je drei Bytes in der Reihenfolge
+    ///   Blau, Grün, Rot.
+    /// - `buffer` (`&mut [u32]`): Der Zielpuffer mit einem Eintrag je Pixel.
+    ///
+    /// # Rückgabewert
+    /// `None`, falls die Größe der Daten oder des Puffers nicht zur Lookup-Tabelle passt.
+    pub fn apply_bgr(&self, bgr: &[u8], buffer: &mut [u32]) -> Option<()> {
+        let pixels = self.lookup.len();
+        if bgr.len() != pixels * 3 || buffer.len() != pixels {
+            return None;
+        }
+        for (out, &index) in buffer.iter_mut().zip(&self.lookup) {
+            *out = if index == NO_SOURCE {
+                0
+            } else {
+                let start = index as usize * 3;
+                let (b, g, r) = (bgr[start], bgr[start + 1], bgr[start + 2]);
+                ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+            };
+        }
+        Some(())
+    }
+
+    /// Wendet die Lookup-Tabelle auf ein Bild beliebigen Pixeltyps an.
+    ///
+    /// Der Pixeltyp des Eingabebildes bleibt erhalten, siehe [`HolomorphicLookup::apply`].
+    ///
+    /// # Parameter
+    /// - `img` (`&DynamicImage`): Das Eingabebild.
+    ///
+    /// # Rückgabewert
+    /// Gibt das transformierte Bild zurück oder `None`, falls die Größe des Bildes
+    /// nicht zur Lookup-Tabelle passt.
+    pub fn apply_dynamic(&self, img: &DynamicImage) -> Option<DynamicImage> {
+        Some(match img {
+            DynamicImage::ImageLuma8(img) => DynamicImage::ImageLuma8(self.apply(img)?),
+            DynamicImage::ImageLumaA8(img) => DynamicImage::ImageLumaA8(self.apply(img)?),
+            DynamicImage::ImageRgb8(img) => DynamicImage::ImageRgb8(self.apply(img)?),
+            DynamicImage::ImageRgba8(img) => DynamicImage::ImageRgba8(self.apply(img)?),
+            DynamicImage::ImageLuma16(img) => DynamicImage::ImageLuma16(self.apply(img)?),
+            DynamicImage::ImageLumaA16(img) => DynamicImage::ImageLumaA16(self.apply(img)?),
+            DynamicImage::ImageRgb16(img) => DynamicImage::ImageRgb16(self.apply(img)?),
+            DynamicImage::ImageRgba16(img) => DynamicImage::ImageRgba16(self.apply(img)?),
+            DynamicImage::ImageRgb32F(img) => DynamicImage::ImageRgb32F(self.apply(img)?),
+            DynamicImage::ImageRgba32F(img) => DynamicImage::ImageRgba32F(self.apply(img)?),
+            img => DynamicImage::ImageRgba32F(self.apply(&img.to_rgba32f())?),
+        })
+    }
+}
+
+/// Rechnet Pixelkoordinaten in normierte komplexe Koordinaten um.
+///
+/// Der Bildmittelpunkt entspricht dem Ursprung, die Bildränder liegen
+/// auf beiden Achsen bei `-1` bzw. `1`.
+///
+/// # Parameter
+/// - `x` (`u32`): Die X-Koordinate des Pixels.
+/// - `y` (`u32`): Die Y-Koordinate des Pixels.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+///
+/// # Rückgabewert
+/// Die komplexe Zahl, die dem Pixel entspricht.
+pub fn pixel_to_complex(x: u32, y: u32, width: u32, height: u32) -> Complex<f64> {
+    let center_x = width as f64 / 2.0;
+    let center_y = height as f64 / 2.0;
+    Complex::new(
+        (x as f64 - center_x) / center_x,
+        (y as f64 - center_y) / center_y,
+    )
+}
+
+/// Rechnet einen Funktionswert in einen Pixelindex des Quellbildes um.
+///
+/// Werte außerhalb des Bildes werden an den Bildrändern gespiegelt, sodass jede
+/// komplexe Zahl einem gültigen Pixel zugeordnet wird.
+///
+/// # Parameter
+/// - `result` (`Complex<f64>`): Der Funktionswert in normierten Koordinaten.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+///
+/// # Rückgabewert
+/// Der Index `y * width + x` des Quellpixels. Bei Singularitäten wird ein
+/// Platzhalterindex in der linken unteren Ecke zurückgegeben.
+pub fn complex_to_index(result: Complex<f64>, width: u32, height: u32) -> u32 {
+    let (x, y) = complex_to_source(result, width, height);
+    y * width + x
+}
+
+/// Rechnet einen Funktionswert in die Koordinaten des Quellpixels um.
+///
+/// Wie [`complex_to_index`], jedoch ohne den Index zu bilden, sodass auch Bilder
+/// mit mehr als `u32::MAX` Pixeln adressiert werden können.
+///
+/// # Parameter
+/// - `result` (`Complex<f64>`): Der Funktionswert in normierten Koordinaten.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+///
+/// # Rückgabewert
+/// Die Koordinaten `(x, y)` des Quellpixels, bei Singularitäten die linke untere Ecke.
+pub fn complex_to_source(result: Complex<f64>, width: u32, height: u32) -> (u32, u32) {
+    let center_x = width as f64 / 2.0;
+    let center_y = height as f64 / 2.0;
+
+    // Überprüfen auf Singularitäten
+    if !result.re.is_finite()
+        || !result.im.is_finite()
+        || result.re.abs() > SINGULARITY_THRESHOLD
+        || result.im.abs() > SINGULARITY_THRESHOLD
+    {
+        return (0, height - 1); // Platzhalter für Singularitäten
+    }
+
+    // Rücktransformation der Ergebnisse in Bildkoordinaten
+    let orig_x = result.re * center_x + center_x;
+    let orig_y = result.im * center_y + center_y;
+
+    // Handle quadrant wrapping
+    let orig_x_extended = (orig_x + width as f64) % (width as f64 * 2.0);
+    let orig_y_extended = (orig_y + height as f64) % (height as f64 * 2.0);
+
+    let (final_x, final_y) = if orig_x_extended < width as f64 && orig_y_extended < height as f64 {
+        (orig_x_extended, orig_y_extended)
+    } else if orig_x_extended >= width as f64 && orig_y_extended < height as f64 {
+        (
+            width as f64 - (orig_x_extended % width as f64),
+            orig_y_extended,
+        )
+    } else if orig_x_extended < width as f64 && orig_y_extended >= height as f64 {
+        (
+            orig_x_extended,
+            height as f64 - (orig_y_extended % height as f64),
+        )
+    } else {
+        (
+            width as f64 - (orig_x_extended % width as f64),
+            height as f64 - (orig_y_extended % height as f64),
+        )
+    };
+
+    // Clamp coordinates
+    let final_x = final_x.clamp(0.0, width as f64 - 1.0) as u32;
+    let final_y = final_y.clamp(0.0, height as f64 - 1.0) as u32;
+
+    (final_x, final_y)
 }
 
 /// Verarbeitet ein einzelnes Webcam-Frame und wendet eine holomorphe Transformation an.
 ///
+/// Frames, deren Größe nicht zur Lookup-Tabelle passt (z. B. weil die Kamera oder ein
+/// Video eine andere Auflösung liefert), werden vorher auf deren Größe skaliert.
+///
 /// # Parameter
 /// - `lookup` (`&HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle.
 /// - `mat` (`&Mat`): Das Webcam-Frame im OpenCV-Mat-Format.
@@ -170,6 +439,108 @@ impl HolomorphicLookup {
 /// # Rückgabewert
 /// Gibt das transformierte Bild (`Option<RgbImage>`) zurück oder `None`, falls ein Fehler auftritt.
 pub fn process_frame(lookup: &HolomorphicLookup, mat: &Mat) -> Option<RgbImage> {
-    let img = mat_to_rgb_image(mat)?;
-    lookup.apply(&img)
+    transform_frame(lookup, &mat_to_rgb_image(mat)?)
+}
+
+/// Wendet eine Lookup-Tabelle auf ein bereits umgewandeltes Frame an.
+///
+/// Wie [`process_frame`], jedoch ohne die Umwandlung aus dem OpenCV-Format, sodass sich
+/// beide Schritte getrennt messen lassen.
+///
+/// # Parameter
+/// - `lookup` (`&HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle.
+/// - `img` (`&RgbImage`): Das Frame, es wird bei Bedarf auf die Größe der Tabelle skaliert.
+///
+/// # Rückgabewert
+/// Das transformierte Bild oder `None`, falls ein Fehler auftritt.
+pub fn transform_frame(lookup: &HolomorphicLookup, img: &RgbImage) -> Option<RgbImage> {
+    if img.dimensions() == (lookup.width, lookup.height) {
+        lookup.apply(img)
+    } else {
+        let resized = imageops::resize(
+            img,
+            lookup.width,
+            lookup.height,
+            imageops::FilterType::Triangle,
+        );
+        lookup.apply(&resized)
+    }
+}
+
+/// Wendet eine Lookup-Tabelle auf die BGR-Rohdaten eines Frames an und liefert einen
+/// Fensterpuffer.
+///
+/// Das Gegenstück zu [`transform_frame`] für die direkte Anzeige, siehe
+/// [`HolomorphicLookup::apply_bgr`]. Die Rohdaten werden nicht skaliert.
+///
+/// # Parameter
+/// - `lookup` (`&HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle.
+/// - `bgr` (`&[u8]`): Die Pixel des Frames, z. B. aus [`crate::webcam::bgr_bytes`].
+///
+/// # Rückgabewert
+/// Ein `0RGB`-Wert je Pixel oder `None`, falls die Größe nicht zur Tabelle passt.
+pub fn transform_bgr_frame(lookup: &HolomorphicLookup, bgr: &[u8]) -> Option<Vec<u32>> {
+    let mut buffer = vec![0; lookup.width as usize * lookup.height as usize];
+    lookup.apply_bgr(bgr, &mut buffer)?;
+    Some(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, Rgb32FImage, Rgba};
+
+    #[test]
+    fn test_apply_keeps_pixel_type() {
+        let lookup = HolomorphicLookup::new(|z| z, 4, 4);
+        let img =
+            ImageBuffer::<Rgba<u16>, Vec<u16>>::from_pixel(4, 4, Rgba([1000, 40000, 65535, 1234]));
+        assert_eq!(lookup.apply(&img).unwrap(), img);
+
+        let hdr = DynamicImage::ImageRgb32F(Rgb32FImage::from_pixel(4, 4, Rgb([4.5, 0.0, -1.0])));
+        let transformed = lookup.apply_dynamic(&hdr).unwrap();
+        assert_eq!(transformed, hdr);
+
+        // Pixel ohne Quellpixel werden vollständig transparent
+        let partial = HolomorphicLookup::new_partial(|_| None, 4, 4);
+        let transformed = partial.apply(&img).unwrap();
+        assert!(transformed.pixels().all(|p| p.0 == [0; 4]));
+        assert!(partial
+            .apply(&ImageBuffer::<Rgba<u16>, Vec<u16>>::new(3, 4))
+            .is_none());
+    }
+
+    #[test]
+    fn test_apply_bgr_matches_rgb_path() {
+        let lookup = HolomorphicLookup::new(|z| z * z, 6, 4);
+        let img = RgbImage::from_fn(6, 4, |x, y| Rgb([x as u8 * 40, y as u8 * 60, 7]));
+        let bgr: Vec<u8> = img.pixels().flat_map(|p| [p[2], p[1], p[0]]).collect();
+
+        let mut buffer = vec![0; 24];
+        lookup.apply_bgr(&bgr, &mut buffer).unwrap();
+        let expected: Vec<u32> = lookup
+            .apply(&img)
+            .unwrap()
+            .pixels()
+            .map(|p| ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32)
+            .collect();
+        assert_eq!(buffer, expected);
+        assert!(lookup.apply_bgr(&bgr[3..], &mut buffer).is_none());
+    }
+
+    #[test]
+    fn test_zoom_keeps_anchor_and_pan_moves_content() {
+        let viewport = Viewport {
+            center: Complex::new(0.5, -0.25),
+            scale: 2.0,
+        };
+        let anchor = viewport.pixel_to_complex(30, 10, 80, 40);
+        let zoomed = viewport.zoomed(30.0, 10.0, 80, 40, 0.5);
+        assert_eq!(zoomed.scale, 1.0);
+        assert!((zoomed.pixel_to_complex(30, 10, 80, 40) - anchor).norm() < 1e-12);
+
+        // Nach dem Verschieben liegt der Punkt um (dx, dy) Pixel weiter
+        let panned = viewport.panned(8.0, -4.0, 80, 40);
+        assert!((panned.pixel_to_complex(38, 6, 80, 40) - anchor).norm() < 1e-12);
+    }
 }
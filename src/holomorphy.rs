@@ -0,0 +1,183 @@
+use image::{Rgb, RgbImage};
+use num_complex::Complex;
+use rayon::prelude::*;
+
+use crate::holo::Viewport;
+use crate::parsing::Expr;
+
+/// Schrittweite für die numerischen partiellen Ableitungen.
+const DIFFERENCE_STEP: f64 = 1e-6;
+
+/// Konformitätsfehler, ab dem ein Punkt als nicht holomorph gilt.
+pub const RESIDUAL_TOLERANCE: f64 = 1e-4;
+
+/// Anteil der Stützstellen, der den Fehler überschreiten darf, ohne dass der Ausdruck
+/// als nicht holomorph gilt. Nahe Polen wird die Differenzenquotienten-Näherung ungenau.
+pub const MAX_VIOLATION_FRACTION: f64 = 0.01;
+
+/// Zusammenfassung der Holomorphieprüfung eines Ausdrucks.
+///
+/// # Felder
+/// - `symbolic` (`bool`): `true`, wenn eine symbolische komplexe Ableitung existiert.
+/// - `samples` (`usize`): Anzahl der ausgewerteten (endlichen) Stützstellen.
+/// - `max_residual` (`f64`): Größter Konformitätsfehler.
+/// - `mean_residual` (`f64`): Mittlerer Konformitätsfehler.
+/// - `violation_fraction` (`f64`): Anteil der Stützstellen mit Fehler über [`RESIDUAL_TOLERANCE`].
+#[derive(Debug, Clone, Copy)]
+pub struct HolomorphyReport {
+    pub symbolic: bool,
+    pub samples: usize,
+    pub max_residual: f64,
+    pub mean_residual: f64,
+    pub violation_fraction: f64,
+}
+
+impl HolomorphyReport {
+    /// Gibt an, ob der Ausdruck im untersuchten Ausschnitt holomorph ist.
+    pub fn is_holomorphic(&self) -> bool {
+        self.symbolic && self.violation_fraction <= MAX_VIOLATION_FRACTION
+    }
+}
+
+/// Berechnet den relativen Cauchy-Riemann-Fehler von `f` im Punkt `z`.
+///
+/// Aus den partiellen Ableitungen `f_x` und `f_y` ergeben sich die Wirtinger-Ableitungen
+/// `∂f = (f_x - i f_y) / 2` und `∂̄f = (f_x + i f_y) / 2`. Der Fehler
+/// `|∂̄f| / (|∂f| + |∂̄f|)` ist `0` für konforme und `1` für antikonforme Abbildungen.
+///
+/// # Parameter
+/// - `f` (`impl Fn(Complex<f64>) -> Complex<f64>`): Die untersuchte Funktion.
+/// - `z` (`Complex<f64>`): Der Auswertungspunkt.
+///
+/// # Rückgabewert
+/// Der Fehler zwischen `0` und `1` oder `None`, wenn `f` bei `z` nicht endlich ist
+/// oder verschwindende Ableitungen besitzt.
+pub fn cauchy_riemann_residual(
+    f: impl Fn(Complex<f64>) -> Complex<f64>,
+    z: Complex<f64>,
+) -> Option<f64> {
+    let h = DIFFERENCE_STEP * z.norm().max(1.0);
+    let f_x = (f(z + Complex::new(h, 0.0)) - f(z - Complex::new(h, 0.0))) / (2.0 * h);
+    let f_y = (f(z + Complex::new(0.0, h)) - f(z - Complex::new(0.0, h))) / (2.0 * h);
+    let i = Complex::<f64>::i();
+    let holomorphic = ((f_x - i * f_y) / 2.0).norm();
+    let antiholomorphic = ((f_x + i * f_y) / 2.0).norm();
+    let total = holomorphic + antiholomorphic;
+
+    if total.is_finite() && total > 0.0 {
+        Some(antiholomorphic / total)
+    } else {
+        None
+    }
+}
+
+/// Prüft einen Ausdruck auf einem Gitter im Ausschnitt auf Holomorphie.
+///
+/// # Parameter
+/// - `expr` (`&Expr`): Der untersuchte Ausdruck.
+/// - `viewport` (`&Viewport`): Der untersuchte Ausschnitt der komplexen Ebene.
+/// - `grid` (`u32`): Anzahl der Stützstellen pro Achse.
+///
+/// # Rückgabewert
+/// Die Zusammenfassung der Prüfung.
+pub fn check(expr: &Expr, viewport: &Viewport, grid: u32) -> HolomorphyReport {
+    let residuals: Vec<f64> = (0..grid * grid)
+        .into_par_iter()
+        .filter_map(|index| {
+            let z = viewport.pixel_to_complex(index % grid, index / grid, grid, grid);
+            cauchy_riemann_residual(|w| expr.evaluate(w), z)
+        })
+        .collect();
+
+    let samples = residuals.len();
+    let max_residual = residuals.iter().cloned().fold(0.0, f64::max);
+    let mean_residual = residuals.iter().sum::<f64>() / samples.max(1) as f64;
+    let violations = residuals
+        .iter()
+        .filter(|&&residual| residual >= RESIDUAL_TOLERANCE)
+        .count();
+
+    HolomorphyReport {
+        symbolic: expr.derivative().is_some(),
+        samples,
+        max_residual,
+        mean_residual,
+        violation_fraction: violations as f64 / samples.max(1) as f64,
+    }
+}
+
+/// Farbverlauf schwarz → rot → gelb → weiß für die Heatmap.
+fn heat_color(value: f64) -> Rgb<u8> {
+    let t = value.clamp(0.0, 1.0) * 3.0;
+    let channel = |offset: f64| ((t - offset).clamp(0.0, 1.0) * 255.0) as u8;
+    Rgb([channel(0.0), channel(1.0), channel(2.0)])
+}
+
+/// Rendert den Konformitätsfehler als Heatmap.
+///
+/// Der Fehler wird logarithmisch zwischen [`RESIDUAL_TOLERANCE`] (schwarz) und `1`
+/// (weiß) skaliert. Punkte ohne gültigen Fehler (z. B. Singularitäten) sind blau.
+///
+/// # Parameter
+/// - `expr` (`&Expr`): Der untersuchte Ausdruck.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+/// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+///
+/// # Rückgabewert
+/// Die Heatmap als Bild.
+pub fn heatmap(expr: &Expr, width: u32, height: u32, viewport: &Viewport) -> RgbImage {
+    let log_tolerance = RESIDUAL_TOLERANCE.log10();
+    let mut img = RgbImage::new(width, height);
+    img.par_chunks_mut(width as usize * 3)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.chunks_mut(3).enumerate() {
+                let z = viewport.pixel_to_complex(x as u32, y as u32, width, height);
+                let color = match cauchy_riemann_residual(|w| expr.evaluate(w), z) {
+                    Some(residual) => {
+                        let level = (residual.max(1e-300).log10() - log_tolerance) / -log_tolerance;
+                        heat_color(level)
+                    }
+                    None => Rgb([0, 0, 255]),
+                };
+                pixel.copy_from_slice(&color.0);
+            }
+        });
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial_is_holomorphic() {
+        let expr = Expr::parse("z^3 + 2*z - 1").unwrap();
+        let report = check(&expr, &Viewport::default(), 32);
+        assert!(report.is_holomorphic(), "{:?}", report);
+    }
+
+    #[test]
+    fn test_powers_with_variable_exponent_are_holomorphic() {
+        // Rechts der Verzweigung des Logarithmus auf der negativen reellen Achse
+        let viewport = Viewport {
+            center: Complex::new(1.5, 0.0),
+            scale: 1.0,
+        };
+        for input in ["2^z", "z^z"] {
+            let expr = Expr::parse(input).unwrap();
+            let report = check(&expr, &viewport, 32);
+            assert!(report.symbolic, "{}", input);
+            assert!(report.is_holomorphic(), "{}: {:?}", input, report);
+        }
+    }
+
+    #[test]
+    fn test_conjugate_is_antiholomorphic() {
+        let expr = Expr::parse("conj(z)").unwrap();
+        let report = check(&expr, &Viewport::default(), 32);
+        assert!(!report.is_holomorphic());
+        assert!((report.max_residual - 1.0).abs() < 1e-6, "{:?}", report);
+    }
+}
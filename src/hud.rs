@@ -0,0 +1,214 @@
+use std::time::{Duration, Instant};
+
+use image::{Rgb, RgbImage};
+
+use crate::live::LookupRequest;
+use crate::overlay::{draw_panel, text_scale};
+
+/// Gewicht eines neuen Messwerts im gleitenden Mittel, z. B. der Frame-Zeiten.
+const SMOOTHING: f64 = 0.1;
+
+/// Schriftfarbe des Head-up-Displays.
+pub const HUD_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Abschnitte eines Frames, deren Dauer einzeln gemessen wird.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Lesen des Frames von Kamera oder Video.
+    Capture,
+    /// Umwandlung des OpenCV-Frames in ein `RgbImage`.
+    Convert,
+    /// Anwenden der Lookup-Tabelle.
+    Apply,
+    /// Übergabe an das Fenster.
+    Display,
+}
+
+/// Gleitende Mittelwerte der Frame-Zeiten in Sekunden.
+///
+/// # Felder
+/// - `capture` (`f64`): Dauer von [`Stage::Capture`].
+/// - `convert` (`f64`): Dauer von [`Stage::Convert`].
+/// - `apply` (`f64`): Dauer von [`Stage::Apply`].
+/// - `display` (`f64`): Dauer von [`Stage::Display`].
+/// - `frame` (`f64`): Abstand zwischen zwei Frames, einschließlich Wartezeiten.
+/// - `latency` (`f64`): Zeit von der Erfassung bis zur Anzeige.
+/// - `dropped` (`u64`): Anzahl der verworfenen Frames.
+/// - `last_frame` (`Option<Instant>`): Beginn des letzten Frames.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimes {
+    pub capture: f64,
+    pub convert: f64,
+    pub apply: f64,
+    pub display: f64,
+    pub frame: f64,
+    pub latency: f64,
+    pub dropped: u64,
+    last_frame: Option<Instant>,
+}
+
+impl FrameTimes {
+    /// Nimmt die Dauer eines Abschnitts in das gleitende Mittel auf.
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        let average = match stage {
+            Stage::Capture => &mut self.capture,
+            Stage::Convert => &mut self.convert,
+            Stage::Apply => &mut self.apply,
+            Stage::Display => &mut self.display,
+        };
+        smooth(average, elapsed.as_secs_f64());
+    }
+
+    /// Markiert den Beginn eines Frames und misst den Abstand zum vorherigen.
+    pub fn start_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame.replace(now) {
+            smooth(&mut self.frame, now.duration_since(last).as_secs_f64());
+        }
+    }
+
+    /// Gibt die mittlere Bildrate zurück, `0` vor dem zweiten Frame.
+    pub fn fps(&self) -> f64 {
+        if self.frame > 0.0 {
+            1.0 / self.frame
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Aktualisiert ein gleitendes Mittel, der erste Messwert wird direkt übernommen.
+pub(crate) fn smooth(average: &mut f64, sample: f64) {
+    *average = if *average == 0.0 {
+        sample
+    } else {
+        *average + (sample - *average) * SMOOTHING
+    };
+}
+
+/// Head-up-Display des Vorschaufensters.
+///
+/// Zeigt die aktuelle Funktion, Auflösung, Bildrate, die Frame-Zeiten je Abschnitt sowie
+/// Mittelpunkt und Zoom des Ausschnitts oben links im Bild an. Die Schrift ist eingebettet
+/// (siehe [`crate::overlay`]), es werden keine Systemschriften benötigt.
+///
+/// # Felder
+/// - `visible` (`bool`): Ob das Display gezeichnet wird.
+/// - `label` (`String`): Beschreibung der Transformation für Modi ohne bearbeitbare Funktion.
+/// - `times` (`FrameTimes`): Die gemessenen Frame-Zeiten.
+#[derive(Debug, Clone)]
+pub struct Hud {
+    pub visible: bool,
+    pub label: String,
+    pub times: FrameTimes,
+}
+
+impl Hud {
+    /// Erstellt das Display für eine Transformation.
+    ///
+    /// # Parameter
+    /// - `label` (`&str`): Beschreibung der Transformation.
+    /// - `visible` (`bool`): Ob das Display anfangs sichtbar ist.
+    pub fn new(label: &str, visible: bool) -> Self {
+        Hud {
+            visible,
+            label: label.to_string(),
+            times: FrameTimes::default(),
+        }
+    }
+
+    /// Stellt den Text des Displays zusammen.
+    ///
+    /// # Parameter
+    /// - `request` (`&LookupRequest`): Funktion und Ausschnitt der angezeigten Lookup-Tabelle.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    pub fn text(&self, request: &LookupRequest, width: u32, height: u32) -> String {
+        let times = &self.times;
+        let ms = |seconds: f64| seconds * 1000.0;
+        let title = match &request.expression {
+            Some(expression) => format!("f(z) = {}", expression),
+            None => self.label.clone(),
+        };
+        let viewport = &request.viewport;
+        [
+            title,
+            format!(
+                "{}x{}  {:.1} fps ({:.1} ms)  latency {:.1} ms  dropped {}",
+                width,
+                height,
+                times.fps(),
+                ms(times.frame),
+                ms(times.latency),
+                times.dropped
+            ),
+            format!(
+                "capture {:.1}  convert {:.1}  apply {:.1}  display {:.1} ms",
+                ms(times.capture),
+                ms(times.convert),
+                ms(times.apply),
+                ms(times.display)
+            ),
+            format!(
+                "center {:.4}{:+.4}i  zoom {:.2}x",
+                viewport.center.re,
+                viewport.center.im,
+                1.0 / viewport.scale
+            ),
+        ]
+        .join("\n")
+    }
+
+    /// Zeichnet das Display oben links in das Bild, sofern es sichtbar ist.
+    ///
+    /// # Parameter
+    /// - `img` (`&mut RgbImage`): Das transformierte Frame.
+    /// - `request` (`&LookupRequest`): Funktion und Ausschnitt der angezeigten Lookup-Tabelle,
+    ///   nicht die gerade angeforderten, solange deren Tabelle noch berechnet wird.
+    pub fn draw(&self, img: &mut RgbImage, request: &LookupRequest) {
+        if self.visible {
+            let (width, height) = img.dimensions();
+            let text = self.text(request, width, height);
+            draw_panel(img, 0, 0, &text, HUD_COLOR, text_scale(width));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holo::Viewport;
+    use crate::parsing::Expr;
+    use num_complex::Complex;
+
+    #[test]
+    fn test_hud_averages_times_and_describes_view() {
+        let mut hud = Hud::new("droste", true);
+        hud.times.record(Stage::Apply, Duration::from_millis(10));
+        hud.times.record(Stage::Apply, Duration::from_millis(20));
+        assert!((hud.times.apply - 0.011).abs() < 1e-9);
+
+        let start = Instant::now();
+        hud.times.start_frame(start);
+        assert_eq!(hud.times.fps(), 0.0);
+        hud.times.start_frame(start + Duration::from_millis(40));
+        assert!((hud.times.fps() - 25.0).abs() < 1e-6);
+
+        let mut request = LookupRequest::default();
+        assert!(hud
+            .text(&request, 640, 480)
+            .starts_with("droste\n640x480  25.0 fps"));
+        request.expression = Expr::parse("z^2").ok();
+        request.viewport = Viewport {
+            center: Complex::new(0.5, -0.25),
+            scale: 0.5,
+        };
+        let text = hud.text(&request, 640, 480);
+        assert!(text.starts_with("f(z) = z ^ 2\n"));
+        assert!(text.contains("apply 11.0"));
+        assert!(text.ends_with("center 0.5000-0.2500i  zoom 2.00x"));
+
+        let mut img = RgbImage::new(320, 60);
+        hud.draw(&mut img, &request);
+        assert!(img.pixels().any(|p| p == &HUD_COLOR));
+    }
+}
@@ -0,0 +1,210 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+use crate::holo::{HolomorphicLookup, Viewport};
+
+/// Parameter einer hyperbolischen `{p,q}`-Parkettierung im Poincaré-Kreismodell.
+///
+/// Die Parkettierung besteht aus regelmäßigen `p`-Ecken, von denen sich jeweils `q`
+/// in einer Ecke treffen. Sie ist nur hyperbolisch, wenn `(p - 2)(q - 2) > 4` gilt.
+///
+/// # Felder
+/// - `p` (`u32`): Anzahl der Ecken eines Polygons.
+/// - `q` (`u32`): Anzahl der Polygone, die sich in einer Ecke treffen.
+/// - `scale` (`f64`): Größe der Textur relativ zum Inkreisradius des zentralen Polygons.
+/// - `rotation` (`f64`): Drehung der Textur im Bogenmaß.
+/// - `offset` (`Complex<f64>`): Verschiebung der Textur in normierten Koordinaten.
+/// - `mirror` (`bool`): Spiegelt die Textur in jedem Dreieck (Kaleidoskop), statt die
+///   Orientierung der Kacheln beizubehalten.
+/// - `max_iterations` (`u32`): Maximale Anzahl an Faltungsschritten.
+#[derive(Debug, Clone, Copy)]
+pub struct HyperbolicTiling {
+    pub p: u32,
+    pub q: u32,
+    pub scale: f64,
+    pub rotation: f64,
+    pub offset: Complex<f64>,
+    pub mirror: bool,
+    pub max_iterations: u32,
+}
+
+impl Default for HyperbolicTiling {
+    fn default() -> Self {
+        HyperbolicTiling {
+            p: 7,
+            q: 3,
+            scale: 1.0,
+            rotation: 0.0,
+            offset: Complex::new(0.0, 0.0),
+            mirror: false,
+            max_iterations: 64,
+        }
+    }
+}
+
+/// Das Fundamentaldreieck einer `{p,q}`-Parkettierung.
+///
+/// Es wird begrenzt von der reellen Achse, der Geraden mit Winkel `π/p` durch den
+/// Ursprung und dem Kreis um `center` (auf der reellen Achse) mit Radius `radius`,
+/// der den Einheitskreis orthogonal schneidet.
+#[derive(Debug, Clone, Copy)]
+struct FundamentalTriangle {
+    angle: f64,
+    center: f64,
+    radius: f64,
+}
+
+impl HyperbolicTiling {
+    /// Prüft, ob `p` und `q` eine hyperbolische Parkettierung beschreiben.
+    ///
+    /// # Fehler
+    /// Gibt eine Fehlermeldung zurück, wenn `p` oder `q` kleiner als 3 sind oder
+    /// `(p - 2)(q - 2) <= 4` gilt (euklidische oder sphärische Parkettierung).
+    pub fn validate(&self) -> Result<(), String> {
+        if self.p < 3 || self.q < 3 || (self.p - 2) * (self.q - 2) <= 4 {
+            Err(format!(
+                "{{{},{}}} is not a hyperbolic tiling, (p-2)(q-2) must be greater than 4",
+                self.p, self.q
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Berechnet das Fundamentaldreieck mit den Winkeln `π/p`, `π/q` und `π/2`.
+    ///
+    /// Der Kreis schneidet die Gerade mit Winkel `a = π/p` unter dem Winkel `b = π/q`.
+    /// Aus `c * sin(a) = r * cos(b)` und der Orthogonalität `c² = 1 + r²` folgen
+    /// Mittelpunkt `c` und Radius `r`.
+    fn triangle(&self) -> FundamentalTriangle {
+        let a = PI / self.p as f64;
+        let b = PI / self.q as f64;
+        let radius = 1.0 / ((b.cos() / a.sin()).powi(2) - 1.0).sqrt();
+        let center = radius * b.cos() / a.sin();
+        FundamentalTriangle {
+            angle: a,
+            center,
+            radius,
+        }
+    }
+
+    /// Faltet einen Punkt der Kreisscheibe durch wiederholte Spiegelungen in das
+    /// Fundamentaldreieck.
+    ///
+    /// Gespiegelt wird an der reellen Achse, an der Geraden mit Winkel `π/p` und
+    /// (per Kreisinversion) am geodätischen Kreis, bis der Punkt im Dreieck liegt.
+    ///
+    /// # Parameter
+    /// - `z` (`Complex<f64>`): Ein Punkt mit `|z| < 1`.
+    ///
+    /// # Rückgabewert
+    /// Der gefaltete Punkt und die Anzahl der durchgeführten Spiegelungen.
+    pub fn fold(&self, z: Complex<f64>) -> (Complex<f64>, u32) {
+        let triangle = self.triangle();
+        let line = Complex::from_polar(1.0, 2.0 * triangle.angle);
+        let center = Complex::new(triangle.center, 0.0);
+        let mut z = z;
+        let mut reflections = 0;
+
+        for _ in 0..self.max_iterations {
+            if z.im < 0.0 {
+                z = z.conj();
+            } else if z.arg() > triangle.angle {
+                z = line * z.conj();
+            } else if (z - center).norm() < triangle.radius {
+                z = center + triangle.radius * triangle.radius / (z - center).conj();
+            } else {
+                break;
+            }
+            reflections += 1;
+        }
+
+        (z, reflections)
+    }
+
+    /// Bildet einen Punkt der Kreisscheibe auf die Texturkoordinate im Quellbild ab.
+    ///
+    /// Ohne `mirror` werden Punkte nach einer ungeraden Anzahl von Spiegelungen
+    /// zusätzlich an der reellen Achse gespiegelt, sodass alle Kacheln dieselbe
+    /// Orientierung besitzen.
+    ///
+    /// # Parameter
+    /// - `z` (`Complex<f64>`): Der Punkt der Ausgabe in normierten Koordinaten.
+    ///
+    /// # Rückgabewert
+    /// Die Quellkoordinate oder `None`, wenn `z` außerhalb der Kreisscheibe liegt.
+    pub fn map(&self, z: Complex<f64>) -> Option<Complex<f64>> {
+        if z.norm() >= 1.0 {
+            return None;
+        }
+
+        let (folded, reflections) = self.fold(z);
+        let folded = if !self.mirror && reflections % 2 == 1 {
+            folded.conj()
+        } else {
+            folded
+        };
+
+        // Der Inkreisradius des zentralen Polygons entspricht dem Texturradius `scale`
+        let triangle = self.triangle();
+        let inradius = triangle.center - triangle.radius;
+        Some(folded * Complex::from_polar(self.scale / inradius, self.rotation) + self.offset)
+    }
+
+    /// Berechnet die Lookup-Tabelle für die Parkettierung.
+    ///
+    /// # Parameter
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    /// - `viewport` (`&Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+    ///
+    /// # Rückgabewert
+    /// Die vorab berechnete `HolomorphicLookup`. Pixel außerhalb der Kreisscheibe
+    /// besitzen keinen Quellpixel.
+    pub fn lookup(&self, width: u32, height: u32, viewport: &Viewport) -> HolomorphicLookup {
+        HolomorphicLookup::new_partial(
+            |z| self.map(viewport.center + z * viewport.scale),
+            width,
+            height,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangle_circle_is_orthogonal_to_unit_circle() {
+        let triangle = HyperbolicTiling::default().triangle();
+        let orthogonality = triangle.center.powi(2) - triangle.radius.powi(2);
+        assert!((orthogonality - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fold_lands_in_fundamental_triangle() {
+        let tiling = HyperbolicTiling::default();
+        let triangle = tiling.triangle();
+        for &(re, im) in &[(0.9, 0.1), (-0.5, -0.7), (0.0, 0.95), (0.1, -0.2)] {
+            let (z, _) = tiling.fold(Complex::new(re, im));
+            assert!(z.im >= 0.0, "{:?}", z);
+            assert!(z.arg() <= triangle.angle + 1e-9, "{:?}", z);
+            assert!(
+                (z - Complex::new(triangle.center, 0.0)).norm() >= triangle.radius - 1e-9,
+                "{:?}",
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_euclidean_tiling() {
+        let tiling = HyperbolicTiling {
+            p: 4,
+            q: 4,
+            ..HyperbolicTiling::default()
+        };
+        assert!(tiling.validate().is_err());
+    }
+}
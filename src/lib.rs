@@ -1,16 +1,37 @@
 /// Dieses Modul enthält die Hauptfunktionalität zur Transformation von Bildern
 /// mit holomorphen Funktionen. Es nutzt verschiedene Submodule, um Parsing,
 /// Transformation und Bildverarbeitung zu implementieren.
-
+pub mod analysis;
+pub mod animation;
+pub mod batch;
+pub mod cache;
+pub mod camera;
+pub mod compare;
 pub mod display;
+pub mod droste;
+pub mod fractal;
+pub mod headless;
 pub mod holo;
+pub mod holomorphy;
+pub mod hud;
+pub mod hyperbolic;
+pub mod live;
+pub mod metadata;
+pub mod morph;
+pub mod output;
+pub mod overlay;
 pub mod parsing;
+pub mod pipeline;
+pub mod presets;
+pub mod serve;
+pub mod tiled;
+pub mod video;
 pub mod webcam;
 
 use std::io::Cursor;
 
 use holo::HolomorphicLookup;
-use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder, RgbImage};
+use image::{codecs::png::PngEncoder, DynamicImage, ImageEncoder, RgbImage, RgbaImage};
 use parsing::Expr;
 use wasm_bindgen::prelude::wasm_bindgen;
 
@@ -18,10 +39,11 @@ use wasm_bindgen::prelude::wasm_bindgen;
 ///
 /// Diese Funktion nimmt ein Bild als Byte-Array und eine mathematische Funktion in Stringform.
 /// Sie wendet die Funktion auf jedes Pixel des Bildes an und gibt das transformierte Bild
-/// als PNG-kodierte Byte-Daten zurück.
+/// als PNG-kodierte Byte-Daten zurück. Ob die Rohdaten RGB oder RGBA (z. B. `ImageData`
+/// eines Canvas) sind, ergibt sich aus ihrer Länge; der Alphakanal bleibt erhalten.
 ///
 /// # Parameter
-/// - `image_data` (`Vec<u8>`): Die Rohdaten des Eingabebildes im RGB-Format.
+/// - `image_data` (`Vec<u8>`): Die Rohdaten des Eingabebildes im RGB- oder RGBA-Format.
 /// - `func_str` (`String`): Die mathematische Funktion, die auf das Bild angewendet wird.
 ///   Diese Funktion muss holomorph sein und in einer unterstützten Syntax vorliegen.
 /// - `width` (`u32`): Die Breite des Eingabebildes in Pixeln.
@@ -33,7 +55,7 @@ use wasm_bindgen::prelude::wasm_bindgen;
 /// wird `None` zurückgegeben.
 ///
 /// # Fehler
-/// - Wenn die Eingabe-Bilddaten ungültig sind.
+/// - Wenn die Länge der Eingabe-Bilddaten weder zu RGB noch zu RGBA passt.
 /// - Wenn die Funktion nicht korrekt geparst werden kann.
 /// - Wenn ein Fehler beim Kodieren der PNG-Daten auftritt.
 #[wasm_bindgen]
@@ -47,13 +69,17 @@ pub fn transform_image(
     let parsed_function = Expr::parse(&func_str).ok();
     let holomorphic_fn = parsed_function?.get_closure();
 
-    // Konvertiere die Eingabe-Bilddaten in ein Bildobjekt
-    let img = RgbImage::from_raw(width, height, image_data).unwrap();
-    let (width, height) = img.dimensions();
+    // Konvertiere die Eingabe-Bilddaten in ein Bildobjekt (RGB oder RGBA)
+    let pixels = width as usize * height as usize;
+    let img = if image_data.len() == pixels * 4 {
+        DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, image_data)?)
+    } else {
+        DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, image_data)?)
+    };
 
     // Wende die holomorphe Funktion auf das Bild an
     let lookup = HolomorphicLookup::new(holomorphic_fn, width, height);
-    let transformed_img = lookup.apply(&img)?;
+    let transformed_img = lookup.apply_dynamic(&img)?;
 
     // Konvertiere das transformierte Bild zurück in einen Byte-Vektor zur Rückgabe
     let mut transformed_data = Vec::new();
@@ -62,12 +88,12 @@ pub fn transform_image(
     let encoder = PngEncoder::new(&mut cursor);
     encoder
         .write_image(
-            transformed_img.as_raw(),
+            transformed_img.as_bytes(),
             transformed_img.width(),
             transformed_img.height(),
-            ExtendedColorType::Rgb8,
+            transformed_img.color().into(),
         )
-        .expect("Failed to encode PNG image");
+        .ok()?;
 
     Some(transformed_data)
 }
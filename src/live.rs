@@ -0,0 +1,478 @@
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::Scope;
+
+use minifb::{Key, KeyRepeat, Window};
+
+use crate::holo::{HolomorphicLookup, Viewport};
+use crate::parsing::{parse_expression, Expr};
+
+/// Faktor, um den ein Schritt des Mausrads den Ausschnitt verkleinert bzw. vergrößert.
+pub const ZOOM_STEP: f64 = 1.25;
+
+/// Eingabeaufforderung der Funktionskonsole.
+const PROMPT: &str = "f(z) = ";
+
+/// Einstellungen, für die in der Vorschau eine Lookup-Tabelle berechnet wird.
+///
+/// # Felder
+/// - `expression` (`Option<Expr>`): Die live bearbeitbare Funktion, `None` für Modi mit fester
+///   Transformation (z. B. Droste).
+/// - `viewport` (`Viewport`): Der dargestellte Ausschnitt der komplexen Ebene.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LookupRequest {
+    pub expression: Option<Expr>,
+    pub viewport: Viewport,
+}
+
+/// Berechnet Lookup-Tabellen für neue Einstellungen in einem Hintergrund-Thread.
+///
+/// Die Vorschau läuft währenddessen mit der bisherigen Tabelle weiter. Anfragen, die während
+/// einer Berechnung eintreffen, werden zusammengefasst, sodass nur die zuletzt angefragten
+/// Einstellungen berechnet werden. Der Thread endet, sobald der `LookupWorker` verworfen wird.
+///
+/// # Felder
+/// - `requests` (`Sender<LookupRequest>`): Angefragte Einstellungen.
+/// - `results` (`Receiver<(LookupRequest, HolomorphicLookup)>`): Fertige Tabellen mit ihren
+///   Einstellungen.
+pub struct LookupWorker {
+    requests: Sender<LookupRequest>,
+    results: Receiver<(LookupRequest, HolomorphicLookup)>,
+}
+
+impl LookupWorker {
+    /// Startet den Hintergrund-Thread im angegebenen Scope.
+    ///
+    /// # Parameter
+    /// - `scope` (`&Scope`): Der Scope aus `std::thread::scope`, der den Thread am Ende einsammelt.
+    /// - `build` (`&F`): Berechnet die Lookup-Tabelle für die angefragten Einstellungen.
+    pub fn spawn<'scope, 'env, F>(scope: &'scope Scope<'scope, 'env>, build: &'env F) -> Self
+    where
+        F: Fn(&LookupRequest) -> HolomorphicLookup + Sync,
+    {
+        let (requests, pending) = mpsc::channel::<LookupRequest>();
+        let (finished, results) = mpsc::channel();
+        scope.spawn(move || {
+            while let Ok(request) = pending.recv() {
+                // Zwischenzeitlich überholte Anfragen überspringen
+                let request = pending.try_iter().last().unwrap_or(request);
+                let lookup = build(&request);
+                if finished.send((request, lookup)).is_err() {
+                    break;
+                }
+            }
+        });
+        LookupWorker { requests, results }
+    }
+
+    /// Fordert die Lookup-Tabelle für neue Einstellungen an.
+    pub fn request(&self, request: LookupRequest) {
+        // Ein beendeter Thread kann keine Tabellen mehr liefern, die Vorschau läuft trotzdem weiter
+        let _ = self.requests.send(request);
+    }
+
+    /// Gibt die zuletzt fertiggestellte Lookup-Tabelle zurück, ohne zu blockieren.
+    ///
+    /// # Rückgabewert
+    /// Die neueste Tabelle mit ihren Einstellungen oder `None`, wenn keine neue vorliegt.
+    pub fn try_receive(&self) -> Option<(LookupRequest, HolomorphicLookup)> {
+        self.results.try_iter().last()
+    }
+}
+
+/// Bewahrt zuletzt verwendete Lookup-Tabellen auf, um beim Zurückwechseln (z. B. zu einer
+/// Voreinstellung) nicht neu rechnen zu müssen.
+///
+/// Pro Funktion wird nur die Tabelle des zuletzt verwendeten Ausschnitts behalten; sind mehr
+/// als `capacity` Funktionen abgelegt, wird die älteste verworfen.
+///
+/// # Felder
+/// - `entries` (`Vec<(LookupRequest, Arc<HolomorphicLookup>)>`): Die Tabellen, älteste zuerst.
+/// - `capacity` (`usize`): Die Höchstzahl aufbewahrter Tabellen.
+pub struct LookupShelf {
+    entries: Vec<(LookupRequest, Arc<HolomorphicLookup>)>,
+    capacity: usize,
+}
+
+impl LookupShelf {
+    /// Erstellt einen leeren Vorrat für höchstens `capacity` Tabellen.
+    pub fn new(capacity: usize) -> Self {
+        LookupShelf {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Legt eine nicht mehr angezeigte Tabelle ab.
+    pub fn store(&mut self, request: LookupRequest, lookup: Arc<HolomorphicLookup>) {
+        self.entries
+            .retain(|(stored, _)| stored.expression != request.expression);
+        self.entries.push((request, lookup));
+        if self.entries.len() > self.capacity {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Entnimmt die Tabelle für genau diese Einstellungen, sofern sie abgelegt wurde.
+    pub fn take(&mut self, request: &LookupRequest) -> Option<Arc<HolomorphicLookup>> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(stored, _)| stored == request)?;
+        Some(self.entries.remove(index).1)
+    }
+}
+
+/// Steuert den Ausschnitt der Vorschau mit der Maus.
+///
+/// Das Mausrad zoomt um die Mausposition, Ziehen mit gedrückter linker Maustaste verschiebt
+/// den Ausschnitt und `Home` stellt den ursprünglichen Ausschnitt wieder her.
+///
+/// # Felder
+/// - `viewport` (`Viewport`): Der aktuell gewünschte Ausschnitt.
+/// - `initial` (`Viewport`): Der Ausschnitt beim Start.
+/// - `drag` (`Option<(f32, f32)>`): Die letzte Mausposition während des Ziehens.
+#[derive(Debug, Clone)]
+pub struct ViewportControl {
+    pub viewport: Viewport,
+    initial: Viewport,
+    drag: Option<(f32, f32)>,
+}
+
+impl ViewportControl {
+    /// Erstellt die Steuerung für einen Startausschnitt.
+    pub fn new(viewport: Viewport) -> Self {
+        ViewportControl {
+            viewport,
+            initial: viewport,
+            drag: None,
+        }
+    }
+
+    /// Zoomt um die Mausposition.
+    ///
+    /// # Parameter
+    /// - `position` (`(f32, f32)`): Die Mausposition in Pixeln.
+    /// - `delta` (`f32`): Die Bewegung des Mausrads, positive Werte vergrößern.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// `true`, wenn sich der Ausschnitt geändert hat.
+    pub fn scroll(&mut self, position: (f32, f32), delta: f32, width: u32, height: u32) -> bool {
+        if delta == 0.0 {
+            return false;
+        }
+        // Nur die Richtung zählt, die Schrittweite des Mausrads hängt vom System ab
+        let factor = ZOOM_STEP.powf(-delta.signum() as f64);
+        self.viewport =
+            self.viewport
+                .zoomed(position.0 as f64, position.1 as f64, width, height, factor);
+        true
+    }
+
+    /// Verschiebt den Ausschnitt, solange die Maustaste gedrückt ist.
+    ///
+    /// # Parameter
+    /// - `position` (`Option<(f32, f32)>`): Die Mausposition, `None` außerhalb des Fensters.
+    /// - `pressed` (`bool`): Ob die linke Maustaste gedrückt ist.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// `true`, wenn sich der Ausschnitt geändert hat.
+    pub fn drag(
+        &mut self,
+        position: Option<(f32, f32)>,
+        pressed: bool,
+        width: u32,
+        height: u32,
+    ) -> bool {
+        let (Some(position), true) = (position, pressed) else {
+            self.drag = None;
+            return false;
+        };
+        let last = self.drag.replace(position).unwrap_or(position);
+        if last == position {
+            return false;
+        }
+        self.viewport = self.viewport.panned(
+            (position.0 - last.0) as f64,
+            (position.1 - last.1) as f64,
+            width,
+            height,
+        );
+        true
+    }
+
+    /// Stellt den ursprünglichen Ausschnitt wieder her.
+    ///
+    /// # Rückgabewert
+    /// `true`, wenn sich der Ausschnitt geändert hat.
+    pub fn reset(&mut self) -> bool {
+        let changed = self.viewport != self.initial;
+        self.viewport = self.initial;
+        changed
+    }
+
+    /// Liest Mausrad, Maus und Tastatur des Fensters aus.
+    ///
+    /// # Parameter
+    /// - `window` (`&Window`): Das Vorschaufenster.
+    /// - `position` (`Option<(f32, f32)>`): Die Mausposition in Pixeln des transformierten
+    ///   Bildes, `None` außerhalb davon (siehe [`crate::compare::CompareView::locate`]).
+    /// - `pressed` (`bool`): Ob die linke Maustaste für den Ausschnitt gedrückt ist.
+    /// - `width` (`u32`): Die Breite des Bildes.
+    /// - `height` (`u32`): Die Höhe des Bildes.
+    ///
+    /// # Rückgabewert
+    /// `true`, wenn sich der Ausschnitt geändert hat und eine neue Tabelle benötigt wird.
+    pub fn poll(
+        &mut self,
+        window: &Window,
+        position: Option<(f32, f32)>,
+        pressed: bool,
+        width: u32,
+        height: u32,
+    ) -> bool {
+        let mut changed = self.drag(position, pressed, width, height);
+        if let (Some(position), Some((_, delta))) = (position, window.get_scroll_wheel()) {
+            changed |= self.scroll(position, delta, width, height);
+        }
+        if window.is_key_pressed(Key::Home, KeyRepeat::No) {
+            changed |= self.reset();
+        }
+        changed
+    }
+}
+
+/// Liest Zeilen in einem eigenen Thread, damit die Vorschau nicht auf Eingaben wartet.
+///
+/// Der Thread wird nicht eingesammelt, da das Lesen von der Standardeingabe nicht
+/// abgebrochen werden kann; er endet mit dem Ende der Eingabe.
+///
+/// # Parameter
+/// - `input` (`impl BufRead + Send + 'static`): Die Eingabe, z. B. die Standardeingabe.
+///
+/// # Rückgabewert
+/// Die gelesenen, nicht leeren Zeilen ohne umgebende Leerzeichen.
+pub fn read_lines(input: impl BufRead + Send + 'static) -> Receiver<String> {
+    let (sender, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = line.trim();
+            if !line.is_empty() && sender.send(line.to_string()).is_err() {
+                break;
+            }
+        }
+    });
+    lines
+}
+
+/// Konsole, über die die Funktion der Vorschau während der Aufnahme geändert wird.
+///
+/// Jede eingegebene Zeile wird vollständig als [`Expr`] geparst. Gültige Funktionen ersetzen die
+/// bisherige, ungültige werden als Fehlermeldung festgehalten, bis eine gültige folgt.
+///
+/// # Felder
+/// - `lines` (`Receiver<String>`): Die eingegebenen Zeilen.
+/// - `error` (`Option<String>`): Die Fehlermeldung der letzten ungültigen Eingabe.
+pub struct FunctionConsole {
+    lines: Receiver<String>,
+    pub error: Option<String>,
+}
+
+impl FunctionConsole {
+    /// Erstellt eine Konsole für bereits gelesene Zeilen, siehe [`read_lines`].
+    pub fn new(lines: Receiver<String>) -> Self {
+        FunctionConsole { lines, error: None }
+    }
+
+    /// Öffnet die Konsole auf der Standardeingabe und gibt die Eingabeaufforderung aus.
+    pub fn stdin() -> Self {
+        println!("Enter a new function to apply it to the preview");
+        prompt();
+        Self::new(read_lines(std::io::BufReader::new(std::io::stdin())))
+    }
+
+    /// Verarbeitet alle seit dem letzten Aufruf eingegebenen Zeilen.
+    ///
+    /// # Rückgabewert
+    /// Die zuletzt eingegebene gültige Funktion oder `None`, wenn keine neue vorliegt.
+    pub fn poll(&mut self) -> Option<Expr> {
+        let mut latest = None;
+        for line in self.lines.try_iter() {
+            match parse_line(&line) {
+                Ok(expression) => {
+                    if expression.derivative().is_none() {
+                        eprintln!(
+                            "warning: {} is not holomorphic, the transformation will not be conformal",
+                            line
+                        );
+                    }
+                    latest = Some(expression);
+                    self.error = None;
+                }
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    self.error = Some(error);
+                }
+            }
+            prompt();
+        }
+        latest
+    }
+}
+
+/// Parst eine Zeile der Funktionskonsole.
+///
+/// Anders als [`Expr::parse`] muss die ganze Zeile zur Funktion gehören, damit Tippfehler wie
+/// `z^2)` nicht stillschweigend als `z^2` übernommen werden.
+///
+/// # Rückgabewert
+/// Die Funktion oder eine Fehlermeldung, die auch nicht geparste Reste nennt.
+fn parse_line(line: &str) -> Result<Expr, String> {
+    match parse_expression(line) {
+        Ok((rest, expression)) if rest.trim().is_empty() => Ok(expression),
+        Ok((rest, _)) => Err(format!(
+            "Unexpected input {:?} after the function",
+            rest.trim()
+        )),
+        Err(_) => Expr::parse(line),
+    }
+}
+
+/// Gibt die Eingabeaufforderung der Funktionskonsole aus.
+fn prompt() {
+    print!("{}", PROMPT);
+    let _ = std::io::stdout().flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn test_worker_skips_outdated_requests() {
+        let gate = Mutex::new(());
+        let builds = AtomicUsize::new(0);
+        let build = |request: &LookupRequest| {
+            builds.fetch_add(1, Ordering::SeqCst);
+            let _open = gate.lock().unwrap();
+            HolomorphicLookup::new(|z| z * request.viewport.scale, 4, 4)
+        };
+
+        std::thread::scope(|scope| {
+            let closed = gate.lock().unwrap();
+            let worker = LookupWorker::spawn(scope, &build);
+            let zoom = |scale| LookupRequest {
+                expression: None,
+                viewport: Viewport {
+                    scale,
+                    ..Viewport::default()
+                },
+            };
+            worker.request(zoom(2.0));
+            while builds.load(Ordering::SeqCst) == 0 {
+                std::thread::yield_now();
+            }
+            // Während der ersten Berechnung eintreffende Anfragen werden zusammengefasst
+            worker.request(zoom(3.0));
+            worker.request(zoom(4.0));
+            drop(closed);
+
+            let mut latest = None;
+            while latest != Some(4.0) {
+                std::thread::sleep(Duration::from_millis(1));
+                latest = worker
+                    .try_receive()
+                    .map(|(request, _)| request.viewport.scale)
+                    .or(latest);
+            }
+        });
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_shelf_keeps_latest_viewport_per_function() {
+        let request = |function: &str, scale| LookupRequest {
+            expression: Expr::parse(function).ok(),
+            viewport: Viewport {
+                scale,
+                ..Viewport::default()
+            },
+        };
+        let lookup = || Arc::new(HolomorphicLookup::new(|z| z, 4, 4));
+        let mut shelf = LookupShelf::new(2);
+        shelf.store(request("z^2", 1.0), lookup());
+        shelf.store(request("z^2", 2.0), lookup());
+        assert!(shelf.take(&request("z^2", 1.0)).is_none());
+        assert!(shelf.take(&request("z^2", 2.0)).is_some());
+        assert!(shelf.take(&request("z^2", 2.0)).is_none());
+
+        // Die älteste Funktion wird verworfen
+        shelf.store(request("z", 1.0), lookup());
+        shelf.store(request("1/z", 1.0), lookup());
+        shelf.store(request("exp(z)", 1.0), lookup());
+        assert!(shelf.take(&request("z", 1.0)).is_none());
+        assert!(shelf.take(&request("1/z", 1.0)).is_some());
+    }
+
+    #[test]
+    fn test_drag_pans_and_reset_restores() {
+        let mut control = ViewportControl::new(Viewport::default());
+        assert!(!control.drag(Some((10.0, 10.0)), true, 100, 100));
+        assert!(control.drag(Some((60.0, 10.0)), true, 100, 100));
+        assert_eq!(control.viewport.center, Complex::new(-1.0, 0.0));
+
+        // Nach dem Loslassen beginnt ein neuer Zug ohne Sprung
+        assert!(!control.drag(Some((60.0, 10.0)), false, 100, 100));
+        assert!(!control.drag(Some((0.0, 0.0)), true, 100, 100));
+
+        assert!(control.scroll((50.0, 50.0), 1.0, 100, 100));
+        assert_eq!(control.viewport.scale, 1.0 / ZOOM_STEP);
+        assert!(control.reset());
+        assert_eq!(control.viewport, Viewport::default());
+    }
+
+    #[test]
+    fn test_console_keeps_last_valid_function_and_error() {
+        let lines = read_lines(std::io::Cursor::new("z^2\n\n  1 / z  \n"));
+        let collected: Vec<String> = lines.iter().collect();
+        assert_eq!(collected, ["z^2", "1 / z"]);
+
+        let (sender, lines) = mpsc::channel();
+        let mut console = FunctionConsole::new(lines);
+        assert_eq!(console.poll(), None);
+
+        sender.send("z^2".to_string()).unwrap();
+        sender.send("*".to_string()).unwrap();
+        assert_eq!(console.poll(), Expr::parse("z^2").ok());
+        assert!(console.error.is_some());
+
+        sender.send("sin(z)".to_string()).unwrap();
+        assert_eq!(console.poll(), Expr::parse("sin(z)").ok());
+        assert_eq!(console.error, None);
+    }
+
+    #[test]
+    fn test_console_rejects_trailing_input() {
+        assert_eq!(parse_line("z^2 + 1"), Expr::parse("z^2 + 1"));
+        assert!(parse_line("z^2)").unwrap_err().contains("\")\""));
+        assert!(parse_line("z^2 foo").unwrap_err().contains("foo"));
+
+        let (sender, lines) = mpsc::channel();
+        let mut console = FunctionConsole::new(lines);
+        sender.send("z^2)".to_string()).unwrap();
+        assert_eq!(console.poll(), None);
+        assert!(console.error.is_some());
+    }
+}
@@ -3,143 +3,1280 @@
 //! in Echtzeit anzuwenden. Benutzer können entweder eine Eingabedatei oder die Webcam
 //! nutzen und eine mathematische Funktion als Transformation angeben.
 
+mod analysis;
+mod animation;
+mod batch;
+mod cache;
+mod camera;
 mod cli;
+mod compare;
 mod display;
+mod droste;
+mod fractal;
+mod headless;
 mod holo;
+mod holomorphy;
+mod hud;
+mod hyperbolic;
+mod live;
+mod metadata;
+mod morph;
+mod output;
+mod overlay;
 mod parsing;
+mod pipeline;
+mod presets;
+mod serve;
+mod tiled;
+mod video;
 mod webcam;
 
-use chrono::Local;
+use analysis::{analyze, format_table, mark_features, AnalysisSettings};
+use animation::{quantize, AnimationEncoder, AnimationFormat, ParameterSweep};
+use batch::run_batch;
+use cache::{format_entries, LookupCache, LookupKey};
+use camera::{format_cameras, list_cameras, open_camera, MAX_CAMERAS};
 use clap::Parser as ClapParser;
-use cli::Cli;
-use display::display_image;
-use holo::{process_frame, HolomorphicLookup};
-use image::RgbImage;
-use minifb::{Key, Window, WindowOptions};
+use cli::{CacheAction, Cli, Command, SourceArgs};
+use compare::{CompareView, Pane, ViewMode};
+use display::{display_buffer, display_image};
+use droste::{mask_from_image, DrosteParams};
+use fractal::EscapeTime;
+use headless::{run_headless_outputs, SyntheticSource};
+use holo::{HolomorphicLookup, Viewport};
+use holomorphy::{check, heatmap, RESIDUAL_TOLERANCE};
+use hud::{Hud, Stage};
+use hyperbolic::HyperbolicTiling;
+use image::{imageops, DynamicImage, GenericImageView, RgbImage};
+use live::{FunctionConsole, LookupRequest, LookupShelf, LookupWorker, ViewportControl};
+use metadata::{read_metadata, RenderMetadata, SOFTWARE};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use morph::Morph;
+use num_complex::Complex;
+use opencv::core::Mat;
+use opencv::prelude::*;
 use opencv::videoio::{
-    VideoCapture, VideoCaptureTrait, CAP_ANY, CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH,
+    VideoCapture, CAP_ANY, CAP_FFMPEG, CAP_PROP_FRAME_HEIGHT, CAP_PROP_FRAME_WIDTH,
 };
+use output::{
+    create_parent_dir, expand_template, is_stdio, open_image, save_image, unused_path, ImageOutput,
+};
+use overlay::{draw_panel, panel_size, text_scale, ERROR_COLOR};
 use parsing::Expr;
+use pipeline::{FramePixels, FrameSource, Pipeline};
+use presets::{load_presets, PRESET_KEYS};
+use serve::{is_stream_url, MjpegServer, SNAPSHOT_PATH, STREAM_PATH};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
-use webcam::capture_frame;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tiled::{render_file, TiledRenderer};
+use video::{set_bitrate, source_fps, transform_video, Recording, DEFAULT_FPS};
+use webcam::{bgr_bytes, capture_frame, mat_to_rgb_image, rgb_image_to_mat, StreamReconnect};
+
+/// Beschriftung, die während einer Aufzeichnung im Vorschaufenster erscheint.
+const REC_LABEL: &str = "REC";
+
+/// Höchste Wartezeit auf ein Frame, bevor das Fenster trotzdem Eingaben verarbeitet.
+const FRAME_WAIT: Duration = Duration::from_millis(5);
 
-/// Speichert ein transformiertes Bild im JPEG-Format in einem definierten Verzeichnis.
+/// Speichert ein transformiertes Bild unter dem Pfad aus den Ausgabeeinstellungen.
 ///
-/// Diese Funktion generiert einen Dateinamen basierend auf dem ursprünglichen Bildnamen,
-/// der angegebenen Transformationsfunktion und einem Zeitstempel. Der resultierende Dateiname
-/// wird für eine sichere Speicherung angepasst.
+/// Der Pfad ergibt sich aus der Vorlage in `output`, in die der ursprüngliche Bildname,
+/// die Transformationsfunktion und ein Zeitstempel eingesetzt werden. Das Format folgt
+/// aus der Dateiendung, fehlende Verzeichnisse werden angelegt.
 ///
 /// # Parameter
+/// - `output` (`&ImageOutput`): Ausgabepfad bzw. Namensvorlage und JPEG-Qualität.
 /// - `image_path` (`&str`): Der Pfad zur ursprünglichen Bilddatei.
 /// - `function_str` (`&str`): Die holomorphe Transformationsfunktion als String.
-/// - `transformed_img` (`RgbImage`): Das transformierte Bild.
+/// - `metadata` (`&RenderMetadata`): Beschreibung der Berechnung, die in PNG und JPEG
+///   eingebettet wird.
+/// - `transformed_img` (`DynamicImage`): Das transformierte Bild.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Format unbekannt ist oder das Speichern fehlschlägt.
+fn save_transformed_image(
+    output: &ImageOutput,
+    image_path: &str,
+    function_str: &str,
+    metadata: &RenderMetadata,
+    transformed_img: DynamicImage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = output.save(&transformed_img, image_path, function_str, metadata)?;
+    if !output.is_stdout() {
+        println!("Image saved as: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Speichert das aktuelle Frame des Vorschaufensters, transformiert und unverändert.
+///
+/// Beide Dateinamen folgen der Vorlage aus `output` wie bei [`save_transformed_image`]; das
+/// unveränderte Frame erhält `raw` anstelle der Funktion und keine Metadaten.
+///
+/// # Parameter
+/// - `output` (`&ImageOutput`): Namensvorlage und JPEG-Qualität.
+/// - `source_name` (`&str`): Die Videodatei bzw. `webcam`.
+/// - `function_str` (`&str`): Die aktuelle Transformation als String.
+/// - `metadata` (`&RenderMetadata`): Beschreibung der aktuellen Berechnung.
+/// - `raw` (`&RgbImage`): Das Frame der Quelle.
+/// - `transformed` (`&RgbImage`): Das transformierte Frame ohne Overlays.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn eines der Bilder nicht gespeichert werden kann.
+fn save_snapshot(
+    output: &ImageOutput,
+    source_name: &str,
+    function_str: &str,
+    metadata: &RenderMetadata,
+    raw: &RgbImage,
+    transformed: &RgbImage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_transformed_image(
+        output,
+        source_name,
+        function_str,
+        metadata,
+        transformed.clone().into(),
+    )?;
+    let (path, _) = output.target(source_name, "raw")?;
+    save_image(&raw.clone().into(), &path, output.quality, None)?;
+    println!("Image saved as: {}", path.display());
+    Ok(())
+}
+
+/// Gibt den Stream für Statusmeldungen zurück.
+///
+/// Wird das Bild in die Standardausgabe geschrieben, landen Meldungen auf der
+/// Standardfehlerausgabe, damit sie die Bilddaten in einer Pipeline nicht verfälschen.
+///
+/// # Parameter
+/// - `to_stdout` (`bool`): Ob das Bild in die Standardausgabe geschrieben wird.
+fn status_stream(to_stdout: bool) -> Box<dyn Write> {
+    if to_stdout {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+/// Wendet eine Lookup-Tabelle auf eine Bilddatei, eine Videodatei oder die Webcam an.
+///
+/// Ist in `source` eine Bilddatei angegeben, wird diese transformiert und gespeichert.
+/// Eine Videodatei wird mit `--output` Frame für Frame in eine neue Videodatei geschrieben,
+/// ansonsten in einem Fenster abgespielt; Stream-URLs werden über FFmpeg geöffnet. Ohne Bild- oder Videodatei wird die Webcam
+/// (bzw. die mit `--camera` gewählte Kamera) geöffnet und das Ergebnis in einem Fenster
+/// angezeigt (und mit `--output` aufgezeichnet, siehe [`run_window`]). Liefert die Kamera
+/// eine andere Größe als angefordert, wird die Lookup-Tabelle für diese Größe berechnet.
+/// `--synthetic` ersetzt die Kamera durch ein Testbild oder eine Bilddatei, `--headless`
+/// verarbeitet die Frames ohne Fenster (siehe [`run_live`]).
+/// Im Fenster lässt sich der Ausschnitt mit der Maus verschieben und zoomen, eine Funktion in
+/// `request` kann über die Standardeingabe geändert werden.
+///
+/// # Parameter
+/// - `source` (`&SourceArgs`): Die Angaben zur Bildquelle.
+/// - `label` (`&str`): Beschreibung der Transformation, die in den Dateinamen übernommen wird.
+/// - `metadata` (`RenderMetadata`): Beschreibung der Berechnung ohne Quellbild, wird beim
+///   Speichern eines Bildes um dessen Pfad ergänzt.
+/// - `request` (`LookupRequest`): Die anfängliche Funktion und der Ausschnitt.
+/// - `build_lookup` (`impl Fn(u32, u32, &LookupRequest) -> HolomorphicLookup + Sync`): Erzeugt
+///   die Lookup-Tabelle für die gegebene Breite, Höhe und die Einstellungen.
+///
+/// # Fehler
+/// - Kann Fehler ausgeben, wenn die Webcam oder das Video nicht verfügbar ist oder die
+///   Transformation fehlschlägt.
+fn run_source(
+    source: &SourceArgs,
+    label: &str,
+    metadata: RenderMetadata,
+    request: LookupRequest,
+    build_lookup: impl Fn(u32, u32, &LookupRequest) -> HolomorphicLookup + Sync,
+) -> Result<(), Box<dyn std::error::Error>> {
+    source.validate()?;
+    if let Some(file_path) = &source.image {
+        // Lade das Bild
+        // Pixeltyp (Alphakanal, 16 Bit, Gleitkomma) des Bildes beibehalten
+        let img = open_image(file_path)?;
+
+        let (width, height) = img.dimensions();
+        let lookup = build_lookup(width, height, &request);
+
+        let mut transformed_img = lookup
+            .apply_dynamic(&img)
+            .ok_or("transforming image unsuccessful")?;
+        // Vergleichsansichten werden in 8 Bit RGB zusammengesetzt
+        if source.view != ViewMode::Transformed {
+            let view = CompareView::new(source.view);
+            transformed_img = view
+                .compose(&img.to_rgb8(), transformed_img.into_rgb8())
+                .into();
+        }
+        return save_transformed_image(
+            &source.image_output(),
+            file_path,
+            label,
+            &metadata.with_source(file_path),
+            transformed_img,
+        );
+    }
+
+    // Künstliche Quellen ersetzen die Kamera, z. B. für Tests ohne Hardware
+    if let Some(spec) = &source.synthetic {
+        let size = (source.resolution.is_some() || source.dimensions.is_some())
+            .then(|| source.target_dimensions());
+        let synthetic = SyntheticSource::open(spec, size)?;
+        let (width, height) = synthetic.dimensions();
+        let lookup = build_lookup(width, height, &request);
+        return run_live(
+            synthetic.into_frames(DEFAULT_FPS, true, spec),
+            lookup,
+            request,
+            |request| build_lookup(width, height, request),
+            source,
+            label,
+            &metadata,
+        );
+    }
+
+    let (mut cap, width, height) = match &source.video {
+        Some(video_path) => {
+            // Netzwerkstreams über FFmpeg öffnen, Dateien mit dem passenden Backend
+            let api = if is_stream_url(video_path) {
+                CAP_FFMPEG
+            } else {
+                CAP_ANY
+            };
+            let cap = VideoCapture::from_file(video_path, api)?;
+            if !cap.is_opened()? {
+                return Err(format!("failed to open video '{}'", video_path).into());
+            }
+            // Videos behalten ihre Auflösung, sofern keine andere angegeben ist
+            let (width, height) = if source.resolution.is_some() || source.dimensions.is_some() {
+                source.target_dimensions()
+            } else {
+                (
+                    cap.get(CAP_PROP_FRAME_WIDTH)? as u32,
+                    cap.get(CAP_PROP_FRAME_HEIGHT)? as u32,
+                )
+            };
+            (cap, width, height)
+        }
+        None => {
+            // Bestimme Breite und Höhe basierend auf Auflösung oder Dimensionen
+            let settings = source.capture_settings();
+            let camera = source.camera.clone().unwrap_or_default();
+            let (cap, info) = open_camera(&camera, &settings)?;
+            // Die Tabelle wird für die tatsächlich gelieferte Größe berechnet
+            if (info.width, info.height) != (settings.width, settings.height) {
+                eprintln!(
+                    "warning: camera {} delivers {}x{} instead of {}x{}",
+                    camera, info.width, info.height, settings.width, settings.height
+                );
+            }
+            (cap, info.width, info.height)
+        }
+    };
+
+    let lookup = build_lookup(width, height, &request);
+
+    // Videos mit Ausgabe werden ohne Fenster so schnell wie möglich und ohne verworfene
+    // Frames verarbeitet; bei Dateien kürzen `--frames` und `--duration` das Video
+    if let (Some(video), Some(output)) = (&source.video, source.video_output()) {
+        let limit = source.frame_limit();
+        if source.serve.is_none() && (!limit.is_limited() || !is_stream_url(video)) {
+            let fps = source_fps(&cap);
+            let mut writer = output.open(fps, width, height)?;
+            let frames = transform_video(&mut cap, &lookup, &mut writer, limit.max_frames(fps))?;
+            println!("Video saved as: {} ({} frames)", output.path, frames);
+            return Ok(());
+        }
+    }
+
+    // Das Ende einer Videodatei beendet die Pipeline, fehlende Frames von Webcam oder
+    // Stream werden übersprungen, getrennte Streams neu verbunden
+    let is_file = source
+        .video
+        .as_deref()
+        .is_some_and(|video| !is_stream_url(video));
+    let mut reconnect = source
+        .video
+        .as_deref()
+        .filter(|video| is_stream_url(video))
+        .map(StreamReconnect::new);
+    let fps = source_fps(&cap);
+    let frames = FrameSource {
+        capture: move || match reconnect.as_mut() {
+            Some(reconnect) => reconnect.read(&mut cap),
+            None => {
+                let frame = capture_frame(&mut cap);
+                (frame.is_some() || !is_file).then_some(frame)
+            }
+        },
+        convert: |frame: &Option<Mat>| mat_to_rgb_image(frame.as_ref()?),
+        bgr: Some(|frame: &Option<Mat>| bgr_bytes(frame.as_ref()?)),
+        fps,
+        // Videodateien in ihrer eigenen Bildrate abspielen, Webcam und Streams geben den Takt vor
+        frame_time: is_file.then(|| Duration::from_secs_f64(1.0 / fps)),
+        name: source.video.clone().unwrap_or_else(|| "webcam".to_string()),
+    };
+    run_live(
+        frames,
+        lookup,
+        request,
+        |request| build_lookup(width, height, request),
+        source,
+        label,
+        &metadata,
+    )
+}
+
+/// Zeigt eine Live-Quelle im Fenster an oder verarbeitet sie mit `--headless` ohne Fenster.
+///
+/// # Parameter
+/// - `frames` (`FrameSource<T, C, V>`): Die Bildquelle.
+/// - `lookup` (`HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle für `request`.
+/// - `request` (`LookupRequest`): Die anfängliche Funktion und der Ausschnitt.
+/// - `build_lookup` (`impl Fn(&LookupRequest) -> HolomorphicLookup + Sync`): Berechnet die
+///   Lookup-Tabelle für neue Einstellungen.
+/// - `source` (`&SourceArgs`): Die Angaben zur Bildquelle.
+/// - `label` (`&str`): Beschreibung der Transformation.
+/// - `metadata` (`&RenderMetadata`): Beschreibung der Berechnung für gespeicherte Frames.
+///
+/// # Fehler
+/// Siehe [`run_window`] und [`run_without_window`].
+fn run_live<T, C, V>(
+    frames: FrameSource<T, C, V>,
+    lookup: HolomorphicLookup,
+    request: LookupRequest,
+    build_lookup: impl Fn(&LookupRequest) -> HolomorphicLookup + Sync,
+    source: &SourceArgs,
+    label: &str,
+    metadata: &RenderMetadata,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Send,
+    C: FnMut() -> Option<T> + Send,
+    V: Fn(&T) -> Option<RgbImage> + Send,
+{
+    if source.headless {
+        run_without_window(frames, lookup, source, label)
+    } else {
+        run_window(
+            frames,
+            lookup,
+            request,
+            build_lookup,
+            source,
+            label,
+            metadata,
+        )
+    }
+}
+
+/// Startet mit `--serve` den MJPEG-Server für die transformierten Frames.
 ///
 /// # Fehler
-/// Gibt eine Fehlermeldung aus, wenn das Speichern des Bildes fehlschlägt.
-fn save_transformed_image(image_path: &str, function_str: &str, transformed_img: RgbImage) {
-    // Extrahiere den Dateinamen aus dem Pfad
-    let input_filename = Path::new(image_path)
-        .file_stem()
-        .expect("Failed to extract file name")
-        .to_str()
-        .expect("Failed to convert to string");
-
-    let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
-
-    // Ersetze "/" durch "div" für einen sicheren Dateinamen
-    let sanitized_function_str: String = function_str
-        .replace("/", "div")
-        .chars()
-        .filter(|c| !c.is_whitespace())
-        .collect();
-
-    // Generiere den Dateinamen
-    let output_filename = format!(
-        "./images/output/{}_{}_{}.jpeg",
-        input_filename, sanitized_function_str, timestamp
+/// Gibt einen Fehler zurück, wenn die Adresse nicht gebunden werden kann.
+fn start_server(source: &SourceArgs) -> Result<Option<MjpegServer>, Box<dyn std::error::Error>> {
+    let Some(addr) = &source.serve else {
+        return Ok(None);
+    };
+    let server = MjpegServer::bind(addr.as_str(), source.quality)
+        .map_err(|error| format!("failed to serve at '{}': {}", addr, error))?;
+    println!(
+        "Serving MJPEG stream at http://{}{} (snapshot: {})",
+        server.addr, STREAM_PATH, SNAPSHOT_PATH
     );
+    Ok(Some(server))
+}
+
+/// Verarbeitet eine Live-Quelle ohne Fenster, z. B. auf Servern oder in CI.
+///
+/// Die transformierten Frames werden mit `--output` aufgezeichnet und mit `--serve`
+/// ausgeliefert; `--frames` und `--duration` begrenzen den Lauf (siehe
+/// [`run_headless_outputs`]).
+///
+/// # Parameter
+/// - `frames` (`FrameSource<T, C, V>`): Die Bildquelle.
+/// - `lookup` (`HolomorphicLookup`): Die Lookup-Tabelle.
+/// - `source` (`&SourceArgs`): Die Angaben zur Bildquelle mit Ausgabe und Grenzen.
+/// - `label` (`&str`): Beschreibung der Transformation für Dateinamen.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Ausgabe nicht geöffnet oder geschrieben werden kann.
+fn run_without_window<T, C, V>(
+    frames: FrameSource<T, C, V>,
+    lookup: HolomorphicLookup,
+    source: &SourceArgs,
+    label: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Send,
+    C: FnMut() -> Option<T> + Send,
+    V: Fn(&T) -> Option<RgbImage> + Send,
+{
+    let (width, height) = (lookup.width, lookup.height);
+    let mut recording = match source.output {
+        Some(_) => Some(start_recording(
+            source,
+            &frames.name,
+            label,
+            frames.fps,
+            width,
+            height,
+            true,
+        )?),
+        None => None,
+    };
+    let server = start_server(source)?;
+    let summary = run_headless_outputs(
+        frames,
+        Arc::new(lookup),
+        &source.frame_limit(),
+        recording.as_mut(),
+        server.as_ref(),
+    );
+    if let Some(recording) = recording {
+        finish_recording(recording)?;
+    }
+    let summary = summary?;
+    println!(
+        "Processed {} frames in {:.1} s ({:.1} fps, {} dropped)",
+        summary.frames,
+        summary.elapsed.as_secs_f64(),
+        summary.fps(),
+        summary.dropped
+    );
+    Ok(())
+}
 
-    // Speichere das Bild
-    transformed_img
-        .save(&output_filename)
-        .expect("Failed to save image");
+/// Beginnt eine Aufzeichnung aus dem Vorschaufenster.
+///
+/// # Parameter
+/// - `source` (`&SourceArgs`): Die Angaben zur Bildquelle mit Pfadvorlage und Codec.
+/// - `source_name` (`&str`): Die Videodatei bzw. `webcam` für `{stem}`.
+/// - `function_str` (`&str`): Die aktuelle Transformation für `{expr}`.
+/// - `fps` (`f64`): Die Bildrate der Quelle.
+/// - `width` (`u32`): Die Breite der Frames.
+/// - `height` (`u32`): Die Höhe der Frames.
+/// - `overwrite` (`bool`): Eine vorhandene Datei ersetzen; sonst erhält der Dateiname einen
+///   Zähler, siehe [`unused_path`]. Mit `R` gestartete Aufzeichnungen ersetzen nie eine Datei,
+///   auch nicht die vorige Aufzeichnung derselben Sitzung.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Datei nicht geöffnet werden kann.
+fn start_recording(
+    source: &SourceArgs,
+    source_name: &str,
+    function_str: &str,
+    fps: f64,
+    width: u32,
+    height: u32,
+    overwrite: bool,
+) -> Result<Recording, Box<dyn std::error::Error>> {
+    let mut output = source.recording_output();
+    let path = expand_template(&output.path, source_name, function_str);
+    let path = if overwrite { path } else { unused_path(&path) };
+    output.path = path.to_string_lossy().into_owned();
+    let recording = Recording::start(&output, fps, width, height)?;
+    println!("Recording to: {}", recording.path);
+    Ok(recording)
+}
 
-    println!("Image saved as: {}", output_filename);
+/// Schließt eine Aufzeichnung ab und meldet die Datei.
+fn finish_recording(recording: Recording) -> Result<(), Box<dyn std::error::Error>> {
+    let (path, frames) = (recording.path.clone(), recording.frames);
+    recording.finish()?;
+    println!("Video saved as: {} ({} frames)", path, frames);
+    Ok(())
+}
+
+/// Zeigt die transformierten Frames einer Videoquelle in einem Fenster an.
+///
+/// Erfassung und Transformation laufen in einer [`Pipeline`] neben der Anzeige; kommt die
+/// Anzeige nicht nach, werden Frames verworfen.
+///
+/// Mausrad und Ziehen mit der linken Maustaste verändern den Ausschnitt (siehe
+/// [`ViewportControl`]). Enthält `request` eine Funktion, kann sie über die Standardeingabe
+/// ersetzt werden (siehe [`FunctionConsole`]); Parse-Fehler erscheinen im Fenster. Die
+/// Lookup-Tabelle wird im Hintergrund berechnet und ausgetauscht, sobald sie fertig ist;
+/// bis dahin läuft die Anzeige unverändert weiter. `V` wechselt die Vergleichsansicht
+/// (siehe [`CompareView`]), `P` schaltet die Pixelsonde unter dem Mauszeiger um.
+///
+/// Die Zifferntasten wählen die Voreinstellungen aus `--presets` (siehe [`presets`]); bereits
+/// berechnete Tabellen werden dabei wiederverwendet. `S` speichert das aktuelle Frame
+/// transformiert und unverändert, `R` startet bzw. beendet eine Aufzeichnung (mit `--output`
+/// läuft sie von Beginn an). Mit `--serve` werden die transformierten Frames zusätzlich als
+/// MJPEG-Stream ausgeliefert (siehe [`MjpegServer`]).
+///
+/// # Parameter
+/// - `frames` (`FrameSource<T, C, V>`): Die Webcam, Videodatei, der Stream oder eine künstliche
+///   Quelle; Videodateien werden in ihrer eigenen Bildrate abgespielt.
+/// - `lookup` (`HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle für `request`.
+/// - `request` (`LookupRequest`): Die anfängliche Funktion und der Ausschnitt.
+/// - `build_lookup` (`impl Fn(&LookupRequest) -> HolomorphicLookup + Sync`): Berechnet die
+///   Lookup-Tabelle für neue Einstellungen.
+/// - `source` (`&SourceArgs`): Die Angaben zur Bildquelle.
+/// - `label` (`&str`): Beschreibung der Transformation für Head-up-Display und Dateinamen.
+/// - `metadata` (`&RenderMetadata`): Beschreibung der Berechnung für gespeicherte Frames.
+///
+/// # Fehler
+/// - Gibt einen Fehler zurück, wenn die Voreinstellungen ungültig sind oder ein Frame nicht
+///   aufgezeichnet werden kann.
+fn run_window<T, C, V>(
+    frames: FrameSource<T, C, V>,
+    lookup: HolomorphicLookup,
+    mut request: LookupRequest,
+    build_lookup: impl Fn(&LookupRequest) -> HolomorphicLookup + Sync,
+    source: &SourceArgs,
+    label: &str,
+    metadata: &RenderMetadata,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Send,
+    C: FnMut() -> Option<T> + Send,
+    V: Fn(&T) -> Option<RgbImage> + Send,
+{
+    let (width, height) = (lookup.width, lookup.height);
+    let mut lookup = Arc::new(lookup);
+    let (fps, source_name) = (frames.fps, frames.name.as_str());
+    // Voreinstellungen gibt es nur für Modi mit bearbeitbarer Funktion
+    let presets = match request.expression {
+        Some(_) => load_presets(source.presets.as_deref().map(Path::new))?,
+        None => Vec::new(),
+    };
+    let initial_expression = request.expression.clone();
+    let mut function_label = label.to_string();
+    let snapshots = ImageOutput::new(None, source.quality, source.format.output_format);
+    let mut recording = match source.output {
+        Some(_) => Some(start_recording(
+            source,
+            source_name,
+            label,
+            fps,
+            width,
+            height,
+            true,
+        )?),
+        None => None,
+    };
+    let server = start_server(source)?;
+    let mut hud = Hud::new(label, source.hud);
+    let mut view = CompareView::new(source.view);
+    // Erstelle ein Anzeigefenster, spätere Ansichten anderer Größe werden skaliert
+    let (window_width, window_height) = view.size(width, height);
+    let mut window = Window::new(
+        "Holomorphic Webcam",
+        window_width as usize,
+        window_height as usize,
+        WindowOptions::default(),
+    )
+    .expect("Failed to create window");
+    let mut control = ViewportControl::new(request.viewport);
+    let mut console = request.expression.is_some().then(FunctionConsole::stdin);
+    let scale = text_scale(width);
+    // Die angezeigte Tabelle gehört zu `built`, abgelöste Tabellen wandern in den Vorrat
+    let mut built = request.clone();
+    let mut shelf = LookupShelf::new(presets.len() + 1);
+    // Schnappschüsse warten auf das nächste Frame mit Quellbild
+    let mut snapshot_requested = false;
+
+    let result = std::thread::scope(|scope| {
+        let worker = LookupWorker::spawn(scope, &build_lookup);
+        let mut pipeline = Pipeline::spawn(
+            scope,
+            frames.capture,
+            frames.convert,
+            frames.bgr,
+            Arc::clone(&lookup),
+            frames.frame_time,
+        );
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            if window.is_key_pressed(Key::H, KeyRepeat::No) {
+                hud.visible = !hud.visible;
+            }
+            if window.is_key_pressed(Key::V, KeyRepeat::No) {
+                view.mode = view.mode.next();
+            }
+            if window.is_key_pressed(Key::P, KeyRepeat::No) {
+                view.probe = !view.probe;
+            }
+            // Mausposition vom Fenster auf die aktuelle Ansicht umrechnen
+            let (view_width, view_height) = view.size(width, height);
+            let (current_width, current_height) = window.get_size();
+            let mouse = window.get_mouse_pos(MouseMode::Pass).map(|(x, y)| {
+                (
+                    x * view_width as f32 / current_width.max(1) as f32,
+                    y * view_height as f32 / current_height.max(1) as f32,
+                )
+            });
+            let pressed = window.get_mouse_down(MouseButton::Left);
+            let pointer = mouse
+                .and_then(|(x, y)| view.locate(x, y, width, height))
+                .filter(|&(pane, _, _)| pane == Pane::Transformed)
+                .map(|(_, x, y)| (x, y));
+            let dragging = view.drag_divider(mouse, pressed, width);
+            let mut changed = control.poll(&window, pointer, pressed && !dragging, width, height);
+            if let Some(expression) = console.as_mut().and_then(FunctionConsole::poll) {
+                request.expression = Some(expression);
+                changed = true;
+            }
+            let selected = PRESET_KEYS[..presets.len()]
+                .iter()
+                .position(|&key| window.is_key_pressed(key, KeyRepeat::No));
+            if let Some(index) = selected {
+                let preset = &presets[index];
+                println!("Preset {}: {}", index + 1, preset.name);
+                request.expression = Some(preset.expression.clone());
+                changed = true;
+            }
+            if changed {
+                request.viewport = control.viewport;
+                if let Some(expression) = &request.expression {
+                    function_label = expression.to_string();
+                }
+                // Bereits berechnete Tabellen sofort übernehmen
+                match shelf.take(&request) {
+                    Some(stored) => {
+                        let previous = std::mem::replace(&mut lookup, stored);
+                        shelf.store(std::mem::replace(&mut built, request.clone()), previous);
+                        pipeline.set_lookup(Arc::clone(&lookup));
+                    }
+                    None => worker.request(request.clone()),
+                }
+            }
+            if let Some((finished, rebuilt)) = worker.try_receive() {
+                // Zwischenzeitlich überholte Tabellen nur ablegen
+                if finished == request {
+                    let previous = std::mem::replace(&mut lookup, Arc::new(rebuilt));
+                    shelf.store(std::mem::replace(&mut built, finished), previous);
+                    pipeline.set_lookup(Arc::clone(&lookup));
+                } else {
+                    shelf.store(finished, Arc::new(rebuilt));
+                }
+            }
+            if window.is_key_pressed(Key::R, KeyRepeat::No) {
+                let result = match recording.take() {
+                    Some(finished) => finish_recording(finished),
+                    None => start_recording(
+                        source,
+                        source_name,
+                        &function_label,
+                        fps,
+                        width,
+                        height,
+                        false,
+                    )
+                    .map(|started| recording = Some(started)),
+                };
+                if let Err(error) = result {
+                    eprintln!("error: {}", error);
+                }
+            }
+            if window.is_key_pressed(Key::S, KeyRepeat::No) {
+                snapshot_requested = true;
+            }
+            // Ohne Vergleich, Overlays und Ausgaben direkt in den Fensterpuffer transformieren
+            let console_error = console.as_ref().and_then(|c| c.error.as_deref());
+            pipeline.set_direct(
+                view.mode == ViewMode::Transformed
+                    && !hud.visible
+                    && !view.probe
+                    && console_error.is_none()
+                    && recording.is_none()
+                    && server.is_none()
+                    && !snapshot_requested,
+            );
+            // Neuestes transformiertes Frame abholen
+            let Some(frame) = pipeline.latest(FRAME_WAIT) else {
+                if pipeline.is_finished() {
+                    break; // Ende der Videodatei
+                }
+                // Eingaben trotzdem verarbeiten, damit das Fenster bedienbar bleibt
+                window.update();
+                continue;
+            };
+            hud.times.start_frame(Instant::now());
+            hud.times.record(Stage::Capture, frame.capture);
+            hud.times.record(Stage::Convert, frame.convert);
+            hud.times.record(Stage::Apply, frame.apply);
+            let stats = pipeline.stats();
+            (hud.times.latency, hud.times.dropped) = (stats.latency, stats.dropped);
+            // Auch Frames aufzeichnen, die vor dem Umschalten noch direkt berechnet wurden
+            if recording.is_some() || server.is_some() {
+                let transformed = frame.transformed();
+                if let Some(recording) = recording.as_mut() {
+                    recording.write(&transformed)?;
+                }
+                if let Some(server) = &server {
+                    server.publish(&transformed);
+                }
+            }
+            let (img, transformed_image) = match frame.pixels {
+                FramePixels::Images {
+                    source,
+                    transformed,
+                } => (source, transformed),
+                FramePixels::Buffer(buffer) => {
+                    let stage = Instant::now();
+                    display_buffer(&mut window, &buffer, width, height);
+                    hud.times.record(Stage::Display, stage.elapsed());
+                    continue;
+                }
+            };
+            if std::mem::take(&mut snapshot_requested) {
+                // Die Metadaten beschreiben die Tabelle des gezeigten Frames, nicht die angeforderte
+                let mut metadata = metadata
+                    .clone()
+                    .with_source(source_name)
+                    .with_viewport(&built.viewport);
+                if built.expression != initial_expression {
+                    metadata.expression = built.expression.as_ref().map(ToString::to_string);
+                }
+                let saved = save_snapshot(
+                    &snapshots,
+                    source_name,
+                    &function_label,
+                    &metadata,
+                    &img,
+                    &transformed_image,
+                );
+                if let Err(error) = saved {
+                    eprintln!("error: {}", error);
+                }
+            }
+            // Vergleichsansicht und Overlays nur anzeigen, nicht aufzeichnen
+            let mut shown = view.compose(&img, transformed_image);
+            // Angezeigt wird, wofür die Tabelle berechnet wurde, bis der Worker nachzieht
+            hud.draw(&mut shown, &built);
+            if let (true, Some((x, y))) = (view.probe, pointer) {
+                view.draw_probe(&mut shown, &frame.lookup, x as u32, y as u32);
+            }
+            if let Some(error) = console.as_ref().and_then(|c| c.error.as_deref()) {
+                let (_, panel_height) = panel_size(error, scale);
+                let y = height.saturating_sub(panel_height);
+                draw_panel(&mut shown, 0, y, error, ERROR_COLOR, scale);
+            }
+            if recording.is_some() {
+                let (panel_width, panel_height) = panel_size(REC_LABEL, scale);
+                let (x, y) = (
+                    shown.width().saturating_sub(panel_width),
+                    height.saturating_sub(panel_height),
+                );
+                draw_panel(&mut shown, x, y, REC_LABEL, ERROR_COLOR, scale);
+            }
+            // Transformiertes Bild anzeigen
+            let stage = Instant::now();
+            display_image(&mut window, &shown);
+            hud.times.record(Stage::Display, stage.elapsed());
+        }
+        Ok::<(), Box<dyn std::error::Error>>(())
+    });
+    if let Some(recording) = recording {
+        finish_recording(recording)?;
+    }
+    result
 }
 
 /// Der Haupteinstiegspunkt des Programms.
 ///
-/// Parst die Kommandozeilenargumente, lädt ein Bild oder öffnet die Webcam, wendet
-/// die angegebene holomorphe Funktion an und zeigt das Ergebnis an oder speichert es.
+/// Parst die Kommandozeilenargumente und führt sie mit [`run`] aus.
 ///
 /// # Rückgabewert
 /// Gibt `Ok(())` zurück, wenn das Programm erfolgreich ausgeführt wurde,
 /// oder einen Fehler, falls etwas schiefgeht.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Cli::parse();
+    // Vor allen weiteren Threads setzen, siehe `set_bitrate`
+    set_bitrate(args.bitrate());
+    run(args)
+}
+
+/// Führt die geparsten Kommandozeilenargumente aus.
+///
+/// Lädt ein Bild oder öffnet die Webcam, wendet die angegebene holomorphe Funktion bzw.
+/// den gewählten Modus an und zeigt das Ergebnis an oder speichert es. `reproduce` ruft
+/// diese Funktion mit der aus den Metadaten wiederhergestellten Kommandozeile erneut auf.
+///
+/// # Parameter
+/// - `args` (`Cli`): Die Kommandozeilenargumente.
 ///
 /// # Fehler
 /// - Gibt Fehler zurück, wenn die Eingabedaten ungültig sind.
 /// - Kann Fehler ausgeben, wenn die Webcam nicht verfügbar ist oder die Transformation fehlschlägt.
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Cli::parse();
+fn run(args: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    match args.command {
+        Some(Command::Droste(droste_args)) => {
+            let params = DrosteParams {
+                inner_radius: droste_args.inner,
+                outer_radius: droste_args.outer,
+                twist: droste_args.twist,
+                max_levels: droste_args.levels,
+            };
+            params.validate()?;
+
+            // Maske aus eigener Datei oder aus dem Alphakanal des Eingabebildes
+            let mask = match (&droste_args.mask, &droste_args.source.image) {
+                (Some(mask_path), _) => Some(mask_from_image(&open_image(mask_path)?)),
+                (None, Some(image_path)) => {
+                    let img = open_image(image_path)?;
+                    img.color().has_alpha().then(|| mask_from_image(&img))
+                }
+                (None, None) => None,
+            };
 
-    let input = &args.function;
-    let expression = Expr::parse(input)?;
-    let holomorphic_fn = expression.get_closure();
+            let label = format!(
+                "droste_{}_{}_{}",
+                params.inner_radius, params.outer_radius, params.twist
+            );
+            let mut metadata = RenderMetadata::new(Some("droste"), None)
+                .with_option("inner", params.inner_radius)
+                .with_option("outer", params.outer_radius)
+                .with_option("twist", params.twist)
+                .with_option("levels", params.max_levels);
+            if let Some(mask_path) = &droste_args.mask {
+                metadata = metadata.with_option("mask", mask_path);
+            }
+            run_source(
+                &droste_args.source,
+                &label,
+                metadata,
+                LookupRequest::default(),
+                |width, height, request| {
+                    params.lookup(width, height, &request.viewport, mask.as_ref())
+                },
+            )
+        }
+        Some(Command::Hyperbolic(hyperbolic_args)) => {
+            let tiling = HyperbolicTiling {
+                p: hyperbolic_args.p,
+                q: hyperbolic_args.q,
+                scale: hyperbolic_args.scale,
+                rotation: hyperbolic_args.rotation.to_radians(),
+                offset: Complex::new(hyperbolic_args.offset.0, hyperbolic_args.offset.1),
+                mirror: hyperbolic_args.mirror,
+                max_iterations: hyperbolic_args.iterations,
+            };
+            tiling.validate()?;
 
-    match args.image {
-        Some(file_path) => {
-            let image_file_path = Path::new(&file_path);
-            let image_file_name = image_file_path.file_name().unwrap();
-            let image_path_string =
-                format!("./images/output/{}", image_file_name.to_string_lossy());
-            let image_path: &str = &image_path_string;
+            let label = format!("hyperbolic_{}_{}", tiling.p, tiling.q);
+            let mut metadata = RenderMetadata::new(Some("hyperbolic"), None)
+                .with_option("p", tiling.p)
+                .with_option("q", tiling.q)
+                .with_option("scale", tiling.scale)
+                .with_option("rotation", hyperbolic_args.rotation)
+                .with_option(
+                    "offset",
+                    format!("{},{}", tiling.offset.re, tiling.offset.im),
+                )
+                .with_option("iterations", tiling.max_iterations);
+            if tiling.mirror {
+                metadata = metadata.with_flag("mirror");
+            }
+            run_source(
+                &hyperbolic_args.source,
+                &label,
+                metadata,
+                LookupRequest::default(),
+                |width, height, request| tiling.lookup(width, height, &request.viewport),
+            )
+        }
+        Some(Command::Fractal(fractal_args)) => {
+            let expression = Expr::parse(&fractal_args.function)?;
+            let settings = EscapeTime {
+                kind: fractal_args.kind,
+                c: Complex::new(fractal_args.c.0, fractal_args.c.1),
+                max_iterations: fractal_args.iterations,
+                bailout: fractal_args.bailout,
+                trap_size: fractal_args.trap_size,
+                ..EscapeTime::default()
+            };
+            let viewport = Viewport {
+                center: Complex::new(fractal_args.center.0, fractal_args.center.1),
+                scale: fractal_args.scale,
+            };
+            let kind_name = format!("{:?}", settings.kind).to_lowercase();
+            let metadata = RenderMetadata::new(Some("fractal"), Some(&fractal_args.function))
+                .with_viewport(&viewport)
+                .with_option("kind", &kind_name)
+                .with_option("c", format!("{},{}", settings.c.re, settings.c.im))
+                .with_option("iterations", settings.max_iterations)
+                .with_option("bailout", settings.bailout)
+                .with_option("trap-size", settings.trap_size)
+                .with_option(
+                    "center",
+                    format!("{},{}", viewport.center.re, viewport.center.im),
+                )
+                .with_option("scale", viewport.scale);
 
-            // Lade das Bild
-            let img = image::open(image_file_path)
-                .expect("Failed to load image")
-                .to_rgb8();
+            if fractal_args.texture {
+                let label = format!("{}_{}", kind_name, fractal_args.function);
+                let metadata = metadata.with_flag("texture");
+                run_source(
+                    &fractal_args.source,
+                    &label,
+                    metadata,
+                    LookupRequest {
+                        expression: Some(expression.clone()),
+                        viewport,
+                    },
+                    |width, height, request| {
+                        let expression = request.expression.as_ref().unwrap_or(&expression);
+                        settings.trap_lookup(expression, width, height, &request.viewport)
+                    },
+                )
+            } else if fractal_args.source.image.is_some() {
+                Err("an input image is only used together with --texture".into())
+            } else {
+                let (width, height) = fractal_args.source.target_dimensions();
+                let img = settings.render(&expression, width, height, &viewport);
+                save_transformed_image(
+                    &fractal_args.source.image_output(),
+                    &kind_name,
+                    &fractal_args.function,
+                    &metadata.with_option("dimensions", format!("{},{}", width, height)),
+                    img.into(),
+                )
+            }
+        }
+        Some(Command::Analyze(analyze_args)) => {
+            let expression = Expr::parse(&analyze_args.function)?;
+            let viewport = Viewport {
+                center: Complex::new(analyze_args.center.0, analyze_args.center.1),
+                scale: analyze_args.scale,
+            };
+            let settings = AnalysisSettings {
+                grid: analyze_args.grid,
+                critical_points: !analyze_args.no_critical,
+                ..AnalysisSettings::default()
+            };
 
+            let output = analyze_args.image_output();
+            let features = analyze(&expression, &viewport, &settings);
+            write!(
+                status_stream(output.is_stdout()),
+                "{}",
+                format_table(&features)
+            )?;
+
+            if let (true, Some(image_path)) = (analyze_args.mark, &analyze_args.image) {
+                let img = open_image(image_path)?.to_rgb8();
+                let (width, height) = img.dimensions();
+                let lookup = HolomorphicLookup::with_viewport(
+                    |z| expression.evaluate(z),
+                    width,
+                    height,
+                    &viewport,
+                );
+                if let Some(mut transformed_img) = lookup.apply(&img) {
+                    mark_features(&mut transformed_img, &features, &viewport);
+                    let mut metadata =
+                        RenderMetadata::new(Some("analyze"), Some(&analyze_args.function))
+                            .with_source(image_path)
+                            .with_viewport(&viewport)
+                            .with_option(
+                                "center",
+                                format!("{},{}", viewport.center.re, viewport.center.im),
+                            )
+                            .with_option("scale", viewport.scale)
+                            .with_option("grid", settings.grid)
+                            .with_flag("mark");
+                    if analyze_args.no_critical {
+                        metadata = metadata.with_flag("no-critical");
+                    }
+                    save_transformed_image(
+                        &output,
+                        image_path,
+                        &analyze_args.function,
+                        &metadata,
+                        transformed_img.into(),
+                    )?;
+                }
+            }
+            Ok(())
+        }
+        Some(Command::Check(check_args)) => {
+            let expression = Expr::parse(&check_args.function)?;
+            let viewport = Viewport {
+                center: Complex::new(check_args.center.0, check_args.center.1),
+                scale: check_args.scale,
+            };
+
+            let output = check_args.image_output();
+            let mut status = status_stream(output.is_stdout());
+            let report = check(&expression, &viewport, check_args.grid);
+            writeln!(
+                status,
+                "symbolic derivative: {}",
+                if report.symbolic { "yes" } else { "no" }
+            )?;
+            writeln!(status, "samples:             {}", report.samples)?;
+            writeln!(status, "max residual:        {:.3e}", report.max_residual)?;
+            writeln!(status, "mean residual:       {:.3e}", report.mean_residual)?;
+            writeln!(
+                status,
+                "non-conformal:       {:.1}% (residual >= {:.0e})",
+                report.violation_fraction * 100.0,
+                RESIDUAL_TOLERANCE
+            )?;
+            writeln!(
+                status,
+                "{} is {}holomorphic in the viewport",
+                check_args.function,
+                if report.is_holomorphic() { "" } else { "not " }
+            )?;
+
+            if check_args.heatmap {
+                let (width, height) = check_args.dimensions;
+                let img = heatmap(&expression, width, height, &viewport);
+                let metadata = RenderMetadata::new(Some("check"), Some(&check_args.function))
+                    .with_viewport(&viewport)
+                    .with_option(
+                        "center",
+                        format!("{},{}", viewport.center.re, viewport.center.im),
+                    )
+                    .with_option("scale", viewport.scale)
+                    .with_option("grid", check_args.grid)
+                    .with_flag("heatmap")
+                    .with_option("dimensions", format!("{},{}", width, height));
+                save_transformed_image(
+                    &output,
+                    "conformality",
+                    &check_args.function,
+                    &metadata,
+                    img.into(),
+                )?;
+            }
+            Ok(())
+        }
+        Some(Command::RenderAnimation(animation_args)) => {
+            let expression = Expr::parse(&animation_args.function)?;
+            let format = match animation_args.format {
+                Some(format) => format,
+                None => AnimationFormat::from_path(Path::new(&animation_args.output)).ok_or(
+                    "unknown animation format, use --format or a .gif, .png or .webp file",
+                )?,
+            };
+
+            let mut img = open_image(&animation_args.image)?.to_rgb8();
+            if let Some((width, height)) = animation_args.dimensions {
+                img = imageops::resize(&img, width, height, imageops::FilterType::Triangle);
+            }
+
+            let sweep = ParameterSweep {
+                frames: animation_args.frames,
+                t_start: animation_args.time.0,
+                t_end: animation_args.time.1,
+                c_center: Complex::new(animation_args.c.0, animation_args.c.1),
+                c_radius: animation_args.radius,
+                playback: animation_args.playback,
+            };
+            let mut frames = sweep
+                .render(&expression, &img)
+                .ok_or("transforming image unsuccessful")?;
+
+            // GIF benötigt ohnehin eine Palette, eine gemeinsame verhindert Flackern
+            let colors = match (animation_args.colors, format) {
+                (Some(colors), _) => Some(colors),
+                (None, AnimationFormat::Gif) => Some(256),
+                (None, _) => None,
+            };
+            if let Some(colors) = colors {
+                quantize(&mut frames, colors as usize, animation_args.dither);
+            }
+
+            let frames = sweep.arrange(frames);
+            let encoder = AnimationEncoder {
+                format,
+                delay_ms: animation_args.delay,
+                loops: animation_args.loops,
+            };
+            let output = Path::new(&animation_args.output);
+            create_parent_dir(output)?;
+            encoder.encode(&frames, BufWriter::new(File::create(output)?))?;
+            println!(
+                "Animation saved as: {} ({} frames)",
+                animation_args.output,
+                frames.len()
+            );
+            Ok(())
+        }
+        Some(Command::Morph(morph_args)) => {
+            let from = Expr::parse(&morph_args.from)?;
+            let to = Expr::parse(&morph_args.to)?;
+            let mut img = open_image(&morph_args.image)?.to_rgb8();
+            if let Some((width, height)) = morph_args.dimensions {
+                img = imageops::resize(&img, width, height, imageops::FilterType::Triangle);
+            }
             let (width, height) = img.dimensions();
-            let lookup = HolomorphicLookup::new(holomorphic_fn, width, height);
+            let morph = Morph::new(from, to, morph_args.interpolation, width, height);
 
-            if let Some(transformed_img) = lookup.apply(&img) {
-                save_transformed_image(image_path, input, transformed_img);
+            // Ohne Dateiendung wird eine Bildsequenz in das Verzeichnis geschrieben
+            let output = Path::new(&morph_args.output);
+            if output.extension().is_none() {
+                std::fs::create_dir_all(output)?;
+                morph.render(&img, morph_args.frames, |frame, transformed_img| {
+                    transformed_img.save(output.join(format!("frame_{:04}.png", frame)))?;
+                    Ok(())
+                })?;
+                println!(
+                    "Image sequence saved in: {} ({} frames)",
+                    morph_args.output, morph_args.frames
+                );
             } else {
-                eprint!("transforming image unsuccessful");
+                let video_output = morph_args.encoding.video_output(morph_args.output.clone());
+                let mut writer = video_output.open(DEFAULT_FPS, width, height)?;
+                morph.render(&img, morph_args.frames, |_, transformed_img| {
+                    writer.write(&rgb_image_to_mat(&transformed_img)?)?;
+                    Ok(())
+                })?;
+                writer.release()?;
+                println!(
+                    "Video saved as: {} ({} frames)",
+                    morph_args.output, morph_args.frames
+                );
             }
+            Ok(())
         }
-        None => {
-            // Bestimme Breite und Höhe basierend auf Auflösung oder Dimensionen
-            let (width, height) = if let Some(res) = args.resolution {
-                res.to_dimensions()
+        Some(Command::Reproduce(reproduce_args)) => {
+            let mut metadata = read_metadata(Path::new(&reproduce_args.image))?;
+            if let Some(source) = &reproduce_args.source {
+                metadata.source = Some(source.clone());
+            }
+            if metadata.version != env!("CARGO_PKG_VERSION") {
+                eprintln!(
+                    "warning: {} was rendered by holo {}, this is {}",
+                    reproduce_args.image, metadata.version, SOFTWARE
+                );
+            }
+            if let Some(source) = metadata
+                .source
+                .as_deref()
+                .filter(|&s| !is_stdio(s) && !Path::new(s).exists())
+            {
+                return Err(format!("source image '{}' not found, pass --source", source).into());
+            }
+
+            let metadata = match reproduce_args.format.output_format {
+                Some(format) => metadata.with_option("output-format", format.extensions_str()[0]),
+                None => metadata,
+            };
+            let arguments = metadata.arguments(reproduce_args.output.as_deref());
+            let to_stdout = reproduce_args.output.as_deref().is_some_and(is_stdio);
+            writeln!(
+                status_stream(to_stdout),
+                "Reproducing: {:?}",
+                &arguments[1..]
+            )?;
+            run(Cli::try_parse_from(arguments)?)
+        }
+        Some(Command::Batch(batch_args)) => {
+            let cache = batch_args.cache.open();
+            let summary = run_batch(
+                &batch_args.function,
+                Path::new(&batch_args.input_dir),
+                Path::new(&batch_args.output_dir),
+                batch_args.overwrite,
+                cache.as_ref(),
+            )?;
+            print!("{}", summary);
+
+            if summary.failures.is_empty() {
+                Ok(())
             } else {
-                args.dimensions.unwrap_or((640, 480)) // Standard-Dimensionen
-            };
-            let lookup = HolomorphicLookup::new(holomorphic_fn, width, height);
-            let mut cap = VideoCapture::new(0, CAP_ANY)?; // 0 ist die Standardkamera
-            cap.set(CAP_PROP_FRAME_WIDTH, width as f64)?;
-            cap.set(CAP_PROP_FRAME_HEIGHT, height as f64)?;
-
-            // Erstelle ein Anzeigefenster
-            let mut window = Window::new(
-                "Holomorphic Webcam",
-                width as usize,
-                height as usize,
-                WindowOptions::default(),
-            )
-            .expect("Failed to create window");
-            while window.is_open() && !window.is_key_down(Key::Escape) {
-                // Frame von der Webcam erfassen
-                if let Some(frame) = capture_frame(&mut cap) {
-                    // Transformation anwenden
-                    if let Some(transformed_image) = process_frame(&lookup, &frame) {
-                        // Transformiertes Bild anzeigen
-                        display_image(&mut window, &transformed_image);
-                    }
+                Err(format!("{} images could not be processed", summary.failures.len()).into())
+            }
+        }
+        Some(Command::Cache(cache_args)) => {
+            let cache = LookupCache::open_default();
+            match cache_args.action {
+                CacheAction::List => {
+                    println!("cache directory: {}", cache.dir.display());
+                    print!("{}", format_entries(&cache.entries()?));
+                }
+                CacheAction::Clear => {
+                    let removed = cache.clear()?;
+                    println!(
+                        "removed {} lookup tables from {}",
+                        removed,
+                        cache.dir.display()
+                    );
                 }
             }
+            Ok(())
         }
-    }
+        Some(Command::Cameras) => {
+            print!("{}", format_cameras(&list_cameras(MAX_CAMERAS)));
+            Ok(())
+        }
+        None => {
+            let input = args.function.as_deref().unwrap_or_default();
+            let expression = Expr::parse(input)?;
+            if expression.derivative().is_none() {
+                eprintln!(
+                    "warning: {} is not holomorphic, the transformation will not be conformal",
+                    input
+                );
+            }
 
-    Ok(())
+            let metadata = RenderMetadata::new(None, Some(input));
+            if let (Some(tile_size), Some(image_path)) = (args.tile_size, &args.source.image) {
+                args.source.validate()?;
+                let renderer = TiledRenderer {
+                    tile_size,
+                    ..TiledRenderer::default()
+                };
+                let metadata = metadata
+                    .with_source(image_path)
+                    .with_option("tile-size", tile_size);
+                let output = args.source.image_output();
+                let (path, format) = output.target(image_path, input)?;
+                let stats = render_file(
+                    |z| Some(expression.evaluate(z)),
+                    &renderer,
+                    Path::new(image_path),
+                    &path,
+                    format,
+                    Some(&metadata),
+                )?;
+                if !output.is_stdout() {
+                    println!("Image saved as: {} ({})", path.display(), stats);
+                }
+                return Ok(());
+            }
+            let cache = args.cache.open();
+            let initial = Viewport::default();
+            run_source(
+                &args.source,
+                input,
+                metadata,
+                LookupRequest {
+                    expression: Some(expression.clone()),
+                    viewport: initial,
+                },
+                |width, height, request| {
+                    let expression = request.expression.as_ref().unwrap_or(&expression);
+                    let viewport = &request.viewport;
+                    let build = || {
+                        HolomorphicLookup::with_viewport(
+                            |z| expression.evaluate(z),
+                            width,
+                            height,
+                            viewport,
+                        )
+                    };
+                    // Beim Zoomen entstehende Ausschnitte nicht im Cache ablegen
+                    match &cache {
+                        Some(cache) if *viewport == initial => {
+                            let key = LookupKey::new(expression, width, height, viewport);
+                            cache.get_or_build(&key, build)
+                        }
+                        _ => build(),
+                    }
+                },
+            )
+        }
+    }
 }
@@ -0,0 +1,574 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use image::ImageFormat;
+use num_complex::Complex;
+
+use crate::holo::Viewport;
+
+/// Name und Version des Programms, wie sie in `Software`-Feldern abgelegt werden.
+pub const SOFTWARE: &str = concat!("holo ", env!("CARGO_PKG_VERSION"));
+
+/// Abtastung der Lookup-Tabellen (nächster Nachbar).
+pub const INTERPOLATION: &str = "nearest";
+
+/// Behandlung von Punkten außerhalb des Quellbildes (Spiegelung an den Rändern).
+pub const BOUNDARY: &str = "mirror";
+
+/// Präfix aller Schlüssel, die dieses Programm schreibt.
+const KEY_PREFIX: &str = "holo:";
+
+/// Präfix der Schlüssel für Kommandozeilenoptionen.
+const OPTION_PREFIX: &str = "holo:option:";
+
+/// PNG-Signatur am Dateianfang.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// EXIF-Tags für die Bildbeschreibung und die erzeugende Software.
+const EXIF_IMAGE_DESCRIPTION: u16 = 0x010e;
+const EXIF_SOFTWARE: u16 = 0x0131;
+
+/// Beschreibung, wie ein Bild erzeugt wurde.
+///
+/// Die Metadaten werden in PNG-Dateien als `tEXt`/`iTXt`-Chunks und in JPEG-Dateien als
+/// Kommentar (`COM`) und EXIF-Bildbeschreibung gespeichert. Neben den beschreibenden Feldern
+/// enthalten sie die Kommandozeilenoptionen, mit denen sich das Bild über
+/// `holo reproduce` erneut berechnen lässt.
+///
+/// # Felder
+/// - `version` (`String`): Die Version des Programms, das das Bild erzeugt hat.
+/// - `command` (`Option<String>`): Der Unterbefehl, `None` für die einfache Transformation.
+/// - `expression` (`Option<String>`): Die Funktion genau so, wie sie angegeben wurde.
+/// - `source` (`Option<String>`): Der Pfad des Quellbildes.
+/// - `viewport` (`Viewport`): Der Ausschnitt der komplexen Ebene.
+/// - `interpolation` (`String`): Die Abtastung der Lookup-Tabelle.
+/// - `boundary` (`String`): Die Behandlung von Punkten außerhalb des Quellbildes.
+/// - `options` (`Vec<(String, String)>`): Lange Optionsnamen mit Werten, leere Werte stehen
+///   für Schalter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderMetadata {
+    pub version: String,
+    pub command: Option<String>,
+    pub expression: Option<String>,
+    pub source: Option<String>,
+    pub viewport: Viewport,
+    pub interpolation: String,
+    pub boundary: String,
+    pub options: Vec<(String, String)>,
+}
+
+impl RenderMetadata {
+    /// Erstellt Metadaten für die aktuelle Programmversion.
+    ///
+    /// # Parameter
+    /// - `command` (`Option<&str>`): Der Unterbefehl, `None` für die einfache Transformation.
+    /// - `expression` (`Option<&str>`): Die verwendete Funktion, falls es eine gibt.
+    pub fn new(command: Option<&str>, expression: Option<&str>) -> Self {
+        RenderMetadata {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            command: command.map(str::to_string),
+            expression: expression.map(str::to_string),
+            source: None,
+            viewport: Viewport::default(),
+            interpolation: INTERPOLATION.to_string(),
+            boundary: BOUNDARY.to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    /// Setzt den Pfad des Quellbildes.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Setzt den Ausschnitt der komplexen Ebene.
+    pub fn with_viewport(mut self, viewport: &Viewport) -> Self {
+        self.viewport = *viewport;
+        self
+    }
+
+    /// Fügt eine Kommandozeilenoption mit Wert hinzu.
+    ///
+    /// # Parameter
+    /// - `name` (`&str`): Der Optionsname ohne führende Striche.
+    /// - `value` (`impl ToString`): Der Wert.
+    pub fn with_option(mut self, name: &str, value: impl ToString) -> Self {
+        self.options.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Fügt einen Schalter ohne Wert hinzu.
+    ///
+    /// # Parameter
+    /// - `name` (`&str`): Der Optionsname ohne führende Striche.
+    pub fn with_flag(self, name: &str) -> Self {
+        self.with_option(name, "")
+    }
+
+    /// Gibt alle Felder als Schlüssel-Wert-Paare zurück.
+    ///
+    /// # Rückgabewert
+    /// Die Paare in fester Reihenfolge, Schlüssel beginnen mit `holo:`.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = vec![(format!("{}version", KEY_PREFIX), self.version.clone())];
+        if let Some(command) = &self.command {
+            entries.push((format!("{}command", KEY_PREFIX), command.clone()));
+        }
+        if let Some(expression) = &self.expression {
+            entries.push((format!("{}expression", KEY_PREFIX), expression.clone()));
+        }
+        if let Some(source) = &self.source {
+            entries.push((format!("{}source", KEY_PREFIX), source.clone()));
+        }
+        entries.push((
+            format!("{}center", KEY_PREFIX),
+            format!("{},{}", self.viewport.center.re, self.viewport.center.im),
+        ));
+        entries.push((
+            format!("{}scale", KEY_PREFIX),
+            self.viewport.scale.to_string(),
+        ));
+        entries.push((
+            format!("{}interpolation", KEY_PREFIX),
+            self.interpolation.clone(),
+        ));
+        entries.push((format!("{}boundary", KEY_PREFIX), self.boundary.clone()));
+        for (name, value) in &self.options {
+            entries.push((format!("{}{}", OPTION_PREFIX, name), value.clone()));
+        }
+        entries
+    }
+
+    /// Setzt Metadaten aus Schlüssel-Wert-Paaren zusammen.
+    ///
+    /// Unbekannte Schlüssel werden ignoriert.
+    ///
+    /// # Parameter
+    /// - `entries` (`impl IntoIterator<Item = (String, String)>`): Die gelesenen Paare.
+    ///
+    /// # Rückgabewert
+    /// Die Metadaten oder `None`, wenn kein `holo:version`-Eintrag vorhanden ist.
+    pub fn from_entries(entries: impl IntoIterator<Item = (String, String)>) -> Option<Self> {
+        let mut metadata = RenderMetadata::new(None, None);
+        let mut version = None;
+        for (key, value) in entries {
+            if let Some(name) = key.strip_prefix(OPTION_PREFIX) {
+                metadata.options.push((name.to_string(), value));
+                continue;
+            }
+            match key.strip_prefix(KEY_PREFIX) {
+                Some("version") => version = Some(value),
+                Some("command") => metadata.command = Some(value),
+                Some("expression") => metadata.expression = Some(value),
+                Some("source") => metadata.source = Some(value),
+                Some("center") => {
+                    let (re, im) = value.split_once(',')?;
+                    metadata.viewport.center = Complex::new(re.parse().ok()?, im.parse().ok()?);
+                }
+                Some("scale") => metadata.viewport.scale = value.parse().ok()?,
+                Some("interpolation") => metadata.interpolation = value,
+                Some("boundary") => metadata.boundary = value,
+                _ => {}
+            }
+        }
+        metadata.version = version?;
+        Some(metadata)
+    }
+
+    /// Gibt die Metadaten als Text mit einer Zeile `schlüssel=wert` pro Feld zurück.
+    pub fn to_text(&self) -> String {
+        self.entries()
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect()
+    }
+
+    /// Liest Metadaten aus Text im Format von [`RenderMetadata::to_text`].
+    ///
+    /// # Rückgabewert
+    /// Die Metadaten oder `None`, wenn der Text keine Metadaten dieses Programms enthält.
+    pub fn from_text(text: &str) -> Option<Self> {
+        Self::from_entries(text.lines().filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        }))
+    }
+
+    /// Baut die Kommandozeile, mit der das Bild erneut berechnet wird.
+    ///
+    /// Einbuchstabige Optionen werden als kurze Option übergeben, alle anderen als
+    /// `--name=wert`, damit auch negative Werte eindeutig bleiben. Die Funktion steht als
+    /// letztes hinter `--`, damit Ausdrücke wie `-z^2` nicht als Option gelesen werden.
+    ///
+    /// # Parameter
+    /// - `output` (`Option<&str>`): Optionaler Ausgabepfad bzw. Namensvorlage.
+    ///
+    /// # Rückgabewert
+    /// Die Argumente einschließlich des Programmnamens.
+    pub fn arguments(&self, output: Option<&str>) -> Vec<String> {
+        let mut arguments = vec![String::from("holo")];
+        arguments.extend(self.command.clone());
+        if let Some(source) = &self.source {
+            arguments.push(format!("--image={}", source));
+        }
+        for (name, value) in &self.options {
+            match (name.len(), value.is_empty()) {
+                (1, true) => arguments.push(format!("-{}", name)),
+                (1, false) => arguments.extend([format!("-{}", name), value.clone()]),
+                (_, true) => arguments.push(format!("--{}", name)),
+                (_, false) => arguments.push(format!("--{}={}", name, value)),
+            }
+        }
+        if let Some(output) = output {
+            arguments.push(format!("--output={}", output));
+        }
+        if let Some(expression) = &self.expression {
+            arguments.extend([String::from("--"), expression.clone()]);
+        }
+        arguments
+    }
+
+    /// Bettet die Metadaten in ein kodiertes Bild ein.
+    ///
+    /// PNG erhält einen `tEXt`-Chunk `Software` und je Feld einen `iTXt`-Chunk, JPEG einen
+    /// EXIF-Block mit Bildbeschreibung und Software sowie einen Kommentar. Andere Formate
+    /// werden unverändert zurückgegeben.
+    ///
+    /// # Parameter
+    /// - `bytes` (`Vec<u8>`): Das kodierte Bild.
+    /// - `format` (`ImageFormat`): Das Format von `bytes`.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Daten kein gültiges PNG bzw. JPEG sind oder die
+    /// Metadaten nicht in ein JPEG-Segment passen.
+    pub fn embed(&self, bytes: Vec<u8>, format: ImageFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+        match format {
+            ImageFormat::Png => self.embed_png(bytes),
+            ImageFormat::Jpeg => self.embed_jpeg(bytes),
+            _ => Ok(bytes),
+        }
+    }
+
+    /// Erstellt die Textchunks eines PNG: `Software` als `tEXt`, alle Einträge als `iTXt`.
+    ///
+    /// # Rückgabewert
+    /// Typ und Daten der Chunks, ohne Länge und Prüfsumme.
+    pub fn png_text_chunks(&self) -> Vec<([u8; 4], Vec<u8>)> {
+        let mut chunks = vec![(*b"tEXt", [b"Software\0", SOFTWARE.as_bytes()].concat())];
+        for (key, value) in self.entries() {
+            // Schlüssel, unkomprimiert, leere Sprachangabe und leere Übersetzung
+            let data = [key.as_bytes(), b"\0\0\0\0\0", value.as_bytes()].concat();
+            chunks.push((*b"iTXt", data));
+        }
+        chunks
+    }
+
+    /// Fügt die Chunks direkt hinter dem `IHDR`-Chunk ein.
+    fn embed_png(&self, bytes: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+        let chunks = png_chunks(&bytes).ok_or("invalid PNG data")?;
+        let (_, _, ihdr_end) = chunks.first().ok_or("PNG without IHDR chunk")?;
+
+        let mut text = Vec::new();
+        for (kind, data) in self.png_text_chunks() {
+            write_png_chunk(&mut text, &kind, &data);
+        }
+
+        let mut result = bytes[..*ihdr_end].to_vec();
+        result.extend(text);
+        result.extend(&bytes[*ihdr_end..]);
+        Ok(result)
+    }
+
+    /// Fügt EXIF-Block und Kommentar hinter den führenden `APPn`-Segmenten ein.
+    ///
+    /// So bleiben ein JFIF-Segment bzw. ein vorhandener EXIF-Block an erster Stelle, wie es
+    /// die jeweiligen Formate verlangen.
+    fn embed_jpeg(&self, bytes: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+        let segments = jpeg_segments(&bytes).ok_or("invalid JPEG data")?;
+        let position = segments
+            .iter()
+            .take_while(|(marker, _, _)| (0xe0..=0xef).contains(marker))
+            .last()
+            .map_or(2, |(_, _, end)| *end);
+
+        let text = self.to_text();
+        let exif = [b"Exif\0\0".as_slice(), &exif_block(&text)].concat();
+        let mut segments = Vec::new();
+        write_jpeg_segment(&mut segments, 0xe1, &exif)?;
+        write_jpeg_segment(&mut segments, 0xfe, text.as_bytes())?;
+
+        let mut result = bytes[..position].to_vec();
+        result.extend(segments);
+        result.extend(&bytes[position..]);
+        Ok(result)
+    }
+}
+
+/// Typ, Daten und Endposition eines PNG-Chunks.
+type PngChunk<'a> = ([u8; 4], &'a [u8], usize);
+
+/// Zerlegt ein PNG in seine Chunks.
+///
+/// # Rückgabewert
+/// Die Chunks bis einschließlich `IEND` oder `None`, wenn die Daten kein PNG sind.
+fn png_chunks(bytes: &[u8]) -> Option<Vec<PngChunk<'_>>> {
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    while position + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[position..position + 4].try_into().ok()?) as usize;
+        let kind: [u8; 4] = bytes[position + 4..position + 8].try_into().ok()?;
+        let data = bytes.get(position + 8..position + 8 + length)?;
+        position += 12 + length;
+        chunks.push((kind, data, position));
+        if &kind == b"IEND" {
+            break;
+        }
+    }
+    Some(chunks)
+}
+
+/// Marker, Daten und Endposition eines JPEG-Segments.
+type JpegSegment<'a> = (u8, &'a [u8], usize);
+
+/// Zerlegt den Kopf eines JPEG in seine Segmente.
+///
+/// # Rückgabewert
+/// Die Segmente bis zum Beginn der Bilddaten (`SOS`) oder `None`, wenn die Daten kein JPEG
+/// sind.
+fn jpeg_segments(bytes: &[u8]) -> Option<Vec<JpegSegment<'_>>> {
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    let mut segments = Vec::new();
+    let mut position = 2;
+    while position + 4 <= bytes.len() && bytes[position] == 0xff {
+        let marker = bytes[position + 1];
+        if marker == 0xda || marker == 0xd9 {
+            break; // Start of Scan bzw. End of Image
+        }
+        let length = u16::from_be_bytes([bytes[position + 2], bytes[position + 3]]) as usize;
+        let data = bytes.get(position + 4..position + 2 + length)?;
+        position += 2 + length;
+        segments.push((marker, data, position));
+    }
+    Some(segments)
+}
+
+/// Schreibt einen PNG-Chunk mit Länge und Prüfsumme.
+fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(data);
+    out.extend(hasher.finalize().to_be_bytes());
+}
+
+/// Schreibt ein JPEG-Segment mit Marker und Länge.
+fn write_jpeg_segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let length = u16::try_from(data.len() + 2).map_err(|_| "metadata too large for JPEG")?;
+    out.extend([0xff, marker]);
+    out.extend(length.to_be_bytes());
+    out.extend(data);
+    Ok(())
+}
+
+/// Erstellt einen TIFF-Block mit einer IFD aus Bildbeschreibung und Software.
+fn exif_block(description: &str) -> Vec<u8> {
+    let fields = [
+        (EXIF_IMAGE_DESCRIPTION, description),
+        (EXIF_SOFTWARE, SOFTWARE),
+    ];
+    // Little-Endian-Kopf, die erste IFD folgt direkt danach
+    let mut block = b"II*\0".to_vec();
+    block.extend(8u32.to_le_bytes());
+    block.extend((fields.len() as u16).to_le_bytes());
+
+    let mut data = Vec::new();
+    let data_offset = 8 + 2 + 12 * fields.len() + 4;
+    for (tag, value) in fields {
+        let mut value = value.as_bytes().to_vec();
+        value.push(0);
+        block.extend(tag.to_le_bytes());
+        block.extend(2u16.to_le_bytes()); // ASCII
+        block.extend((value.len() as u32).to_le_bytes());
+        if value.len() <= 4 {
+            value.resize(4, 0);
+            block.extend(value);
+        } else {
+            block.extend(((data_offset + data.len()) as u32).to_le_bytes());
+            data.extend(value);
+        }
+    }
+    block.extend(0u32.to_le_bytes()); // keine weitere IFD
+    block.extend(data);
+    block
+}
+
+/// Sammelt die Textfelder eines PNG (`tEXt` und unkomprimierte `iTXt`).
+fn png_entries(bytes: &[u8]) -> Option<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for (kind, data, _) in png_chunks(bytes)? {
+        let entry = match &kind {
+            b"tEXt" => data.iter().position(|&b| b == 0).map(|split| {
+                // tEXt ist Latin-1 kodiert
+                let latin1 = |bytes: &[u8]| bytes.iter().map(|&b| b as char).collect();
+                (latin1(&data[..split]), latin1(&data[split + 1..]))
+            }),
+            b"iTXt" => parse_itxt(data),
+            _ => None,
+        };
+        entries.extend(entry);
+    }
+    Some(entries)
+}
+
+/// Liest Schlüssel und Text eines unkomprimierten `iTXt`-Chunks.
+fn parse_itxt(data: &[u8]) -> Option<(String, String)> {
+    let mut parts = data.splitn(2, |&b| b == 0);
+    let key = String::from_utf8(parts.next()?.to_vec()).ok()?;
+    let rest = parts.next()?;
+    if rest.first() != Some(&0) {
+        return None; // komprimierter Text wird nicht geschrieben
+    }
+    // Kompressionsflag und -methode, danach Sprache und Übersetzung
+    let mut parts = rest.get(2..)?.splitn(3, |&b| b == 0);
+    parts.next()?;
+    parts.next()?;
+    let text = String::from_utf8(parts.next()?.to_vec()).ok()?;
+    Some((key, text))
+}
+
+/// Sammelt die Kommentare (`COM`) eines JPEG bis zum Beginn der Bilddaten.
+fn jpeg_comments(bytes: &[u8]) -> Option<Vec<String>> {
+    let segments = jpeg_segments(bytes)?;
+    Some(
+        segments
+            .into_iter()
+            .filter(|(marker, _, _)| *marker == 0xfe)
+            .map(|(_, data, _)| String::from_utf8_lossy(data).into_owned())
+            .collect(),
+    )
+}
+
+/// Liest die Metadaten eines von diesem Programm gespeicherten Bildes.
+///
+/// # Parameter
+/// - `path` (`&Path`): Der Pfad eines PNG- oder JPEG-Bildes.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Datei nicht gelesen werden kann, kein PNG bzw. JPEG
+/// ist oder keine Metadaten enthält.
+pub fn read_metadata(path: &Path) -> Result<RenderMetadata, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let metadata = match image::guess_format(&bytes)? {
+        ImageFormat::Png => png_entries(&bytes).and_then(RenderMetadata::from_entries),
+        ImageFormat::Jpeg => jpeg_comments(&bytes)
+            .and_then(|comments| comments.iter().find_map(|c| RenderMetadata::from_text(c))),
+        format => {
+            return Err(format!("{:?} files do not carry render metadata", format).into());
+        }
+    };
+    metadata.ok_or_else(|| format!("no render metadata found in '{}'", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::RgbImage;
+    use std::io::Cursor;
+
+    fn metadata() -> RenderMetadata {
+        RenderMetadata::new(Some("fractal"), Some("z^2 + c / (z - 1)"))
+            .with_viewport(&Viewport {
+                center: Complex::new(-0.5, 0.25),
+                scale: 1.5,
+            })
+            .with_option("c", "-0.8,0.156")
+            .with_option("trap-size", 0.5)
+            .with_flag("texture")
+    }
+
+    #[test]
+    fn test_png_and_jpeg_roundtrip() {
+        let img = RgbImage::new(8, 8);
+        let path = std::env::temp_dir().join(format!("holo_metadata_{}", std::process::id()));
+
+        let mut png = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let png = metadata().embed(png, ImageFormat::Png).unwrap();
+        assert!(image::load_from_memory(&png).is_ok());
+        fs::write(path.with_extension("png"), png).unwrap();
+        assert_eq!(
+            read_metadata(&path.with_extension("png")).unwrap(),
+            metadata()
+        );
+
+        let mut jpeg = Vec::new();
+        JpegEncoder::new(&mut jpeg).encode_image(&img).unwrap();
+        let jpeg = metadata().embed(jpeg, ImageFormat::Jpeg).unwrap();
+        assert!(image::load_from_memory(&jpeg).is_ok());
+        fs::write(path.with_extension("jpg"), jpeg).unwrap();
+        assert_eq!(
+            read_metadata(&path.with_extension("jpg")).unwrap(),
+            metadata()
+        );
+
+        fs::remove_file(path.with_extension("png")).unwrap();
+        fs::remove_file(path.with_extension("jpg")).unwrap();
+    }
+
+    #[test]
+    fn test_jpeg_embed_keeps_leading_exif() {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new(&mut jpeg)
+            .encode_image(&RgbImage::new(8, 8))
+            .unwrap();
+        // JFIF-Segment durch einen EXIF-Block ersetzen, wie ihn Kameras schreiben
+        let (_, _, jfif_end) = jpeg_segments(&jpeg).unwrap()[0];
+        let mut exif_first = jpeg[..2].to_vec();
+        write_jpeg_segment(&mut exif_first, 0xe1, b"Exif\0\0camera").unwrap();
+        exif_first.extend(&jpeg[jfif_end..]);
+
+        let embedded = metadata().embed(exif_first, ImageFormat::Jpeg).unwrap();
+        assert!(image::load_from_memory(&embedded).is_ok());
+        let segments = jpeg_segments(&embedded).unwrap();
+        assert_eq!(
+            (segments[0].0, segments[0].1),
+            (0xe1, b"Exif\0\0camera".as_slice())
+        );
+        assert_eq!(segments[1].0, 0xe1);
+        assert_eq!(jpeg_comments(&embedded).unwrap(), [metadata().to_text()]);
+    }
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!(
+            metadata().with_source("in.png").arguments(Some("out.png")),
+            [
+                "holo",
+                "fractal",
+                "--image=in.png",
+                "-c",
+                "-0.8,0.156",
+                "--trap-size=0.5",
+                "--texture",
+                "--output=out.png",
+                "--",
+                "z^2 + c / (z - 1)"
+            ]
+        );
+        assert_eq!(
+            RenderMetadata::new(None, Some("-z^2")).arguments(None),
+            ["holo", "--", "-z^2"]
+        );
+    }
+}
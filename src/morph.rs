@@ -0,0 +1,261 @@
+use std::error::Error;
+
+use clap::ValueEnum;
+use image::RgbImage;
+use num_complex::Complex;
+use rayon::prelude::*;
+
+use crate::holo::{HolomorphicLookup, NO_SOURCE};
+use crate::parsing::Expr;
+
+/// Art der Überblendung zwischen zwei Funktionen `f` und `g`.
+///
+/// # Varianten
+/// - `Linear`: Die Quellpixel der beiden Lookup-Tabellen werden linear interpoliert,
+///   jedes Pixel wandert also auf einer Geraden durch das Quellbild.
+/// - `Homotopy`: Die Funktionswerte werden überblendet, `(1 - s) f(z) + s g(z)`.
+/// - `Sphere`: Die Funktionswerte werden auf der Riemannschen Zahlenkugel entlang
+///   eines Großkreises interpoliert, sodass auch Pole gleichmäßig wandern.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Homotopy,
+    Sphere,
+}
+
+/// Überblendung zwischen zwei Transformationen.
+///
+/// # Felder
+/// - `from` (`Expr`): Die Funktion `f` bei `s = 0`.
+/// - `to` (`Expr`): Die Funktion `g` bei `s = 1`.
+/// - `interpolation` (`Interpolation`): Die Art der Überblendung.
+/// - `width` (`u32`): Die Breite der Frames.
+/// - `height` (`u32`): Die Höhe der Frames.
+/// - `endpoints` (`Option<(HolomorphicLookup, HolomorphicLookup)>`): Die Lookup-Tabellen
+///   von `f` und `g`, nur für `Interpolation::Linear`.
+pub struct Morph {
+    from: Expr,
+    to: Expr,
+    interpolation: Interpolation,
+    width: u32,
+    height: u32,
+    endpoints: Option<(HolomorphicLookup, HolomorphicLookup)>,
+}
+
+/// Projiziert einen Punkt der Ebene stereographisch auf die Einheitskugel.
+///
+/// Nicht endliche Werte (Pole) werden auf den Nordpol `(0, 0, 1)` abgebildet.
+fn to_sphere(w: Complex<f64>) -> [f64; 3] {
+    if !w.re.is_finite() || !w.im.is_finite() {
+        return [0.0, 0.0, 1.0];
+    }
+    let norm_sqr = w.norm_sqr();
+    let d = 1.0 + norm_sqr;
+    [2.0 * w.re / d, 2.0 * w.im / d, (norm_sqr - 1.0) / d]
+}
+
+/// Projiziert einen Punkt der Einheitskugel zurück in die Ebene.
+///
+/// Der Nordpol wird zu einem unendlichen Wert, der beim Erstellen der Lookup-Tabelle
+/// wie eine Singularität behandelt wird.
+fn from_sphere([x, y, z]: [f64; 3]) -> Complex<f64> {
+    Complex::new(x, y) / (1.0 - z)
+}
+
+/// Sphärische Interpolation zwischen zwei Einheitsvektoren.
+fn slerp(a: [f64; 3], b: [f64; 3], s: f64) -> [f64; 3] {
+    let dot = (a[0] * b[0] + a[1] * b[1] + a[2] * b[2]).clamp(-1.0, 1.0);
+    let angle = dot.acos();
+    let (wa, wb) = if angle.sin().abs() < 1e-9 {
+        // (Fast) gleiche oder gegenüberliegende Punkte: normierte lineare Interpolation
+        (1.0 - s, s)
+    } else {
+        (
+            ((1.0 - s) * angle).sin() / angle.sin(),
+            (s * angle).sin() / angle.sin(),
+        )
+    };
+    let p = [
+        wa * a[0] + wb * b[0],
+        wa * a[1] + wb * b[1],
+        wa * a[2] + wb * b[2],
+    ];
+    let length = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+    if length < 1e-12 {
+        a
+    } else {
+        [p[0] / length, p[1] / length, p[2] / length]
+    }
+}
+
+impl Morph {
+    /// Erstellt eine Überblendung für Frames der Größe `width` x `height`.
+    ///
+    /// Für `Interpolation::Linear` werden die Lookup-Tabellen von `f` und `g` einmalig
+    /// vorab berechnet, die Zwischenbilder kosten danach nur noch eine Interpolation.
+    ///
+    /// # Parameter
+    /// - `from` (`Expr`): Die Funktion `f`.
+    /// - `to` (`Expr`): Die Funktion `g`.
+    /// - `interpolation` (`Interpolation`): Die Art der Überblendung.
+    /// - `width` (`u32`): Die Breite der Frames.
+    /// - `height` (`u32`): Die Höhe der Frames.
+    pub fn new(
+        from: Expr,
+        to: Expr,
+        interpolation: Interpolation,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let endpoints = (interpolation == Interpolation::Linear).then(|| {
+            (
+                HolomorphicLookup::new(|z| from.evaluate(z), width, height),
+                HolomorphicLookup::new(|z| to.evaluate(z), width, height),
+            )
+        });
+        Morph {
+            from,
+            to,
+            interpolation,
+            width,
+            height,
+            endpoints,
+        }
+    }
+
+    /// Überblendet die Funktionswerte von `f` und `g` im Punkt `z`.
+    ///
+    /// Für `Interpolation::Linear` entspricht dies der Homotopie; die Interpolation
+    /// der Quellpixel geschieht erst in [`Morph::lookup`].
+    ///
+    /// # Parameter
+    /// - `z` (`Complex<f64>`): Der Punkt in normierten Koordinaten.
+    /// - `s` (`f64`): Der Fortschritt zwischen `0` (`f`) und `1` (`g`).
+    ///
+    /// # Rückgabewert
+    /// Der überblendete Funktionswert.
+    pub fn blend(&self, z: Complex<f64>, s: f64) -> Complex<f64> {
+        let (f, g) = (self.from.evaluate(z), self.to.evaluate(z));
+        match self.interpolation {
+            _ if s <= 0.0 => f,
+            _ if s >= 1.0 => g,
+            Interpolation::Linear | Interpolation::Homotopy => f * (1.0 - s) + g * s,
+            Interpolation::Sphere => from_sphere(slerp(to_sphere(f), to_sphere(g), s)),
+        }
+    }
+
+    /// Berechnet die Lookup-Tabelle für den Fortschritt `s`.
+    ///
+    /// # Parameter
+    /// - `s` (`f64`): Der Fortschritt zwischen `0` (`f`) und `1` (`g`).
+    ///
+    /// # Rückgabewert
+    /// Die Lookup-Tabelle des Zwischenbildes.
+    pub fn lookup(&self, s: f64) -> HolomorphicLookup {
+        let Some((start, end)) = &self.endpoints else {
+            return HolomorphicLookup::new(|z| self.blend(z, s), self.width, self.height);
+        };
+
+        let width = self.width;
+        let lookup = start
+            .lookup
+            .iter()
+            .zip(&end.lookup)
+            .map(|(&a, &b)| {
+                if a == NO_SOURCE || b == NO_SOURCE {
+                    return NO_SOURCE;
+                }
+                let lerp = |p: u32, q: u32| (p as f64 + s * (q as f64 - p as f64)).round() as u32;
+                lerp(a / width, b / width) * width + lerp(a % width, b % width)
+            })
+            .collect();
+        HolomorphicLookup {
+            lookup,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Rendert `frames` Zwischenbilder von `f` nach `g` (beide eingeschlossen).
+    ///
+    /// Die Frames werden blockweise parallel berechnet und in der richtigen Reihenfolge
+    /// an `sink` übergeben, sodass nie mehr als ein Block im Speicher liegt.
+    ///
+    /// # Parameter
+    /// - `img` (`&RgbImage`): Das Quellbild in der Größe der Überblendung.
+    /// - `frames` (`u32`): Anzahl der Frames, mindestens `2`.
+    /// - `sink` (`impl FnMut(u32, RgbImage) -> Result<(), Box<dyn Error>>`): Erhält den
+    ///   Index und das Bild jedes Frames.
+    ///
+    /// # Fehler
+    /// Gibt den ersten Fehler von `sink` zurück oder einen Fehler, wenn ein Frame nicht
+    /// transformiert werden kann.
+    pub fn render(
+        &self,
+        img: &RgbImage,
+        frames: u32,
+        mut sink: impl FnMut(u32, RgbImage) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let last = frames.saturating_sub(1).max(1) as f64;
+        let indices: Vec<u32> = (0..frames).collect();
+        for block in indices.chunks(rayon::current_num_threads()) {
+            let rendered: Vec<Option<RgbImage>> = block
+                .par_iter()
+                .map(|&frame| self.lookup(frame as f64 / last).apply(img))
+                .collect();
+            for (&frame, transformed_img) in block.iter().zip(rendered) {
+                sink(
+                    frame,
+                    transformed_img.ok_or("transforming image unsuccessful")?,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn morph(interpolation: Interpolation) -> Morph {
+        let from = Expr::parse("z").unwrap();
+        let to = Expr::parse("z^2").unwrap();
+        Morph::new(from, to, interpolation, 40, 30)
+    }
+
+    #[test]
+    fn test_endpoints_match_functions() {
+        for interpolation in [
+            Interpolation::Linear,
+            Interpolation::Homotopy,
+            Interpolation::Sphere,
+        ] {
+            let morph = morph(interpolation);
+            let identity = HolomorphicLookup::new(|z| z, 40, 30);
+            let square_expr = Expr::parse("z^2").unwrap();
+            let square = HolomorphicLookup::new(|z| square_expr.evaluate(z), 40, 30);
+            assert_eq!(
+                morph.lookup(0.0).lookup,
+                identity.lookup,
+                "{:?}",
+                interpolation
+            );
+            assert_eq!(
+                morph.lookup(1.0).lookup,
+                square.lookup,
+                "{:?}",
+                interpolation
+            );
+        }
+    }
+
+    #[test]
+    fn test_sphere_projection_roundtrip() {
+        let w = Complex::new(0.3, -2.5);
+        assert!((from_sphere(to_sphere(w)) - w).norm() < 1e-12);
+
+        let pole = from_sphere(to_sphere(Complex::new(f64::INFINITY, 0.0)));
+        assert!(!pole.re.is_finite() || !pole.im.is_finite());
+    }
+}
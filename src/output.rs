@@ -0,0 +1,508 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::Local;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ColorType, DynamicImage, ImageFormat};
+
+use crate::metadata::RenderMetadata;
+
+/// Vorlage für den Ausgabepfad, wenn kein `--output` angegeben ist.
+pub const DEFAULT_TEMPLATE: &str = "./images/output/{stem}_{expr}_{ts}.jpeg";
+
+/// Dateiname, der verwendet wird, wenn `--output` ein Verzeichnis angibt.
+pub const DEFAULT_FILE_NAME: &str = "{stem}_{expr}_{ts}.jpeg";
+
+/// Standardqualität für JPEG-Ausgaben (wie der Standard der `image`-Crate).
+pub const DEFAULT_JPEG_QUALITY: u8 = 75;
+
+/// Endung für `{ext}`, wenn die Quelle keine Bilddatei ist (z. B. bei Fraktalen).
+const FALLBACK_EXTENSION: &str = "png";
+
+/// Pfad, der für die Standardeingabe bzw. -ausgabe steht.
+pub const STDIO_PATH: &str = "-";
+
+/// Gibt an, ob ein Pfad für die Standardeingabe bzw. -ausgabe steht.
+///
+/// # Parameter
+/// - `path` (`&str`): Der Pfad aus der Kommandozeile.
+pub fn is_stdio(path: &str) -> bool {
+    path == STDIO_PATH
+}
+
+/// Format für die Standardausgabe, wenn `--output-format` fehlt.
+pub const DEFAULT_STDOUT_FORMAT: ImageFormat = ImageFormat::Png;
+
+/// Dateiname, der für `{stem}` eingesetzt wird, wenn die Quelle die Standardeingabe ist.
+const STDIN_STEM: &str = "stdin";
+
+/// Ziel und Qualität für gespeicherte Bilder.
+///
+/// Die Vorlage ist entweder ein fester Pfad oder enthält Platzhalter:
+/// - `{stem}`: Der Dateiname der Quelle ohne Endung.
+/// - `{expr}`: Die Transformation, `/` wird durch `div` ersetzt und Leerraum entfernt.
+/// - `{ts}`: Ein Zeitstempel im Format `YYYYMMDDhhmmss`.
+/// - `{ext}`: Die Endung der Quelle (bzw. `png`, wenn es keine gibt).
+///
+/// Das Format ergibt sich aus der Endung des fertigen Pfads (PNG, JPEG, WebP, TIFF, EXR, ...),
+/// sofern es nicht ausdrücklich angegeben ist. `-` schreibt das Bild in die Standardausgabe.
+///
+/// # Felder
+/// - `template` (`String`): Der Pfad bzw. die Vorlage.
+/// - `quality` (`u8`): Die JPEG-Qualität zwischen 1 und 100.
+/// - `format` (`Option<ImageFormat>`): Erzwingt ein Format unabhängig von der Endung.
+#[derive(Debug, Clone)]
+pub struct ImageOutput {
+    pub template: String,
+    pub quality: u8,
+    pub format: Option<ImageFormat>,
+}
+
+impl Default for ImageOutput {
+    fn default() -> Self {
+        ImageOutput {
+            template: DEFAULT_TEMPLATE.to_string(),
+            quality: DEFAULT_JPEG_QUALITY,
+            format: None,
+        }
+    }
+}
+
+/// Entfernt Zeichen, die in Dateinamen stören, aus einer Transformation.
+///
+/// # Parameter
+/// - `function_str` (`&str`): Die Transformation als String.
+///
+/// # Rückgabewert
+/// Der bereinigte String.
+pub fn sanitize(function_str: &str) -> String {
+    // Ersetze "/" durch "div" für einen sicheren Dateinamen
+    function_str
+        .replace('/', "div")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// Legt das übergeordnete Verzeichnis eines Pfads an, falls es fehlt.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Verzeichnis nicht angelegt werden kann.
+pub fn create_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Setzt die Platzhalter `{stem}`, `{expr}`, `{ts}` und `{ext}` in eine Pfadvorlage ein.
+///
+/// # Parameter
+/// - `template` (`&str`): Die Vorlage, siehe [`ImageOutput`].
+/// - `source` (`&str`): Der Pfad der Quelle oder eine Bezeichnung wie `julia`.
+/// - `function_str` (`&str`): Die Transformation als String.
+///
+/// # Rückgabewert
+/// Der fertige Pfad.
+pub fn expand_template(template: &str, source: &str, function_str: &str) -> PathBuf {
+    let source = Path::new(source);
+    let stem = if source == Path::new(STDIO_PATH) {
+        STDIN_STEM.into()
+    } else {
+        source.file_stem().unwrap_or_default().to_string_lossy()
+    };
+    let extension = source
+        .extension()
+        .map(|ext| ext.to_string_lossy())
+        .unwrap_or(FALLBACK_EXTENSION.into());
+    let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
+
+    PathBuf::from(
+        template
+            .replace("{stem}", &stem)
+            .replace("{expr}", &sanitize(function_str))
+            .replace("{ts}", &timestamp)
+            .replace("{ext}", &extension),
+    )
+}
+
+/// Gibt einen Pfad zurück, unter dem noch keine Datei liegt.
+///
+/// Existiert `path` bereits, wird vor der Endung ein Zähler angehängt (`clip_2.mp4`,
+/// `clip_3.mp4`, ...), bis ein freier Name gefunden ist.
+///
+/// # Parameter
+/// - `path` (`&Path`): Der gewünschte Pfad.
+///
+/// # Rückgabewert
+/// `path` selbst oder der erste freie Pfad mit Zähler.
+pub fn unused_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+impl ImageOutput {
+    /// Erstellt die Ausgabeeinstellungen aus den Kommandozeilenargumenten.
+    ///
+    /// # Parameter
+    /// - `output` (`Option<&str>`): Pfad, Vorlage oder Verzeichnis; `None` für [`DEFAULT_TEMPLATE`].
+    /// - `quality` (`u8`): Die JPEG-Qualität.
+    /// - `format` (`Option<ImageFormat>`): Optionales Format, sonst anhand der Endung.
+    pub fn new(output: Option<&str>, quality: u8, format: Option<ImageFormat>) -> Self {
+        let template = match output {
+            None => DEFAULT_TEMPLATE.to_string(),
+            Some(STDIO_PATH) => STDIO_PATH.to_string(),
+            Some(dir) if dir.ends_with('/') || Path::new(dir).is_dir() => Path::new(dir)
+                .join(DEFAULT_FILE_NAME)
+                .to_string_lossy()
+                .into_owned(),
+            Some(path) => path.to_string(),
+        };
+        ImageOutput {
+            template,
+            quality,
+            format,
+        }
+    }
+
+    /// Gibt an, ob das Bild in die Standardausgabe geschrieben wird.
+    ///
+    /// In diesem Fall dürfen keine Statusmeldungen auf der Standardausgabe erscheinen.
+    pub fn is_stdout(&self) -> bool {
+        is_stdio(&self.template)
+    }
+
+    /// Setzt die Platzhalter der Vorlage ein.
+    ///
+    /// # Parameter
+    /// - `source` (`&str`): Der Pfad der Quelle oder eine Bezeichnung wie `julia`.
+    /// - `function_str` (`&str`): Die Transformation als String.
+    ///
+    /// # Rückgabewert
+    /// Der fertige Ausgabepfad.
+    pub fn path(&self, source: &str, function_str: &str) -> PathBuf {
+        expand_template(&self.template, source, function_str)
+    }
+
+    /// Bestimmt Ausgabepfad und Format.
+    ///
+    /// # Parameter
+    /// - `source` (`&str`): Der Pfad der Quelle oder eine Bezeichnung.
+    /// - `function_str` (`&str`): Die Transformation als String.
+    ///
+    /// # Rückgabewert
+    /// Der fertige Pfad (bzw. `-` für die Standardausgabe) und das Bildformat.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Format nicht aus der Endung hervorgeht.
+    pub fn target(
+        &self,
+        source: &str,
+        function_str: &str,
+    ) -> Result<(PathBuf, ImageFormat), String> {
+        if self.is_stdout() {
+            let format = self.format.unwrap_or(DEFAULT_STDOUT_FORMAT);
+            return Ok((PathBuf::from(STDIO_PATH), format));
+        }
+        let path = self.path(source, function_str);
+        let format = match self.format {
+            Some(format) => format,
+            None => format_from_path(&path)?,
+        };
+        Ok((path, format))
+    }
+
+    /// Speichert ein Bild unter dem Pfad aus der Vorlage oder schreibt es in die
+    /// Standardausgabe.
+    ///
+    /// # Parameter
+    /// - `img` (`&DynamicImage`): Das Bild.
+    /// - `source` (`&str`): Der Pfad der Quelle oder eine Bezeichnung.
+    /// - `function_str` (`&str`): Die Transformation als String.
+    /// - `metadata` (`&RenderMetadata`): Beschreibung der Berechnung für PNG und JPEG.
+    ///
+    /// # Rückgabewert
+    /// Der Pfad, unter dem das Bild gespeichert wurde, bzw. `-`.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Format unbekannt ist oder das Schreiben fehlschlägt.
+    pub fn save(
+        &self,
+        img: &DynamicImage,
+        source: &str,
+        function_str: &str,
+        metadata: &RenderMetadata,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let (path, format) = self.target(source, function_str)?;
+        if self.is_stdout() {
+            let bytes = encode_image(img, format, self.quality, Some(metadata))?;
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&bytes)?;
+            stdout.flush()?;
+            return Ok(path);
+        }
+
+        create_parent_dir(&path)?;
+        fs::write(
+            &path,
+            encode_image(img, format, self.quality, Some(metadata))?,
+        )?;
+        Ok(path)
+    }
+}
+
+/// Bestimmt das Bildformat anhand der Dateiendung.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Endung keinem Format entspricht.
+fn format_from_path(path: &Path) -> Result<ImageFormat, String> {
+    ImageFormat::from_path(path)
+        .map_err(|_| format!("unknown output format for '{}'", path.display()))
+}
+
+/// Wandelt ein Bild in einen Pixeltyp um, den das Zielformat speichern kann.
+///
+/// Alphakanal und Bittiefe bleiben erhalten, soweit das Format sie unterstützt: JPEG
+/// speichert 8 Bit ohne Alphakanal, PNG und TIFF bis zu 16 Bit, OpenEXR ausschließlich
+/// Gleitkommawerte und alle übrigen Formate 8 Bit.
+///
+/// # Parameter
+/// - `img` (`&DynamicImage`): Das Bild.
+/// - `format` (`ImageFormat`): Das Zielformat.
+///
+/// # Rückgabewert
+/// Das unveränderte Bild, falls das Format es direkt speichern kann, sonst eine Kopie.
+fn convert_for_format(img: &DynamicImage, format: ImageFormat) -> Cow<'_, DynamicImage> {
+    let color = img.color();
+    let alpha = color.has_alpha();
+    let float = matches!(color, ColorType::Rgb32F | ColorType::Rgba32F);
+    let eight_bit = color.bytes_per_pixel() == color.channel_count();
+
+    let converted = match format {
+        ImageFormat::Jpeg if !matches!(color, ColorType::L8 | ColorType::Rgb8) => {
+            img.to_rgb8().into()
+        }
+        ImageFormat::Png | ImageFormat::Tiff if float && alpha => img.to_rgba16().into(),
+        ImageFormat::Png | ImageFormat::Tiff if float => img.to_rgb16().into(),
+        ImageFormat::OpenExr if !float && alpha => img.to_rgba32f().into(),
+        ImageFormat::OpenExr if !float => img.to_rgb32f().into(),
+        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Tiff | ImageFormat::OpenExr => {
+            return Cow::Borrowed(img)
+        }
+        _ if !eight_bit && alpha => img.to_rgba8().into(),
+        _ if !eight_bit => img.to_rgb8().into(),
+        _ => return Cow::Borrowed(img),
+    };
+    Cow::Owned(converted)
+}
+
+/// Kodiert ein Bild im angegebenen Format.
+///
+/// JPEG verwendet die angegebene Qualität. Der Pixeltyp wird nur angepasst, wenn das
+/// Format ihn nicht speichern kann, siehe [`convert_for_format`]. Metadaten werden nur
+/// in PNG und JPEG eingebettet.
+///
+/// # Parameter
+/// - `img` (`&DynamicImage`): Das Bild.
+/// - `format` (`ImageFormat`): Das Zielformat.
+/// - `quality` (`u8`): Die JPEG-Qualität zwischen 1 und 100.
+/// - `metadata` (`Option<&RenderMetadata>`): Optionale Beschreibung der Berechnung.
+///
+/// # Rückgabewert
+/// Die kodierten Bilddaten.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Format nicht geschrieben werden kann.
+pub fn encode_image(
+    img: &DynamicImage,
+    format: ImageFormat,
+    quality: u8,
+    metadata: Option<&RenderMetadata>,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let img = convert_for_format(img, format);
+    let mut bytes = Vec::new();
+    if format == ImageFormat::Jpeg {
+        img.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?;
+    } else {
+        img.write_to(&mut Cursor::new(&mut bytes), format)?;
+    }
+    match metadata {
+        Some(metadata) => metadata.embed(bytes, format),
+        None => Ok(bytes),
+    }
+}
+
+/// Speichert ein Bild im Format, das sich aus der Dateiendung ergibt.
+///
+/// Fehlende Verzeichnisse werden angelegt, siehe [`encode_image`].
+///
+/// # Parameter
+/// - `img` (`&DynamicImage`): Das Bild.
+/// - `path` (`&Path`): Der Zielpfad.
+/// - `quality` (`u8`): Die JPEG-Qualität zwischen 1 und 100.
+/// - `metadata` (`Option<&RenderMetadata>`): Optionale Beschreibung der Berechnung.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Endung keinem Format entspricht oder das Schreiben
+/// fehlschlägt.
+pub fn save_image(
+    img: &DynamicImage,
+    path: &Path,
+    quality: u8,
+    metadata: Option<&RenderMetadata>,
+) -> Result<(), Box<dyn Error>> {
+    let format = format_from_path(path)?;
+    create_parent_dir(path)?;
+    fs::write(path, encode_image(img, format, quality, metadata)?)?;
+    Ok(())
+}
+
+/// Die einmalig gelesene Standardeingabe, siehe [`stdin_bytes`].
+static STDIN: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Liest die Standardeingabe einmalig vollständig ein.
+///
+/// Weitere Aufrufe liefern dieselben Daten, sodass z. B. der Droste-Modus das Bild
+/// sowohl für die Maske als auch für die Transformation lesen kann.
+fn stdin_bytes() -> std::io::Result<&'static [u8]> {
+    if let Some(bytes) = STDIN.get() {
+        return Ok(bytes);
+    }
+    let mut bytes = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(STDIN.get_or_init(|| bytes))
+}
+
+/// Öffnet ein Bild aus einer Datei oder mit `-` aus der Standardeingabe.
+///
+/// Bei der Standardeingabe wird das Format anhand der ersten Bytes erkannt.
+///
+/// # Parameter
+/// - `path` (`&str`): Der Pfad oder `-`.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn das Bild nicht gelesen oder dekodiert werden kann.
+pub fn open_image(path: &str) -> Result<DynamicImage, Box<dyn Error>> {
+    if !is_stdio(path) {
+        return Ok(image::open(path)?);
+    }
+    let bytes = stdin_bytes()?;
+    let format =
+        image::guess_format(bytes).map_err(|_| "unrecognised image format on standard input")?;
+    Ok(image::load_from_memory_with_format(bytes, format)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_placeholders() {
+        let output = ImageOutput::new(Some("out/{stem}-{expr}.{ext}"), 90, None);
+        let path = output.path("./images/input/test.tif", "1 / (z + 1)");
+        assert_eq!(path, PathBuf::from("out/test-1div(z+1).tif"));
+
+        let path = output.path("julia", "z^2 + c");
+        assert_eq!(path, PathBuf::from("out/julia-z^2+c.png"));
+
+        let path = output.path(STDIO_PATH, "z");
+        assert_eq!(path, PathBuf::from("out/stdin-z.png"));
+        assert!(ImageOutput::new(Some(STDIO_PATH), 90, None).is_stdout());
+    }
+
+    #[test]
+    fn test_encode_image_formats_and_metadata() {
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(16, 8, |x, y| {
+            image::Rgb([(x * 16) as u8, (y * 32) as u8, 128])
+        }));
+        let metadata = RenderMetadata::new(None, Some("z^2"));
+        for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Tiff] {
+            let bytes = encode_image(&img, format, 90, Some(&metadata)).unwrap();
+            assert_eq!(image::guess_format(&bytes).unwrap(), format);
+            let decoded = image::load_from_memory_with_format(&bytes, format).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (16, 8));
+        }
+
+        let png = encode_image(&img, ImageFormat::Png, 90, Some(&metadata)).unwrap();
+        assert!(png.windows(3).any(|w| w == b"z^2"));
+        let plain = encode_image(&img, ImageFormat::Png, 90, None).unwrap();
+        assert!(!plain.windows(3).any(|w| w == b"z^2"));
+
+        let low = encode_image(&img, ImageFormat::Jpeg, 10, None).unwrap();
+        let high = encode_image(&img, ImageFormat::Jpeg, 100, None).unwrap();
+        assert!(low.len() < high.len());
+    }
+
+    #[test]
+    fn test_open_image_from_standard_input() {
+        let img = DynamicImage::new_rgba8(3, 2);
+        STDIN
+            .set(encode_image(&img, ImageFormat::Png, 90, None).unwrap())
+            .unwrap();
+        let opened = open_image(STDIO_PATH).unwrap();
+        assert_eq!((opened.width(), opened.height()), (3, 2));
+        assert_eq!(opened.color(), ColorType::Rgba8);
+        // Ein zweiter Aufruf liefert dasselbe Bild, z. B. für die Droste-Maske
+        assert_eq!(open_image(STDIO_PATH).unwrap(), opened);
+        assert!(is_stdio(STDIO_PATH) && !is_stdio("-.png"));
+    }
+
+    #[test]
+    fn test_unused_path_appends_counter() {
+        let root = std::env::temp_dir().join(format!("holo_unused_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("clip.mp4");
+        assert_eq!(unused_path(&path), path);
+        fs::write(&path, b"").unwrap();
+        assert_eq!(unused_path(&path), root.join("clip_2.mp4"));
+        fs::write(root.join("clip_2.mp4"), b"").unwrap();
+        assert_eq!(unused_path(&path), root.join("clip_3.mp4"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_save_creates_directories_and_rejects_unknown_formats() {
+        let root = std::env::temp_dir().join(format!("holo_output_{}", std::process::id()));
+        let img = DynamicImage::new_rgb8(4, 4);
+        for name in ["a.png", "b.jpg", "c.webp", "d.tiff", "e.exr"] {
+            let path = root.join("nested").join(name);
+            save_image(&img, &path, 80, None).unwrap();
+            assert!(path.exists(), "{}", name);
+        }
+        assert!(save_image(&img, &root.join("f.unknown"), 80, None).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_save_keeps_alpha_and_bit_depth() {
+        let root = std::env::temp_dir().join(format!("holo_depth_{}", std::process::id()));
+        let cases = [
+            (DynamicImage::new_rgba8(4, 4), "a.png", ColorType::Rgba8),
+            (DynamicImage::new_rgba16(4, 4), "b.png", ColorType::Rgba16),
+            (DynamicImage::new_rgb16(4, 4), "c.tiff", ColorType::Rgb16),
+            (DynamicImage::new_rgb32f(4, 4), "d.exr", ColorType::Rgb32F),
+            (DynamicImage::new_rgba16(4, 4), "e.webp", ColorType::Rgba8),
+            (DynamicImage::new_rgba32f(4, 4), "f.jpg", ColorType::Rgb8),
+        ];
+        for (img, name, expected) in cases {
+            let path = root.join(name);
+            save_image(&img, &path, 90, None).unwrap();
+            assert_eq!(image::open(&path).unwrap().color(), expected, "{}", name);
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+use image::{Rgb, RgbImage};
+
+/// Breite eines Zeichens der eingebetteten Schrift in Pixeln.
+pub const GLYPH_WIDTH: u32 = 5;
+
+/// Höhe eines Zeichens der eingebetteten Schrift in Pixeln.
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// Horizontaler Abstand zwischen zwei Zeichenanfängen.
+const ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+/// Vertikaler Abstand zwischen zwei Zeilenanfängen.
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+
+/// Innenabstand zwischen Text und Rand eines Hintergrundfelds.
+const PADDING: u32 = 3;
+
+/// Farbe für Fehlermeldungen.
+pub const ERROR_COLOR: Rgb<u8> = Rgb([255, 90, 90]);
+
+/// 5x7-Bitmapschrift für die druckbaren ASCII-Zeichen (`' '` bis `'~'`).
+///
+/// Jedes Zeichen besteht aus sieben Zeilen, in denen Bit 4 das linke Pixel ist.
+const FONT: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x04, 0x04, 0x04, 0x04, 0x00, 0x00, 0x04], // '!'
+    [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // '#'
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // '$'
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // '%'
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // '&'
+    [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // '('
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ')'
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // '*'
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ','
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // '.'
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // '/'
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // '0'
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // '1'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // '2'
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // '3'
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // '4'
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // '5'
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // '6'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // '7'
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // '8'
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ';'
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // '<'
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // '='
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // '>'
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E], // '@'
+    [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11], // 'A'
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // 'B'
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // 'C'
+    [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C], // 'D'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // 'E'
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // 'F'
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // 'G'
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // 'H'
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // 'I'
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // 'J'
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // 'K'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // 'L'
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // 'M'
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // 'N'
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // 'O'
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // 'P'
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // 'Q'
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // 'R'
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // 'S'
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // 'T'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // 'U'
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // 'V'
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // 'W'
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // 'X'
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // 'Y'
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // 'Z'
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // '['
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // '\\'
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // ']'
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // '_'
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F], // 'a'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E], // 'b'
+    [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E], // 'c'
+    [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F], // 'd'
+    [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E], // 'e'
+    [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08], // 'f'
+    [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E], // 'g'
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // 'h'
+    [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E], // 'i'
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C], // 'j'
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // 'k'
+    [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // 'l'
+    [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11], // 'm'
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // 'n'
+    [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E], // 'o'
+    [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10], // 'p'
+    [0x00, 0x00, 0x0D, 0x13, 0x0F, 0x01, 0x01], // 'q'
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // 'r'
+    [0x00, 0x00, 0x0E, 0x10, 0x0E, 0x01, 0x1E], // 's'
+    [0x08, 0x08, 0x1C, 0x08, 0x08, 0x09, 0x06], // 't'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0D], // 'u'
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04], // 'v'
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0A], // 'w'
+    [0x00, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11], // 'x'
+    [0x00, 0x00, 0x11, 0x11, 0x0F, 0x01, 0x0E], // 'y'
+    [0x00, 0x00, 0x1F, 0x02, 0x04, 0x08, 0x1F], // 'z'
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // '{'
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // '|'
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // '}'
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // '~'
+];
+
+/// Gibt das Bitmuster eines Zeichens zurück, unbekannte Zeichen werden als `?` dargestellt.
+fn glyph(c: char) -> &'static [u8; 7] {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    &FONT[index]
+}
+
+/// Wählt eine gut lesbare Schriftgröße für die Bildbreite (1 bis 640 Pixel, 2 bis 1280 usw.).
+pub fn text_scale(width: u32) -> u32 {
+    (width / 640).max(1)
+}
+
+/// Berechnet die Größe eines (mehrzeiligen) Textes.
+///
+/// # Parameter
+/// - `text` (`&str`): Der Text, Zeilen werden durch `\n` getrennt.
+/// - `scale` (`u32`): Vergrößerungsfaktor der Schrift.
+///
+/// # Rückgabewert
+/// Breite und Höhe in Pixeln.
+pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+    let lines = text.lines().count().max(1) as u32;
+    let columns = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u32;
+    (
+        (columns * ADVANCE).saturating_sub(1) * scale,
+        (lines * LINE_HEIGHT - (LINE_HEIGHT - GLYPH_HEIGHT)) * scale,
+    )
+}
+
+/// Zeichnet einen Text mit der eingebetteten Bitmapschrift.
+///
+/// Pixel außerhalb des Bildes werden abgeschnitten.
+///
+/// # Parameter
+/// - `img` (`&mut RgbImage`): Das Bild, in das gezeichnet wird.
+/// - `x` (`u32`): Linker Rand des Textes.
+/// - `y` (`u32`): Oberer Rand des Textes.
+/// - `text` (`&str`): Der Text, Zeilen werden durch `\n` getrennt.
+/// - `color` (`Rgb<u8>`): Die Schriftfarbe.
+/// - `scale` (`u32`): Vergrößerungsfaktor der Schrift.
+pub fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str, color: Rgb<u8>, scale: u32) {
+    for (row, line) in text.lines().enumerate() {
+        let top = y + row as u32 * LINE_HEIGHT * scale;
+        for (column, c) in line.chars().enumerate() {
+            let left = x + column as u32 * ADVANCE * scale;
+            for (dy, bits) in glyph(c).iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if bits & (0x10 >> dx) != 0 {
+                        fill_rect(
+                            img,
+                            left + dx * scale,
+                            top + dy as u32 * scale,
+                            scale,
+                            scale,
+                            |_| color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Zeichnet einen Text auf einem abgedunkelten Hintergrundfeld.
+///
+/// # Parameter
+/// - `img` (`&mut RgbImage`): Das Bild, in das gezeichnet wird.
+/// - `x` (`u32`): Linker Rand des Feldes.
+/// - `y` (`u32`): Oberer Rand des Feldes.
+/// - `text` (`&str`): Der Text, Zeilen werden durch `\n` getrennt.
+/// - `color` (`Rgb<u8>`): Die Schriftfarbe.
+/// - `scale` (`u32`): Vergrößerungsfaktor der Schrift.
+///
+/// # Rückgabewert
+/// Breite und Höhe des Feldes in Pixeln.
+pub fn draw_panel(
+    img: &mut RgbImage,
+    x: u32,
+    y: u32,
+    text: &str,
+    color: Rgb<u8>,
+    scale: u32,
+) -> (u32, u32) {
+    let (width, height) = panel_size(text, scale);
+    fill_rect(img, x, y, width, height, |p| Rgb(p.0.map(|v| v / 3)));
+    let padding = PADDING * scale;
+    draw_text(img, x + padding, y + padding, text, color, scale);
+    (width, height)
+}
+
+/// Berechnet die Größe eines Feldes aus [`draw_panel`].
+pub fn panel_size(text: &str, scale: u32) -> (u32, u32) {
+    let (width, height) = text_size(text, scale);
+    (width + 2 * PADDING * scale, height + 2 * PADDING * scale)
+}
+
+/// Füllt ein Rechteck, dessen Pixel außerhalb des Bildes abgeschnitten werden.
+pub fn fill_rect(
+    img: &mut RgbImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color: impl Fn(&Rgb<u8>) -> Rgb<u8>,
+) {
+    let right = x.saturating_add(width).min(img.width());
+    let bottom = y.saturating_add(height).min(img.height());
+    for py in y..bottom {
+        for px in x..right {
+            let pixel = img.get_pixel_mut(px, py);
+            *pixel = color(pixel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_is_drawn_and_clipped() {
+        assert_eq!(text_size("ab", 1), (11, 7));
+        assert_eq!(text_size("a\nbcd", 2), (34, 32));
+
+        let white = Rgb([255, 255, 255]);
+        let mut img = RgbImage::new(20, 12);
+        draw_text(&mut img, 1, 1, "T?", white, 1);
+        // Oberer Balken des „T“, dessen Stamm und die leere Spalte dazwischen
+        assert!((1..6).all(|x| img.get_pixel(x, 1) == &white));
+        assert_eq!(img.get_pixel(3, 7), &white);
+        assert_eq!(img.get_pixel(6, 1), &Rgb([0, 0, 0]));
+        assert_eq!(glyph('ä'), glyph('?'));
+
+        // Über den Rand ragender Text wird abgeschnitten statt zu paniken
+        let (width, height) = draw_panel(&mut img, 10, 0, "Error", ERROR_COLOR, 2);
+        assert_eq!((width, height), (70, 26));
+        assert!(img.pixels().any(|p| p == &ERROR_COLOR));
+    }
+}
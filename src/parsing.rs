@@ -8,6 +8,7 @@ use nom::{
     IResult,
 };
 use num_complex::{Complex, ComplexFloat};
+use std::fmt;
 use std::str::FromStr;
 
 /// Typalias für eine Funktionsclosure, die einen komplexen Wert transformiert.
@@ -16,11 +17,24 @@ type FComp = Box<dyn Fn(Complex<f64>) -> Complex<f64>>;
 /// Ergebnis-Typ für `Expr::parse`, um zwischen Erfolg und Fehlern zu unterscheiden.
 type ExprResult<T> = Result<T, String>;
 
+/// Werte der freien Parameter eines Ausdrucks neben der Variablen `z`.
+///
+/// # Felder
+/// - `c` (`Complex<f64>`): Der Wert des Parameters `c`.
+/// - `t` (`f64`): Der Wert der (reellen) Zeitvariablen `t`, z. B. für Animationen.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Parameters {
+    pub c: Complex<f64>,
+    pub t: f64,
+}
+
 /// Enum, das mathematische Ausdrücke beschreibt.
 ///
 /// # Varianten
 /// - `Number(f64)`: Eine Zahl.
 /// - `Variable`: Die Variable `z`, die typischerweise für komplexe Werte steht.
+/// - `Parameter`: Der Parameter `c`, z. B. für Julia- und Mandelbrot-Mengen.
+/// - `Time`: Die Zeitvariable `t`, z. B. für Animationen.
 /// - `UnaryOp { op, expr }`: Ein unärer Operator, z. B. `-z`.
 /// - `BinaryOp { left, op, right }`: Ein binärer Operator, z. B. `z + 1`.
 /// - `Function { func, expr }`: Eine mathematische Funktion, z. B. `sin(z)`.
@@ -28,6 +42,8 @@ type ExprResult<T> = Result<T, String>;
 pub enum Expr {
     Number(f64),
     Variable,
+    Parameter,
+    Time,
     UnaryOp {
         op: char,
         expr: Box<Expr>,
@@ -120,6 +136,8 @@ fn parse_primary(input: &str) -> IResult<&str, Expr> {
         parse_number,
         parse_variable,
         parse_function,
+        parse_parameter,
+        parse_time,
         delimited(ws(char('(')), parse_expression, ws(char(')'))),
         parse_unary,
     ))(input)
@@ -140,16 +158,17 @@ fn parse_unary(input: &str) -> IResult<&str, Expr> {
 
 /// Parst mathematische Funktionen, z. B. `sin(z)`.
 fn parse_function(input: &str) -> IResult<&str, Expr> {
+    // Längere Namen zuerst, damit z. B. `sinh` nicht als `sin` erkannt wird
     let (input, func) = ws(alt((
+        tag("sinh"),
+        tag("cosh"),
+        tag("tanh"),
         tag("sin"),
         tag("cos"),
         tag("tan"),
         tag("exp"),
         tag("log"),
         tag("sqrt"),
-        tag("sinh"),
-        tag("cosh"),
-        tag("tanh"),
         tag("asin"),
         tag("acos"),
         tag("atan"),
@@ -171,6 +190,16 @@ fn parse_variable(input: &str) -> IResult<&str, Expr> {
     map(ws(tag("z")), |_| Expr::Variable)(input)
 }
 
+/// Parst den Parameter `c`.
+fn parse_parameter(input: &str) -> IResult<&str, Expr> {
+    map(ws(tag("c")), |_| Expr::Parameter)(input)
+}
+
+/// Parst die Zeitvariable `t`.
+fn parse_time(input: &str) -> IResult<&str, Expr> {
+    map(ws(tag("t")), |_| Expr::Time)(input)
+}
+
 /// Parst eine Zahl (z. B. `3.14`, `-2`).
 fn parse_number(input: &str) -> IResult<&str, Expr> {
     let (input, num_str) = recognize(pair(
@@ -181,6 +210,72 @@ fn parse_number(input: &str) -> IResult<&str, Expr> {
     Ok((input, Expr::Number(num)))
 }
 
+/// Erzeugt einen binären Ausdruck `left op right`.
+fn binary(left: Expr, op: char, right: Expr) -> Expr {
+    Expr::BinaryOp {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    }
+}
+
+/// Erzeugt den Funktionsaufruf `func(expr)`.
+fn function(func: &str, expr: Expr) -> Expr {
+    Expr::Function {
+        func: func.to_string(),
+        expr: Box::new(expr),
+    }
+}
+
+/// Summe zweier Ausdrücke, wobei Summanden `0` entfallen.
+fn add(left: Expr, right: Expr) -> Expr {
+    match (&left, &right) {
+        (Expr::Number(l), _) if *l == 0.0 => right,
+        (_, Expr::Number(r)) if *r == 0.0 => left,
+        _ => binary(left, '+', right),
+    }
+}
+
+/// Differenz zweier Ausdrücke, wobei ein Subtrahend `0` entfällt.
+fn sub(left: Expr, right: Expr) -> Expr {
+    match &right {
+        Expr::Number(r) if *r == 0.0 => left,
+        _ => binary(left, '-', right),
+    }
+}
+
+/// Produkt zweier Ausdrücke, vereinfacht für Faktoren `0` und `1`.
+fn mul(left: Expr, right: Expr) -> Expr {
+    match (&left, &right) {
+        (Expr::Number(l), _) | (_, Expr::Number(l)) if *l == 0.0 => Expr::Number(0.0),
+        (Expr::Number(l), _) if *l == 1.0 => right,
+        (_, Expr::Number(r)) if *r == 1.0 => left,
+        _ => binary(left, '*', right),
+    }
+}
+
+/// Negation eines Ausdrucks.
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Number(n) => Expr::Number(-n),
+        Expr::UnaryOp { op: '-', expr } => *expr,
+        other => Expr::UnaryOp {
+            op: '-',
+            expr: Box::new(other),
+        },
+    }
+}
+
+/// Kehrwert `1 / expr`.
+fn reciprocal(expr: Expr) -> Expr {
+    binary(Expr::Number(1.0), '/', expr)
+}
+
+/// Der Ausdruck `1 - expr^2`.
+fn one_minus_square(expr: Expr) -> Expr {
+    binary(Expr::Number(1.0), '-', binary(expr, '^', Expr::Number(2.0)))
+}
+
 impl Expr {
     /// Parst einen String in einen mathematischen Ausdruck (`Expr`).
     pub fn parse(input: &str) -> ExprResult<Self> {
@@ -197,31 +292,41 @@ impl Expr {
     }
 
     /// Evaluierung des Ausdrucks für eine gegebene komplexe Zahl `z`.
+    ///
+    /// Parameter wie `c` und `t` werden dabei mit `0` belegt.
     pub fn evaluate(&self, z: Complex<f64>) -> Complex<f64> {
+        self.evaluate_with(z, &Parameters::default())
+    }
+
+    /// Evaluierung des Ausdrucks für `z` mit den angegebenen Parameterwerten.
+    pub fn evaluate_with(&self, z: Complex<f64>, params: &Parameters) -> Complex<f64> {
         match self {
             Expr::Number(n) => Complex::new(*n, 0.0),
             Expr::Variable => z,
+            Expr::Parameter => params.c,
+            Expr::Time => Complex::new(params.t, 0.0),
             Expr::UnaryOp { op, expr } => {
-                let val = expr.evaluate(z);
+                let val = expr.evaluate_with(z, params);
                 match *op {
                     '-' => -val,
                     _ => val,
                 }
             }
             Expr::BinaryOp { left, op, right } => {
-                let left_val = left.evaluate(z);
-                let right_val = right.evaluate(z);
+                let left_val = left.evaluate_with(z, params);
+                let right_val = right.evaluate_with(z, params);
                 match *op {
                     '+' => left_val + right_val,
                     '-' => left_val - right_val,
                     '*' => left_val * right_val,
                     '/' => left_val / right_val,
-                    '^' => left_val.powf(right_val.re), // assuming real exponentiation
-                    _ => left_val,                      // Or handle other operations
+                    '^' if right_val.im == 0.0 => left_val.powf(right_val.re),
+                    '^' => left_val.powc(right_val),
+                    _ => left_val, // Or handle other operations
                 }
             }
             Expr::Function { func, expr } => {
-                let val = expr.evaluate(z);
+                let val = expr.evaluate_with(z, params);
                 match func.as_str() {
                     "sin" => val.sin(),
                     "cos" => val.cos(),
@@ -236,16 +341,110 @@ impl Expr {
                     "acos" => val.acos(),
                     "atan" => val.atan(),
                     "abs" => Complex::new(val.abs(), 0.0),
+                    "conj" => val.conj(),
                     _ => val, // Default to the expression itself
                 }
             }
         }
     }
 
+    /// Prüft, ob der Ausdruck von der Variablen `z` abhängt.
+    pub fn depends_on_z(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Parameter | Expr::Time => false,
+            Expr::Variable => true,
+            Expr::UnaryOp { expr, .. } | Expr::Function { expr, .. } => expr.depends_on_z(),
+            Expr::BinaryOp { left, right, .. } => left.depends_on_z() || right.depends_on_z(),
+        }
+    }
+
+    /// Bildet die symbolische Ableitung des Ausdrucks nach `z`.
+    ///
+    /// Triviale Terme (Multiplikation mit `0` oder `1`, Addition von `0`) werden
+    /// dabei direkt vereinfacht.
+    ///
+    /// # Rückgabewert
+    /// Die Ableitung als neuer Ausdruck oder `None`, wenn der Ausdruck nicht holomorph
+    /// ableitbar ist (`abs`, `conj`).
+    pub fn derivative(&self) -> Option<Expr> {
+        match self {
+            Expr::Number(_) | Expr::Parameter | Expr::Time => Some(Expr::Number(0.0)),
+            Expr::Variable => Some(Expr::Number(1.0)),
+            Expr::UnaryOp { op, expr } => {
+                let inner = expr.derivative()?;
+                Some(match *op {
+                    '-' => negate(inner),
+                    _ => inner,
+                })
+            }
+            Expr::BinaryOp { left, op, right } => {
+                let dl = left.derivative()?;
+                let dr = right.derivative()?;
+                match *op {
+                    '+' => Some(add(dl, dr)),
+                    '-' => Some(sub(dl, dr)),
+                    '*' => Some(add(mul(dl, (**right).clone()), mul((**left).clone(), dr))),
+                    '/' => Some(binary(
+                        sub(mul(dl, (**right).clone()), mul((**left).clone(), dr)),
+                        '/',
+                        binary((**right).clone(), '^', Expr::Number(2.0)),
+                    )),
+                    '^' if !right.depends_on_z() => {
+                        let exponent = (**right).clone();
+                        let reduced = binary(exponent.clone(), '-', Expr::Number(1.0));
+                        Some(mul(
+                            mul(exponent, binary((**left).clone(), '^', reduced)),
+                            dl,
+                        ))
+                    }
+                    // Allgemeine Potenzregel (a^b)' = a^b · (b' · log(a) + b · a' / a)
+                    '^' => {
+                        let quotient = match dl {
+                            Expr::Number(0.0) => Expr::Number(0.0),
+                            dl => binary(dl, '/', (**left).clone()),
+                        };
+                        Some(mul(
+                            self.clone(),
+                            add(
+                                mul(dr, function("log", (**left).clone())),
+                                mul((**right).clone(), quotient),
+                            ),
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            Expr::Function { func, expr } => {
+                let inner = (**expr).clone();
+                let outer = match func.as_str() {
+                    "sin" => function("cos", inner),
+                    "cos" => negate(function("sin", inner)),
+                    "tan" => reciprocal(binary(function("cos", inner), '^', Expr::Number(2.0))),
+                    "exp" => function("exp", inner),
+                    "log" => reciprocal(inner),
+                    "sqrt" => reciprocal(mul(Expr::Number(2.0), function("sqrt", inner))),
+                    "sinh" => function("cosh", inner),
+                    "cosh" => function("sinh", inner),
+                    "tanh" => reciprocal(binary(function("cosh", inner), '^', Expr::Number(2.0))),
+                    "asin" => reciprocal(function("sqrt", one_minus_square(inner))),
+                    "acos" => negate(reciprocal(function("sqrt", one_minus_square(inner)))),
+                    "atan" => reciprocal(add(
+                        Expr::Number(1.0),
+                        binary(inner, '^', Expr::Number(2.0)),
+                    )),
+                    _ => return None,
+                };
+                Some(mul(outer, expr.derivative()?))
+            }
+        }
+    }
+
     pub fn to_wgsl(&self) -> String {
         match self {
             Expr::Number(n) => format!("{:.1}", n), // Floating-point formatting
             Expr::Variable => "coord".to_string(), // Assuming `coord` is a vec2<f32> passed in the shader
+            Expr::Parameter => "c".to_string(),
+            Expr::Time => "t".to_string(),
             Expr::UnaryOp { op, expr } => format!("({}{})", op, expr.to_wgsl()),
             Expr::BinaryOp { left, op, right } => {
                 format!("({} {} {})", left.to_wgsl(), op, right.to_wgsl())
@@ -273,6 +472,68 @@ impl Expr {
     }
 }
 
+/// Bindungsstärke eines Ausdrucks beim Ausgeben, höhere Werte binden stärker.
+///
+/// Entspricht der Grammatik des Parsers: Addition und Subtraktion, Multiplikation und
+/// Division, unäre Operatoren, Potenzen und schließlich Zahlen, Variablen und Funktionen.
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::BinaryOp { op: '+' | '-', .. } => 1,
+        Expr::BinaryOp { op: '*' | '/', .. } => 2,
+        Expr::UnaryOp { .. } => 3,
+        Expr::BinaryOp { .. } => 4,
+        _ => 5,
+    }
+}
+
+/// Gibt an, ob die Ausgabe eines Ausdrucks mit einer Zahl beginnt.
+fn starts_with_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) => true,
+        Expr::BinaryOp { left, .. } => starts_with_number(left),
+        _ => false,
+    }
+}
+
+/// Gibt den Ausdruck in kanonischer Form aus.
+///
+/// Operatoren werden durch Leerzeichen getrennt und Klammern nur gesetzt, wo sie nötig
+/// sind, sodass gleichwertige Eingaben wie `z^2+c` und `(z ^ 2) + c` denselben String
+/// ergeben. `Expr::parse` liest die Ausgabe wieder ein.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Variable => write!(f, "z"),
+            Expr::Parameter => write!(f, "c"),
+            Expr::Time => write!(f, "t"),
+            // Eine Potenz oder eine führende Zahl würde sonst als negative Basis gelesen,
+            // `-(2 ^ z)` also als `(-2) ^ z`
+            Expr::UnaryOp { op, expr } if precedence(expr) <= 4 || starts_with_number(expr) => {
+                write!(f, "{}({})", op, expr)
+            }
+            Expr::UnaryOp { op, expr } => write!(f, "{}{}", op, expr),
+            Expr::BinaryOp { left, op, right } => {
+                let own = precedence(self);
+                // Alle Operatoren sind linksassoziativ, rechts sind daher auch bei
+                // gleicher Bindungsstärke Klammern nötig
+                if precedence(left) < own {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", op)?;
+                if precedence(right) <= own {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+            Expr::Function { func, expr } => write!(f, "{}({})", func, expr),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,6 +553,37 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn test_display_is_canonical_and_parses_back() {
+        let cases = [
+            ("z^2+c", "z ^ 2 + c"),
+            ("(z ^ 2) + (c)", "z ^ 2 + c"),
+            ("(z+1)/(z-1)", "(z + 1) / (z - 1)"),
+            ("z-(z-1)", "z - (z - 1)"),
+            ("-z^2", "-(z ^ 2)"),
+            ("-(2^z)", "-(2 ^ z)"),
+            ("-2^z", "-2 ^ z"),
+            ("-(2*z)", "-(2 * z)"),
+            ("(-z)^2", "(-z) ^ 2"),
+            ("-(z+1)", "-(z + 1)"),
+            ("2.5*sin(z/t)", "2.5 * sin(z / t)"),
+        ];
+        for (input, canonical) in cases {
+            let expr = Expr::parse(input).unwrap();
+            assert_eq!(expr.to_string(), canonical, "{}", input);
+            let reparsed = Expr::parse(canonical).unwrap();
+            assert_eq!(reparsed, expr, "{}", input);
+            for z in complex_num_tests() {
+                let (a, b) = (expr.evaluate(z), reparsed.evaluate(z));
+                assert!(a == b || (a.is_nan() && b.is_nan()), "{}", input);
+            }
+        }
+        assert_ne!(
+            Expr::parse("-(2^z)").unwrap().to_string(),
+            Expr::parse("-2^z").unwrap().to_string()
+        );
+    }
+
     #[test]
     fn test_simple_number_expressions() {
         assert_eq!(Expr::parse("5").unwrap(), Expr::Number(5.0));
@@ -299,6 +591,40 @@ mod tests {
         assert_eq!(Expr::parse("z").unwrap(), Expr::Variable);
     }
 
+    #[test]
+    fn test_parameter_expressions() {
+        assert_eq!(
+            Expr::parse("z ^ 2 + c").unwrap(),
+            Expr::BinaryOp {
+                left: Box::new(Expr::BinaryOp {
+                    left: Box::new(Expr::Variable),
+                    op: '^',
+                    right: Box::new(Expr::Number(2.0)),
+                }),
+                op: '+',
+                right: Box::new(Expr::Parameter),
+            }
+        );
+        assert_eq!(
+            Expr::parse("cosh(z)").unwrap(),
+            Expr::Function {
+                func: "cosh".to_string(),
+                expr: Box::new(Expr::Variable),
+            }
+        );
+
+        let params = Parameters {
+            c: Complex::new(0.5, -1.0),
+            t: 0.25,
+        };
+        let expression = Expr::parse("z * c").unwrap();
+        let z = Complex::new(2.0, 1.0);
+        assert_eq!(expression.evaluate_with(z, &params), z * params.c);
+
+        let expression = Expr::parse("z + t * tan(z)").unwrap();
+        assert_eq!(expression.evaluate_with(z, &params), z + params.t * z.tan());
+    }
+
     #[test]
     fn test_simple_arithmetic_expressions() {
         assert_eq!(
@@ -401,6 +727,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_derivative_matches_difference_quotient() {
+        let h = Complex::new(1e-6, 0.0);
+        for input in [
+            "z^3 - 2*z",
+            "sin(z) * exp(z)",
+            "1 / (z - 2)",
+            "log(z) + sqrt(z)",
+            "2^z",
+            "z^z",
+            "(z + 1)^(2*z)",
+        ] {
+            let expression = Expr::parse(input).unwrap();
+            let derivative = expression.derivative().unwrap();
+            for &z in complex_num_tests().iter().skip(1) {
+                let expected =
+                    (expression.evaluate(z + h) - expression.evaluate(z - h)) / (2.0 * h);
+                let result = derivative.evaluate(z);
+                assert!(
+                    (result - expected).norm() < 1e-4 * (1.0 + expected.norm()),
+                    "Failed for {} at z = {:?}",
+                    input,
+                    z
+                );
+            }
+        }
+        assert_eq!(Expr::parse("conj(z)").unwrap().derivative(), None);
+    }
+
     #[test]
     fn test_expression_evaluation() -> Result<(), Box<dyn std::error::Error>> {
         let complex_numbers = complex_num_tests();
@@ -0,0 +1,536 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::Scope;
+use std::time::{Duration, Instant};
+
+use image::RgbImage;
+
+use crate::holo::{transform_bgr_frame, transform_frame, HolomorphicLookup};
+use crate::hud::smooth;
+
+/// Anzahl der Frames, die zwischen zwei Stufen warten dürfen, bevor neue Frames verworfen werden.
+pub const CHANNEL_CAPACITY: usize = 2;
+
+/// Wartezeit nach einem Frame, das nicht umgewandelt werden konnte. Sie verdoppelt sich mit
+/// jedem weiteren Fehlschlag bis [`MAX_MISS_BACKOFF`], damit z. B. eine getrennte Kamera den
+/// Erfassungs-Thread nicht in eine Endlosschleife schickt.
+const MISS_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Längste Wartezeit nach wiederholten Fehlschlägen.
+const MAX_MISS_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Liest die Pixel eines Frames als BGR-Bytes ohne Lücken, z. B. mit
+/// [`crate::webcam::bgr_bytes`]. `None` bedeutet, dass das Frame keine Pixel hat.
+pub type BgrReader<T> = fn(&T) -> Option<Cow<'_, [u8]>>;
+
+/// Ein erfasstes Frame auf dem Weg zur Transformation.
+struct Captured<T> {
+    image: CapturedImage<T>,
+    captured: Instant,
+    capture: Duration,
+    convert: Duration,
+}
+
+/// Die Pixel eines erfassten Frames.
+enum CapturedImage<T> {
+    /// In ein `RgbImage` umgewandelt.
+    Rgb(RgbImage),
+    /// Unverändert für die direkte Ausgabe, siehe [`Pipeline::set_direct`].
+    Raw(T),
+}
+
+/// Die Pixel eines fertig transformierten Frames.
+///
+/// # Varianten
+/// - `Images { source, transformed }`: Das unveränderte und das transformierte Frame.
+/// - `Buffer(Vec<u32>)`: Nur das transformierte Frame als `0RGB`-Puffer für das Fenster,
+///   siehe [`Pipeline::set_direct`].
+pub enum FramePixels {
+    Images {
+        source: RgbImage,
+        transformed: RgbImage,
+    },
+    Buffer(Vec<u32>),
+}
+
+/// Ein fertig transformiertes Frame der [`Pipeline`].
+///
+/// # Felder
+/// - `pixels` (`FramePixels`): Die Pixel des Frames.
+/// - `lookup` (`Arc<HolomorphicLookup>`): Die Tabelle, mit der das Frame transformiert wurde.
+/// - `captured` (`Instant`): Beginn der Erfassung, Bezugspunkt der Latenz.
+/// - `capture` (`Duration`): Dauer der Erfassung.
+/// - `convert` (`Duration`): Dauer der Umwandlung in ein `RgbImage`, bei direkter Ausgabe null.
+/// - `apply` (`Duration`): Dauer der Transformation.
+pub struct PipelineFrame {
+    pub pixels: FramePixels,
+    pub lookup: Arc<HolomorphicLookup>,
+    pub captured: Instant,
+    pub capture: Duration,
+    pub convert: Duration,
+    pub apply: Duration,
+}
+
+impl PipelineFrame {
+    /// Gibt das unveränderte Frame zurück, bei direkter Ausgabe gibt es keines.
+    pub fn source(&self) -> Option<&RgbImage> {
+        match &self.pixels {
+            FramePixels::Images { source, .. } => Some(source),
+            FramePixels::Buffer(_) => None,
+        }
+    }
+
+    /// Gibt das transformierte Frame als `RgbImage` zurück, ein Puffer wird dafür umgewandelt.
+    pub fn transformed(&self) -> Cow<'_, RgbImage> {
+        match &self.pixels {
+            FramePixels::Images { transformed, .. } => Cow::Borrowed(transformed),
+            FramePixels::Buffer(buffer) => {
+                let (width, height) = (self.lookup.width, self.lookup.height);
+                Cow::Owned(RgbImage::from_fn(width, height, |x, y| {
+                    let [_, r, g, b] = buffer[(y * width + x) as usize].to_be_bytes();
+                    image::Rgb([r, g, b])
+                }))
+            }
+        }
+    }
+}
+
+/// Eine Bildquelle für die [`Pipeline`] mit ihrer Bildrate.
+///
+/// # Felder
+/// - `capture` (`C`): Liest das nächste Frame, siehe [`Pipeline::spawn`].
+/// - `convert` (`V`): Wandelt ein Frame in ein `RgbImage` um.
+/// - `bgr` (`Option<BgrReader<T>>`): Liest die BGR-Bytes eines Frames für die direkte Ausgabe,
+///   ohne Angabe werden alle Frames mit `convert` umgewandelt.
+/// - `fps` (`f64`): Die Bildrate der Quelle, z. B. für Aufzeichnungen.
+/// - `frame_time` (`Option<Duration>`): Mindestabstand zwischen zwei Erfassungen, ohne Angabe
+///   gibt die Quelle den Takt vor.
+/// - `name` (`String`): Name der Quelle für Dateinamen, z. B. `webcam` oder der Videopfad.
+pub struct FrameSource<T, C, V> {
+    pub capture: C,
+    pub convert: V,
+    pub bgr: Option<BgrReader<T>>,
+    pub fps: f64,
+    pub frame_time: Option<Duration>,
+    pub name: String,
+}
+
+/// Kennzahlen einer [`Pipeline`].
+///
+/// # Felder
+/// - `fps` (`f64`): Gleitendes Mittel der ausgegebenen Frames pro Sekunde.
+/// - `latency` (`f64`): Gleitendes Mittel der Zeit von der Erfassung bis zur Ausgabe in Sekunden.
+/// - `captured` (`u64`): Anzahl der erfassten Frames.
+/// - `delivered` (`u64`): Anzahl der ausgegebenen Frames.
+/// - `dropped` (`u64`): Anzahl der verworfenen Frames, weil eine spätere Stufe nicht nachkam
+///   oder das Frame nicht umgewandelt bzw. transformiert werden konnte.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PipelineStats {
+    pub fps: f64,
+    pub latency: f64,
+    pub captured: u64,
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+/// Zähler, die von den Threads der Pipeline gemeinsam fortgeschrieben werden.
+#[derive(Default)]
+struct Counters {
+    stop: AtomicBool,
+    direct: AtomicBool,
+    captured: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Verarbeitet die Frames einer Videoquelle in drei überlappenden Stufen.
+///
+/// Ein Thread erfasst und wandelt die Frames um, ein zweiter wendet die Lookup-Tabelle an,
+/// der aufrufende Thread holt die fertigen Frames mit [`Pipeline::latest`] ab und zeigt sie
+/// an. Die Stufen sind durch Kanäle mit [`CHANNEL_CAPACITY`] Plätzen verbunden; kommt eine
+/// Stufe nicht nach, werden Frames verworfen statt sich aufzustauen, sodass die Latenz
+/// begrenzt bleibt. Die Bildrate ergibt sich damit aus der langsamsten Stufe statt aus der
+/// Summe aller Stufen.
+///
+/// Die Threads enden, sobald die Quelle erschöpft ist oder die `Pipeline` verworfen wird.
+///
+/// # Felder
+/// - `lookups` (`Sender<Arc<HolomorphicLookup>>`): Neue Tabellen für den Transformations-Thread.
+/// - `frames` (`Receiver<PipelineFrame>`): Die transformierten Frames.
+/// - `counters` (`Arc<Counters>`): Gemeinsame Zähler und das Stoppsignal.
+/// - `stats` (`PipelineStats`): Die zuletzt berechneten Kennzahlen.
+/// - `last_frame` (`Option<Instant>`): Ausgabezeitpunkt des letzten Frames.
+/// - `finished` (`bool`): Ob die Quelle erschöpft ist und alle Frames abgeholt wurden.
+pub struct Pipeline {
+    lookups: Sender<Arc<HolomorphicLookup>>,
+    frames: Receiver<PipelineFrame>,
+    counters: Arc<Counters>,
+    stats: PipelineStats,
+    last_frame: Option<Instant>,
+    finished: bool,
+}
+
+impl Pipeline {
+    /// Startet die Erfassungs- und Transformations-Threads im angegebenen Scope.
+    ///
+    /// # Parameter
+    /// - `scope` (`&Scope`): Der Scope aus `std::thread::scope`, der die Threads am Ende einsammelt.
+    /// - `capture` (`impl FnMut() -> Option<T>`): Liest das nächste Frame, `None` beendet die
+    ///   Pipeline (z. B. am Ende einer Videodatei).
+    /// - `convert` (`impl Fn(&T) -> Option<RgbImage>`): Wandelt ein Frame um, `None` verwirft
+    ///   es. Nach wiederholten Fehlschlägen wartet der Erfassungs-Thread zunehmend länger.
+    /// - `bgr` (`Option<BgrReader<T>>`): Liest die BGR-Bytes eines Frames für die direkte
+    ///   Ausgabe, siehe [`Pipeline::set_direct`].
+    /// - `lookup` (`Arc<HolomorphicLookup>`): Die anfängliche Lookup-Tabelle.
+    /// - `frame_time` (`Option<Duration>`): Mindestabstand zwischen zwei Erfassungen, um
+    ///   Videodateien in Echtzeit abzuspielen.
+    pub fn spawn<'scope, 'env, T, C, V>(
+        scope: &'scope Scope<'scope, 'env>,
+        mut capture: C,
+        convert: V,
+        bgr: Option<BgrReader<T>>,
+        lookup: Arc<HolomorphicLookup>,
+        frame_time: Option<Duration>,
+    ) -> Self
+    where
+        T: Send + 'scope,
+        C: FnMut() -> Option<T> + Send + 'scope,
+        V: Fn(&T) -> Option<RgbImage> + Send + 'scope,
+    {
+        let counters = Arc::new(Counters::default());
+        let (captured_tx, captured_rx) = mpsc::sync_channel::<Captured<T>>(CHANNEL_CAPACITY);
+        // Rohdaten passen nur ohne Skalierung zur Tabelle
+        let frame_bytes = lookup.width as usize * lookup.height as usize * 3;
+        let (frames_tx, frames) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let (lookups, lookups_rx) = mpsc::channel();
+
+        let shared = Arc::clone(&counters);
+        scope.spawn(move || {
+            let mut next = Instant::now();
+            let mut misses = 0;
+            while !shared.stop.load(Ordering::Relaxed) {
+                let captured = Instant::now();
+                let Some(raw) = capture() else {
+                    break; // Quelle erschöpft
+                };
+                let capture = captured.elapsed();
+                let started = Instant::now();
+                shared.captured.fetch_add(1, Ordering::Relaxed);
+                let direct = bgr.filter(|_| shared.direct.load(Ordering::Relaxed));
+                let image = match direct {
+                    Some(bgr) if bgr(&raw).is_some_and(|bytes| bytes.len() == frame_bytes) => {
+                        Some(CapturedImage::Raw(raw))
+                    }
+                    _ => convert(&raw).map(CapturedImage::Rgb),
+                };
+                let Some(image) = image else {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    std::thread::sleep((MISS_BACKOFF * (1 << misses)).min(MAX_MISS_BACKOFF));
+                    misses = (misses + 1).min(7);
+                    continue;
+                };
+                misses = 0;
+                let frame = Captured {
+                    image,
+                    captured,
+                    capture,
+                    convert: started.elapsed(),
+                };
+                if !offer(&captured_tx, frame, &shared) {
+                    break;
+                }
+                if let Some(frame_time) = frame_time {
+                    // Liegt die Wiedergabe zurück, nicht aufholen, sondern im Takt weiterlaufen
+                    next = (next + frame_time).max(Instant::now());
+                    std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                }
+            }
+        });
+
+        let shared = Arc::clone(&counters);
+        scope.spawn(move || {
+            let mut lookup = lookup;
+            while let Ok(frame) = captured_rx.recv() {
+                if let Some(latest) = lookups_rx.try_iter().last() {
+                    lookup = latest;
+                }
+                let started = Instant::now();
+                let pixels = match frame.image {
+                    CapturedImage::Rgb(source) => {
+                        transform_frame(&lookup, &source).map(|transformed| FramePixels::Images {
+                            source,
+                            transformed,
+                        })
+                    }
+                    CapturedImage::Raw(raw) => bgr
+                        .and_then(|bgr| transform_bgr_frame(&lookup, &bgr(&raw)?))
+                        .map(FramePixels::Buffer),
+                };
+                let Some(pixels) = pixels else {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                };
+                let frame = PipelineFrame {
+                    pixels,
+                    lookup: Arc::clone(&lookup),
+                    captured: frame.captured,
+                    capture: frame.capture,
+                    convert: frame.convert,
+                    apply: started.elapsed(),
+                };
+                if !offer(&frames_tx, frame, &shared) {
+                    break;
+                }
+            }
+        });
+
+        Pipeline {
+            lookups,
+            frames,
+            counters,
+            stats: PipelineStats::default(),
+            last_frame: None,
+            finished: false,
+        }
+    }
+
+    /// Schaltet die direkte Ausgabe für alle folgenden Frames ein oder aus.
+    ///
+    /// Direkt ausgegebene Frames werden nicht in ein `RgbImage` umgewandelt, sondern mit
+    /// [`HolomorphicLookup::apply_bgr`] in einem Durchlauf aus den BGR-Bytes der Quelle in einen
+    /// Fensterpuffer transformiert ([`FramePixels::Buffer`]). Das gelingt nur, wenn die Quelle
+    /// ihre Bytes liefert und ihre Frames die Größe der Tabelle haben, andere Frames nehmen
+    /// weiter den Weg über `RgbImage`.
+    pub fn set_direct(&self, direct: bool) {
+        self.counters.direct.store(direct, Ordering::Relaxed);
+    }
+
+    /// Ersetzt die Lookup-Tabelle für alle folgenden Frames.
+    pub fn set_lookup(&self, lookup: Arc<HolomorphicLookup>) {
+        // Ein beendeter Thread braucht keine Tabellen mehr
+        let _ = self.lookups.send(lookup);
+    }
+
+    /// Wartet höchstens `timeout` auf ein Frame und gibt das neueste fertige Frame zurück.
+    ///
+    /// Ältere, noch nicht abgeholte Frames werden verworfen und als solche gezählt.
+    ///
+    /// # Rückgabewert
+    /// Das neueste Frame oder `None`, wenn innerhalb von `timeout` keines fertig wurde oder
+    /// die Pipeline beendet ist (siehe [`Pipeline::is_finished`]).
+    pub fn latest(&mut self, timeout: Duration) -> Option<PipelineFrame> {
+        let first = match self.frames.recv_timeout(timeout) {
+            Ok(frame) => frame,
+            Err(RecvTimeoutError::Timeout) => return None,
+            Err(RecvTimeoutError::Disconnected) => {
+                self.finished = true;
+                return None;
+            }
+        };
+        let mut latest = first;
+        for newer in self.frames.try_iter() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            latest = newer;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            let interval = now.duration_since(last).as_secs_f64();
+            if interval > 0.0 {
+                smooth(&mut self.stats.fps, 1.0 / interval);
+            }
+        }
+        smooth(
+            &mut self.stats.latency,
+            now.duration_since(latest.captured).as_secs_f64(),
+        );
+        self.stats.delivered += 1;
+        Some(latest)
+    }
+
+    /// Gibt an, ob die Quelle erschöpft ist und alle Frames abgeholt wurden.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Gibt die aktuellen Kennzahlen zurück.
+    pub fn stats(&self) -> PipelineStats {
+        PipelineStats {
+            captured: self.counters.captured.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            ..self.stats.clone()
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.counters.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reicht ein Frame an die nächste Stufe weiter und verwirft es, wenn diese ausgelastet ist.
+///
+/// # Rückgabewert
+/// `false`, wenn die nächste Stufe beendet ist.
+fn offer<T>(sender: &SyncSender<T>, frame: T, counters: &Counters) -> bool {
+    match sender.try_send(frame) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            counters.dropped.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_pipeline_delivers_or_drops_every_frame() {
+        let (width, height) = (8, 6);
+        let identity = Arc::new(HolomorphicLookup::new(|z| z, width, height));
+        let mirror = Arc::new(HolomorphicLookup::new(|z| -z, width, height));
+        let frames = 50;
+
+        let switched = AtomicBool::new(false);
+        let (delivered, stats) = std::thread::scope(|scope| {
+            let switched = &switched;
+            let mut next = 0u8;
+            let capture = move || {
+                // Ab dem zweiten Frame erst nach dem Tabellenwechsel erfassen
+                while next > 0 && !switched.load(Ordering::SeqCst) {
+                    std::thread::yield_now();
+                }
+                next += 1;
+                (next <= frames).then_some(next)
+            };
+            let convert = |&value: &u8| Some(RgbImage::from_pixel(width, height, Rgb([value; 3])));
+            let mut pipeline =
+                Pipeline::spawn(scope, capture, convert, None, Arc::clone(&identity), None);
+
+            let mut delivered = Vec::new();
+            while !pipeline.is_finished() {
+                if let Some(frame) = pipeline.latest(Duration::from_millis(10)) {
+                    assert_eq!(frame.source().unwrap().dimensions(), (width, height));
+                    delivered.push(frame);
+                    if delivered.len() == 1 {
+                        pipeline.set_lookup(Arc::clone(&mirror));
+                        switched.store(true, Ordering::SeqCst);
+                    }
+                    // Eine langsame Anzeige zwingt die Pipeline, Frames zu verwerfen
+                    std::thread::sleep(Duration::from_millis(2));
+                }
+            }
+            (delivered, pipeline.stats())
+        });
+
+        assert_eq!(stats.captured, frames as u64);
+        assert_eq!(stats.delivered, delivered.len() as u64);
+        assert_eq!(stats.delivered + stats.dropped, stats.captured);
+        // Frames kommen in Aufnahmereihenfolge an
+        let values: Vec<u8> = delivered
+            .iter()
+            .map(|f| f.source().unwrap().get_pixel(0, 0)[0])
+            .collect();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        // Nach dem Wechsel wird die neue Tabelle verwendet
+        let last = delivered.last().unwrap();
+        assert!(Arc::ptr_eq(&last.lookup, &mirror));
+        let expected = mirror.apply(last.source().unwrap()).unwrap();
+        assert_eq!(*last.transformed(), expected);
+        assert!(stats.latency > 0.0);
+    }
+
+    #[test]
+    fn test_pipeline_counts_unconvertible_frames_as_dropped() {
+        let (width, height) = (4, 4);
+        let lookup = Arc::new(HolomorphicLookup::new(|z| z, width, height));
+        let frames = 20;
+        let stats = std::thread::scope(|scope| {
+            let mut next = 0u8;
+            let capture = move || {
+                next += 1;
+                (next <= frames).then_some(next)
+            };
+            // Jedes zweite Frame und die letzten fünf schlagen fehl
+            let convert = |&value: &u8| {
+                (value % 2 == 0 && value <= frames - 5)
+                    .then(|| RgbImage::from_pixel(width, height, Rgb([value; 3])))
+            };
+            let mut pipeline = Pipeline::spawn(scope, capture, convert, None, lookup, None);
+            while !pipeline.is_finished() {
+                pipeline.latest(Duration::from_millis(10));
+            }
+            pipeline.stats()
+        });
+
+        assert_eq!(stats.captured, frames as u64);
+        assert!(stats.delivered > 0);
+        assert!(stats.dropped >= 13);
+        assert_eq!(stats.delivered + stats.dropped, stats.captured);
+    }
+
+    /// Liest die Bytes eines Test-Frames, das bereits im BGR-Format vorliegt.
+    #[allow(clippy::ptr_arg)] // Die Signatur muss zu `BgrReader<Vec<u8>>` passen
+    fn borrowed(bgr: &Vec<u8>) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(bgr))
+    }
+
+    #[test]
+    fn test_pipeline_direct_output_matches_rgb_path() {
+        let (width, height) = (6, 4);
+        let lookup = Arc::new(HolomorphicLookup::new(|z| 1.0 / z, width, height));
+        let bgr: Vec<u8> = (0..width * height * 3).map(|i| (i * 7) as u8).collect();
+        let rgb = {
+            let mut rgb = bgr.clone();
+            rgb.chunks_exact_mut(3).for_each(|pixel| pixel.swap(0, 2));
+            RgbImage::from_raw(width, height, rgb).unwrap()
+        };
+        let expected = lookup.apply(&rgb).unwrap();
+
+        for direct in [true, false] {
+            let ready = AtomicBool::new(false);
+            let frame = std::thread::scope(|scope| {
+                let ready = &ready;
+                let mut frames = vec![vec![0; 5], bgr.clone()];
+                // Erst erfassen, wenn die Ausgabeart feststeht
+                let capture = move || {
+                    while !ready.load(Ordering::SeqCst) {
+                        std::thread::yield_now();
+                    }
+                    frames.pop()
+                };
+                let convert = |bgr: &Vec<u8>| {
+                    let mut rgb = bgr.clone();
+                    rgb.chunks_exact_mut(3).for_each(|pixel| pixel.swap(0, 2));
+                    RgbImage::from_raw(width, height, rgb)
+                };
+                let mut pipeline = Pipeline::spawn(
+                    scope,
+                    capture,
+                    convert,
+                    Some(borrowed as BgrReader<Vec<u8>>),
+                    Arc::clone(&lookup),
+                    None,
+                );
+                pipeline.set_direct(direct);
+                ready.store(true, Ordering::SeqCst);
+                let mut delivered = Vec::new();
+                while !pipeline.is_finished() {
+                    delivered.extend(pipeline.latest(Duration::from_millis(10)));
+                }
+                // Das zu kurze zweite Frame passt weder direkt noch umgewandelt
+                assert_eq!(pipeline.stats().dropped, 1);
+                delivered.pop().unwrap()
+            });
+            assert_eq!(matches!(frame.pixels, FramePixels::Buffer(_)), direct);
+            assert_eq!(frame.source().is_some(), !direct);
+            assert_eq!(*frame.transformed(), expected);
+        }
+    }
+}
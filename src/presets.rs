@@ -0,0 +1,135 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use minifb::Key;
+
+use crate::parsing::Expr;
+
+/// Umgebungsvariable, die eine andere Datei mit Voreinstellungen angibt.
+pub const PRESETS_VAR: &str = "HOLO_PRESETS";
+
+/// Tasten, mit denen die Voreinstellungen in dieser Reihenfolge gewählt werden.
+pub const PRESET_KEYS: [Key; 10] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::Key0,
+];
+
+/// Eine Funktion, die im Vorschaufenster per Zifferntaste gewählt werden kann.
+///
+/// # Felder
+/// - `name` (`String`): Anzeigename, ohne Angabe die Funktion selbst.
+/// - `expression` (`Expr`): Die Funktion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    pub expression: Expr,
+}
+
+/// Liest Voreinstellungen aus dem Inhalt einer Datei.
+///
+/// Jede Zeile enthält eine Funktion, optional mit einem Namen davor (`name = funktion`).
+/// Leere Zeilen und Kommentare ab `#` werden übersprungen. Es sind höchstens so viele
+/// Voreinstellungen erlaubt, wie es Tasten in [`PRESET_KEYS`] gibt.
+///
+/// # Parameter
+/// - `text` (`&str`): Der Inhalt der Datei.
+///
+/// # Fehler
+/// Gibt die erste ungültige Zeile mit Zeilennummer zurück.
+pub fn parse_presets(text: &str) -> Result<Vec<Preset>, String> {
+    let mut presets = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, function) = match line.split_once('=') {
+            Some((name, function)) => (name.trim(), function.trim()),
+            None => (line, line),
+        };
+        let expression =
+            Expr::parse(function).map_err(|error| format!("line {}: {}", number + 1, error))?;
+        presets.push(Preset {
+            name: name.to_string(),
+            expression,
+        });
+    }
+    if presets.len() > PRESET_KEYS.len() {
+        return Err(format!(
+            "at most {} presets are supported (keys 1-9 and 0)",
+            PRESET_KEYS.len()
+        ));
+    }
+    Ok(presets)
+}
+
+/// Bestimmt die Standarddatei der Voreinstellungen.
+///
+/// # Rückgabewert
+/// `$HOLO_PRESETS`, sonst `holo/presets.txt` in `$XDG_CONFIG_HOME`, `%APPDATA%` bzw.
+/// `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(PRESETS_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("holo").join("presets.txt"))
+}
+
+/// Lädt die Voreinstellungen für das Vorschaufenster.
+///
+/// # Parameter
+/// - `path` (`Option<&Path>`): Die angegebene Datei; ohne Angabe wird [`default_path`]
+///   verwendet, sofern die Datei existiert.
+///
+/// # Rückgabewert
+/// Die Voreinstellungen, leer wenn keine Datei angegeben ist und die Standarddatei fehlt.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn die Datei nicht gelesen werden kann oder ungültig ist.
+pub fn load_presets(path: Option<&Path>) -> Result<Vec<Preset>, Box<dyn Error>> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match default_path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(Vec::new()),
+        },
+    };
+    let text = fs::read_to_string(&path)
+        .map_err(|error| format!("failed to read presets '{}': {}", path.display(), error))?;
+    Ok(parse_presets(&text).map_err(|error| format!("{}: {}", path.display(), error))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_presets_with_names_and_comments() {
+        let presets = parse_presets(
+            "# Vorführung\n\nsquare = z^2\n  1 / z  # Inversion\nspiral = exp(z) * z\n",
+        )
+        .unwrap();
+        assert_eq!(presets.len(), 3);
+        assert_eq!(presets[0].name, "square");
+        assert_eq!(presets[0].expression, Expr::parse("z^2").unwrap());
+        assert_eq!(presets[1].name, "1 / z");
+
+        let error = parse_presets("z\n)\n").unwrap_err();
+        assert!(error.starts_with("line 2:"), "{}", error);
+        assert!(parse_presets(&"z\n".repeat(11)).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::codecs::jpeg::JpegEncoder;
+use image::RgbImage;
+
+/// Trennzeichen zwischen den Bildern des MJPEG-Streams.
+const BOUNDARY: &str = "frame";
+
+/// Wartezeit, nach der wartende Threads prüfen, ob der Server beendet wurde.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Zeitlimit für Lese- und Schreibvorgänge einer Verbindung, danach gilt der Client als
+/// verschwunden.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wartezeit eines Schnappschusses auf das nächste Frame, wenn noch keines vorliegt.
+const SNAPSHOT_WAIT: Duration = Duration::from_millis(500);
+
+/// Höchstzahl gleichzeitiger Verbindungen, weitere werden mit `503` abgewiesen.
+pub const MAX_CLIENTS: usize = 16;
+
+/// Pfad des MJPEG-Streams, `/` liefert ebenfalls den Stream.
+pub const STREAM_PATH: &str = "/stream";
+
+/// Pfad für ein einzelnes JPEG-Bild des aktuellen Frames.
+pub const SNAPSHOT_PATH: &str = "/snapshot.jpg";
+
+/// Prüft, ob eine Quelle ein Netzwerkstream statt einer Datei ist.
+///
+/// # Parameter
+/// - `source` (`&str`): Der Pfad bzw. die URL der Quelle.
+///
+/// # Rückgabewert
+/// `true` für URLs mit Schema, z. B. `rtsp://`, `http://` oder `udp://`.
+pub fn is_stream_url(source: &str) -> bool {
+    match source.split_once("://") {
+        Some((scheme, _)) => {
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Das zuletzt veröffentlichte Frame mit seiner laufenden Nummer.
+#[derive(Default)]
+struct Latest {
+    image: Option<Arc<RgbImage>>,
+    sequence: u64,
+}
+
+/// Zustand, den sich der Server mit seinen Verbindungen teilt.
+struct Shared {
+    latest: Mutex<Latest>,
+    updated: Condvar,
+    stop: AtomicBool,
+    clients: AtomicUsize,
+    quality: u8,
+}
+
+impl Shared {
+    /// Wartet auf ein Frame, das neuer als `seen` ist.
+    ///
+    /// # Parameter
+    /// - `seen` (`u64`): Die Nummer des zuletzt gesendeten Frames.
+    /// - `timeout` (`Option<Duration>`): Höchste Wartezeit, ohne Angabe unbegrenzt.
+    ///
+    /// # Rückgabewert
+    /// Das Frame und seine Nummer, oder `None`, sobald der Server beendet wird oder die
+    /// Wartezeit abläuft.
+    fn next_frame(&self, seen: u64, timeout: Option<Duration>) -> Option<(Arc<RgbImage>, u64)> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut latest = self.latest.lock().unwrap();
+        loop {
+            if self.stop.load(Ordering::Relaxed)
+                || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return None;
+            }
+            if let (Some(image), true) = (&latest.image, latest.sequence > seen) {
+                return Some((Arc::clone(image), latest.sequence));
+            }
+            latest = self.updated.wait_timeout(latest, POLL_INTERVAL).unwrap().0;
+        }
+    }
+
+    /// Kodiert ein Frame als JPEG.
+    fn encode(&self, image: &RgbImage) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        image
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, self.quality))
+            .map_err(io::Error::other)?;
+        Ok(bytes)
+    }
+}
+
+/// HTTP-Server, der transformierte Frames als MJPEG-Stream ausliefert.
+///
+/// `GET /` bzw. [`STREAM_PATH`] liefert einen `multipart/x-mixed-replace`-Stream, den Browser
+/// und Player wie VLC oder ffplay direkt anzeigen; [`SNAPSHOT_PATH`] liefert das aktuelle
+/// Frame als einzelnes JPEG. Jede Verbindung läuft in einem eigenen Thread und kodiert die
+/// Frames selbst, sodass langsame Clients die Anzeige nicht aufhalten, sondern Frames
+/// überspringen. Höchstens [`MAX_CLIENTS`] Verbindungen werden gleichzeitig bedient, und
+/// Clients, die [`CLIENT_TIMEOUT`] lang nichts senden bzw. annehmen, werden getrennt.
+///
+/// # Felder
+/// - `addr` (`SocketAddr`): Die Adresse, an der der Server lauscht.
+pub struct MjpegServer {
+    pub addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl MjpegServer {
+    /// Startet den Server im Hintergrund.
+    ///
+    /// # Parameter
+    /// - `addr` (`impl ToSocketAddrs`): Die Adresse, z. B. `0.0.0.0:8080`; Port `0` wählt einen
+    ///   freien Port.
+    /// - `quality` (`u8`): Die JPEG-Qualität der Frames (1-100).
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Adresse nicht gebunden werden kann.
+    pub fn bind(addr: impl ToSocketAddrs, quality: u8) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        // Nicht blockierend, damit der Thread beim Beenden nicht im `accept` hängen bleibt
+        listener.set_nonblocking(true)?;
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(Latest::default()),
+            updated: Condvar::new(),
+            stop: AtomicBool::new(false),
+            clients: AtomicUsize::new(0),
+            quality,
+        });
+        let accepting = Arc::clone(&shared);
+        thread::spawn(move || {
+            while !accepting.stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((mut stream, _)) => {
+                        if accepting.clients.load(Ordering::Relaxed) >= MAX_CLIENTS {
+                            let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+                            let _ = respond(
+                                &mut stream,
+                                "503 Service Unavailable",
+                                "too many clients\n",
+                            );
+                            continue;
+                        }
+                        accepting.clients.fetch_add(1, Ordering::Relaxed);
+                        let shared = Arc::clone(&accepting);
+                        thread::spawn(move || {
+                            // Abgebrochene Verbindungen sind kein Fehler des Servers
+                            let _ = handle(stream, &shared);
+                            shared.clients.fetch_sub(1, Ordering::Relaxed);
+                        });
+                    }
+                    Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(POLL_INTERVAL);
+                    }
+                    Err(error) => eprintln!("error: {}", error),
+                }
+            }
+        });
+        Ok(MjpegServer { addr, shared })
+    }
+
+    /// Veröffentlicht ein neues Frame für alle Verbindungen.
+    ///
+    /// Ohne Verbindungen wird das Frame nicht kopiert; ein Schnappschuss wartet dann auf das
+    /// nächste Frame.
+    ///
+    /// # Parameter
+    /// - `image` (`&RgbImage`): Das transformierte Frame.
+    pub fn publish(&self, image: &RgbImage) {
+        let connected = self.shared.clients.load(Ordering::Relaxed) > 0;
+        let mut latest = self.shared.latest.lock().unwrap();
+        latest.image = connected.then(|| Arc::new(image.clone()));
+        latest.sequence += 1;
+        self.shared.updated.notify_all();
+    }
+}
+
+impl Drop for MjpegServer {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.shared.updated.notify_all();
+    }
+}
+
+/// Beantwortet eine HTTP-Anfrage.
+fn handle(mut stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Kopfzeilen bis zur Leerzeile überspringen
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or("/"),
+    );
+    let path = path.split('?').next().unwrap_or_default();
+    match (method, path) {
+        ("GET", "/") | ("GET", STREAM_PATH) => serve_stream(&mut stream, shared),
+        ("GET", SNAPSHOT_PATH) => match shared.next_frame(0, Some(SNAPSHOT_WAIT)) {
+            Some((image, _)) => {
+                let jpeg = shared.encode(&image)?;
+                write!(
+                    stream,
+                    "HTTP/1.0 200 OK\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\
+                         Cache-Control: no-cache\r\nConnection: close\r\n\r\n",
+                    jpeg.len()
+                )?;
+                stream.write_all(&jpeg)
+            }
+            None => respond(&mut stream, "503 Service Unavailable", "no frame yet\n"),
+        },
+        ("GET", _) => respond(&mut stream, "404 Not Found", "not found\n"),
+        _ => respond(
+            &mut stream,
+            "405 Method Not Allowed",
+            "method not allowed\n",
+        ),
+    }
+}
+
+/// Sendet eine kurze Textantwort.
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Sendet Frames als MJPEG-Stream, bis der Client die Verbindung schließt.
+fn serve_stream(stream: &mut TcpStream, shared: &Shared) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.0 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\n\
+         Cache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        BOUNDARY
+    )?;
+    let mut seen = 0;
+    while let Some((image, sequence)) = shared.next_frame(seen, None) {
+        seen = sequence;
+        let jpeg = shared.encode(&image)?;
+        write!(
+            stream,
+            "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+            BOUNDARY,
+            jpeg.len()
+        )?;
+        stream.write_all(&jpeg)?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+    use std::io::Read;
+
+    /// Sendet eine Anfrage und liest Kopf und Anfang des Inhalts.
+    fn get(addr: SocketAddr, path: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = Vec::new();
+        let mut buffer = [0; 4096];
+        // Der Stream endet nicht, daher nur lesen, bis das erste Bild vollständig sein kann
+        while let Ok(read @ 1..) = stream.read(&mut buffer) {
+            response.extend_from_slice(&buffer[..read]);
+            if response.ends_with(b"\xff\xd9") || response.ends_with(b"\xff\xd9\r\n") {
+                break;
+            }
+        }
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let head = String::from_utf8_lossy(&response[..split]).into_owned();
+        (head, response[split..].to_vec())
+    }
+
+    /// Wartet, bis der Server die erwartete Zahl an Verbindungen zählt.
+    fn wait_for_clients(server: &MjpegServer, expected: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.shared.clients.load(Ordering::Relaxed) != expected {
+            assert!(
+                Instant::now() < deadline,
+                "clients never reached {}",
+                expected
+            );
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_server_serves_snapshot_and_stream() {
+        assert!(is_stream_url("rtsp://192.168.0.10:554/live"));
+        assert!(!is_stream_url("./videos/clip.mp4"));
+
+        let server = MjpegServer::bind("127.0.0.1:0", 90).unwrap();
+        let (head, _) = get(server.addr, SNAPSHOT_PATH);
+        assert!(head.starts_with("HTTP/1.0 503"), "{}", head);
+
+        // Ohne Verbindungen wird kein Frame zurückgehalten
+        let frame = RgbImage::from_pixel(16, 8, Rgb([200, 40, 10]));
+        wait_for_clients(&server, 0);
+        server.publish(&frame);
+        assert!(server.shared.latest.lock().unwrap().image.is_none());
+
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    server.publish(&frame);
+                    thread::sleep(Duration::from_millis(10));
+                }
+            });
+
+            let (head, body) = get(server.addr, SNAPSHOT_PATH);
+            assert!(head.starts_with("HTTP/1.0 200"), "{}", head);
+            let snapshot = image::load_from_memory(&body).unwrap().to_rgb8();
+            assert_eq!(snapshot.dimensions(), (16, 8));
+
+            let (head, body) = get(server.addr, STREAM_PATH);
+            assert!(head.contains("multipart/x-mixed-replace; boundary=frame"));
+            let part = String::from_utf8_lossy(&body[..body.len().min(64)]).into_owned();
+            assert!(
+                part.starts_with("--frame\r\nContent-Type: image/jpeg"),
+                "{}",
+                part
+            );
+            assert!(get(server.addr, "/missing").0.starts_with("HTTP/1.0 404"));
+
+            // Weitere Verbindungen über der Höchstzahl werden abgewiesen
+            wait_for_clients(&server, 0);
+            let held: Vec<TcpStream> = (0..MAX_CLIENTS)
+                .map(|_| TcpStream::connect(server.addr).unwrap())
+                .collect();
+            wait_for_clients(&server, MAX_CLIENTS);
+            let (head, _) = get(server.addr, STREAM_PATH);
+            assert!(head.starts_with("HTTP/1.0 503"), "{}", head);
+            drop(held);
+            wait_for_clients(&server, 0);
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+}
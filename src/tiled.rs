@@ -0,0 +1,1180 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use image::{ColorType, ImageFormat};
+use num_complex::Complex;
+use rayon::prelude::*;
+use tiff::decoder::{Decoder as TiffDecoder, DecodingResult};
+use tiff::tags::Tag;
+use tiff::ColorType as TiffColorType;
+
+use crate::holo::{complex_to_source, pixel_to_complex};
+use crate::metadata::RenderMetadata;
+use crate::output::{create_parent_dir, STDIO_PATH};
+
+/// Standardkantenlänge einer Kachel in Pixeln.
+pub const DEFAULT_TILE_SIZE: u32 = 512;
+
+/// Kachelkanten müssen ein Vielfaches dieses Werts sein (Vorgabe von TIFF).
+pub const TILE_ALIGNMENT: u32 = 16;
+
+/// Höchstzahl an Quellpixeln, die für eine Kachel auf einmal gelesen werden.
+/// Kacheln mit größerem Einzugsbereich werden geteilt.
+pub const MAX_SOURCE_PIXELS: u64 = 1 << 22;
+
+/// Feldtypen einer TIFF-IFD.
+const TIFF_SHORT: u16 = 3;
+const TIFF_LONG: u16 = 4;
+const TIFF_LONG8: u16 = 16;
+
+/// Zähler für eindeutige Namen der Zwischendateien innerhalb eines Prozesses.
+static SPOOL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Rechteckiger Ausschnitt eines Bildes in Pixeln.
+///
+/// # Felder
+/// - `x` (`u32`): Die linke Spalte.
+/// - `y` (`u32`): Die obere Zeile.
+/// - `width` (`u32`): Die Breite.
+/// - `height` (`u32`): Die Höhe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Gibt die Anzahl der Pixel im Ausschnitt zurück.
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Teilt den Ausschnitt in bis zu vier etwa gleich große Teile.
+    fn split(&self) -> Vec<Rect> {
+        let left = self.width.div_ceil(2);
+        let top = self.height.div_ceil(2);
+        [
+            (0, 0, left, top),
+            (left, 0, self.width - left, top),
+            (0, top, left, self.height - top),
+            (left, top, self.width - left, self.height - top),
+        ]
+        .into_iter()
+        .filter(|&(_, _, width, height)| width > 0 && height > 0)
+        .map(|(dx, dy, width, height)| Rect {
+            x: self.x + dx,
+            y: self.y + dy,
+            width,
+            height,
+        })
+        .collect()
+    }
+}
+
+/// Lookup-Tabelle für einen Ausschnitt des Ausgabebildes.
+///
+/// Im Gegensatz zu [`HolomorphicLookup`](crate::holo::HolomorphicLookup) deckt sie nur
+/// eine Kachel ab und speichert Koordinaten statt Indizes, sodass auch Bilder mit mehr
+/// als `u32::MAX` Pixeln adressiert werden können.
+///
+/// # Felder
+/// - `rect` (`Rect`): Der Ausschnitt des Ausgabebildes.
+/// - `sources` (`Vec<Option<(u32, u32)>>`): Das Quellpixel jedes Pixels im Ausschnitt
+///   (zeilenweise), `None` für Pixel ohne Quellpixel.
+pub struct TileLookup {
+    pub rect: Rect,
+    pub sources: Vec<Option<(u32, u32)>>,
+}
+
+impl TileLookup {
+    /// Berechnet die Lookup-Tabelle eines Ausschnitts.
+    ///
+    /// # Parameter
+    /// - `f` (`impl Fn(Complex<f64>) -> Option<Complex<f64>>`): Die Abbildung, `None` für
+    ///   Punkte außerhalb ihres Definitionsbereichs.
+    /// - `rect` (`Rect`): Der Ausschnitt des Ausgabebildes.
+    /// - `width` (`u32`): Die Breite des gesamten Bildes.
+    /// - `height` (`u32`): Die Höhe des gesamten Bildes.
+    ///
+    /// # Rückgabewert
+    /// Die Lookup-Tabelle des Ausschnitts.
+    pub fn new(
+        f: impl Fn(Complex<f64>) -> Option<Complex<f64>>,
+        rect: Rect,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let mut sources = Vec::with_capacity(rect.area() as usize);
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                let complex_pos = pixel_to_complex(x, y, width, height);
+                sources.push(f(complex_pos).map(|result| complex_to_source(result, width, height)));
+            }
+        }
+        TileLookup { rect, sources }
+    }
+
+    /// Bestimmt den kleinsten Ausschnitt des Quellbildes, der alle Quellpixel enthält.
+    ///
+    /// # Rückgabewert
+    /// Der Ausschnitt oder `None`, wenn kein Pixel einen Quellpixel besitzt.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let mut mapped = self.sources.iter().flatten();
+        let &(x, y) = mapped.next()?;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (x, y, x, y);
+        for &(x, y) in mapped {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        Some(Rect {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+    }
+}
+
+/// Quellbild, dessen Ausschnitte bei Bedarf gelesen werden.
+///
+/// TIFF-Dateien mit Streifen bzw. Kacheln bis `MAX_SOURCE_PIXELS` Pixel werden direkt
+/// gelesen: Jeder Ausschnitt dekodiert nur die Abschnitte, die er berührt. PNG-Dateien und
+/// unkomprimierte TIFF-Dateien mit größeren Streifen lassen sich nicht wahlfrei lesen und
+/// werden Zeile für Zeile in eine unkomprimierte Zwischendatei im temporären Verzeichnis
+/// entpackt (bei 40000x20000 Pixeln in `Rgba16` rund 6,4 GB), die beim Verwerfen gelöscht
+/// wird. Komprimierte TIFF-Abschnitte über der Grenze werden abgewiesen, da sie sich nur
+/// als Ganzes dekodieren lassen. Das Bild liegt also nie vollständig im Speicher.
+///
+/// # Felder
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+/// - `color` (`ColorType`): Der Pixeltyp, z. B. `Rgba16` oder `Rgb32F`.
+pub struct SpooledSource {
+    pub width: u32,
+    pub height: u32,
+    pub color: ColorType,
+    storage: Storage,
+}
+
+/// Der zuletzt dekodierte TIFF-Abschnitt mit seinem Index.
+type ChunkCache = Mutex<Option<(u32, Arc<Vec<u8>>)>>;
+
+/// Ablageort der Pixel einer `SpooledSource`.
+enum Storage {
+    /// Die Zwischendatei mit den Pixeln im Speicherlayout der `image`-Crate.
+    Spool(PathBuf),
+    /// Die TIFF-Datei selbst mit der Größe ihrer Abschnitte und dem zuletzt dekodierten
+    /// Abschnitt, den benachbarte Ausschnitte meist erneut brauchen.
+    Tiff {
+        path: PathBuf,
+        chunk_width: u32,
+        chunk_height: u32,
+        last_chunk: ChunkCache,
+    },
+}
+
+impl SpooledSource {
+    /// Öffnet eine PNG- oder TIFF-Datei und entpackt sie, falls nötig, in eine Zwischendatei.
+    ///
+    /// # Parameter
+    /// - `path` (`&Path`): Der Pfad des Quellbildes.
+    /// - `max_chunk_pixels` (`u64`): Höchstzahl an Pixeln eines TIFF-Abschnitts, der auf
+    ///   einmal dekodiert wird, siehe [`MAX_SOURCE_PIXELS`].
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Datei kein PNG bzw. TIFF ist, ihr Pixeltyp nicht
+    /// unterstützt wird (z. B. Palette in TIFF, CMYK, Interlacing), ein komprimiertes TIFF
+    /// größere Abschnitte hat, im temporären Verzeichnis nicht genug Platz für die
+    /// Zwischendatei frei ist oder ihr Schreiben fehlschlägt.
+    pub fn open(path: &Path, max_chunk_pixels: u64) -> Result<Self, Box<dyn Error>> {
+        let mut header = Vec::new();
+        File::open(path)?.take(16).read_to_end(&mut header)?;
+        match image::guess_format(&header) {
+            Ok(ImageFormat::Png) => Self::spool_png(path),
+            Ok(ImageFormat::Tiff) => Self::open_tiff(path, max_chunk_pixels),
+            _ => Err(format!(
+                "tiled rendering reads PNG or TIFF files, '{}' is neither",
+                path.display()
+            )
+            .into()),
+        }
+    }
+
+    /// Legt eine leere Quelle mit eindeutigem Pfad für die Zwischendatei an.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn im temporären Verzeichnis weniger Platz frei ist, als
+    /// die Zwischendatei braucht.
+    fn spool(width: u32, height: u32, color: ColorType) -> Result<Self, Box<dyn Error>> {
+        let dir = std::env::temp_dir();
+        let needed = width as u64 * height as u64 * color.bytes_per_pixel() as u64;
+        let available = fs4::available_space(&dir)?;
+        if available < needed {
+            return Err(format!(
+                "the image needs a {} MiB temporary file but only {} MiB are free in '{}', \
+                 set TMPDIR to a larger disk or convert the input to a tiled TIFF",
+                needed.div_ceil(1 << 20),
+                available >> 20,
+                dir.display()
+            )
+            .into());
+        }
+        let id = SPOOL_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("holo_{}_{}.raw", std::process::id(), id));
+        Ok(SpooledSource {
+            width,
+            height,
+            color,
+            storage: Storage::Spool(path),
+        })
+    }
+
+    /// Gibt den Pfad der Zwischendatei zurück, falls die Quelle eine hat.
+    fn spool_path(&self) -> Option<&Path> {
+        match &self.storage {
+            Storage::Spool(path) => Some(path),
+            Storage::Tiff { .. } => None,
+        }
+    }
+
+    /// Entpackt ein PNG zeilenweise.
+    fn spool_png(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        // Paletten und Bittiefen unter 8 Bit erweitern, tRNS wird zum Alphakanal
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        if reader.info().interlaced {
+            return Err("interlaced PNG files cannot be read row by row".into());
+        }
+        let (width, height) = (reader.info().width, reader.info().height);
+        let color = match reader.output_color_type() {
+            (png::ColorType::Grayscale, png::BitDepth::Eight) => ColorType::L8,
+            (png::ColorType::GrayscaleAlpha, png::BitDepth::Eight) => ColorType::La8,
+            (png::ColorType::Rgb, png::BitDepth::Eight) => ColorType::Rgb8,
+            (png::ColorType::Rgba, png::BitDepth::Eight) => ColorType::Rgba8,
+            (png::ColorType::Grayscale, png::BitDepth::Sixteen) => ColorType::L16,
+            (png::ColorType::GrayscaleAlpha, png::BitDepth::Sixteen) => ColorType::La16,
+            (png::ColorType::Rgb, png::BitDepth::Sixteen) => ColorType::Rgb16,
+            (png::ColorType::Rgba, png::BitDepth::Sixteen) => ColorType::Rgba16,
+            (color, depth) => {
+                return Err(format!("unsupported PNG pixel type {:?} {:?}", color, depth).into())
+            }
+        };
+
+        let source = SpooledSource::spool(width, height, color)?;
+        let mut file = BufWriter::new(File::create(source.spool_path().unwrap())?);
+        while let Some(row) = reader.next_row()? {
+            let mut data = row.data().to_vec();
+            // PNG speichert 16 Bit als Big-Endian
+            swap_samples(&mut data, color, true);
+            file.write_all(&data)?;
+        }
+        file.flush()?;
+        Ok(source)
+    }
+
+    /// Öffnet ein TIFF zum direkten Lesen oder entpackt es zeilenweise, wenn seine
+    /// Abschnitte zu groß sind.
+    ///
+    /// Der Pixeltyp ergibt sich aus den Tags, sodass hier kein Abschnitt dekodiert wird.
+    fn open_tiff(path: &Path, max_chunk_pixels: u64) -> Result<Self, Box<dyn Error>> {
+        let mut decoder = TiffDecoder::new(BufReader::new(File::open(path)?))?;
+        if decoder.find_tag_unsigned::<u16>(Tag::PlanarConfiguration)? == Some(2) {
+            return Err("planar TIFF files are not supported".into());
+        }
+        let (width, height) = decoder.dimensions()?;
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        // 1 für vorzeichenlose Ganzzahlen, 3 für Gleitkommazahlen
+        let sample_format = decoder
+            .find_tag_unsigned_vec::<u16>(Tag::SampleFormat)?
+            .and_then(|formats| formats.first().copied())
+            .unwrap_or(1);
+        let color = match (decoder.colortype()?, sample_format) {
+            (TiffColorType::Gray(8), 1) => ColorType::L8,
+            (TiffColorType::GrayA(8), 1) => ColorType::La8,
+            (TiffColorType::RGB(8), 1) => ColorType::Rgb8,
+            (TiffColorType::RGBA(8), 1) => ColorType::Rgba8,
+            (TiffColorType::Gray(16), 1) => ColorType::L16,
+            (TiffColorType::GrayA(16), 1) => ColorType::La16,
+            (TiffColorType::RGB(16), 1) => ColorType::Rgb16,
+            (TiffColorType::RGBA(16), 1) => ColorType::Rgba16,
+            (TiffColorType::RGB(32), 3) => ColorType::Rgb32F,
+            (TiffColorType::RGBA(32), 3) => ColorType::Rgba32F,
+            (color, format) => {
+                return Err(format!(
+                    "unsupported TIFF pixel type {:?} with sample format {}",
+                    color, format
+                )
+                .into())
+            }
+        };
+
+        if chunk_width as u64 * chunk_height as u64 <= max_chunk_pixels {
+            return Ok(SpooledSource {
+                width,
+                height,
+                color,
+                storage: Storage::Tiff {
+                    path: path.to_path_buf(),
+                    chunk_width,
+                    chunk_height,
+                    last_chunk: Mutex::new(None),
+                },
+            });
+        }
+        Self::spool_tiff_strips(path, &mut decoder, width, height, color)
+    }
+
+    /// Entpackt ein unkomprimiertes TIFF mit großen Streifen zeilenweise direkt aus der
+    /// Datei, ohne einen Streifen vollständig in den Speicher zu laden.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Abschnitte komprimiert oder Kacheln sind, da sie
+    /// sich nur als Ganzes dekodieren lassen.
+    fn spool_tiff_strips(
+        path: &Path,
+        decoder: &mut TiffDecoder<BufReader<File>>,
+        width: u32,
+        height: u32,
+        color: ColorType,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (chunk_width, rows_per_strip) = decoder.chunk_dimensions();
+        let tiled = decoder.find_tag(Tag::TileWidth)?.is_some();
+        let compression = decoder.find_tag_unsigned::<u16>(Tag::Compression)?;
+        // Nur bei BlackIsZero und RGB entsprechen die gespeicherten Samples den Pixeln
+        let photometric = decoder.find_tag_unsigned::<u16>(Tag::PhotometricInterpretation)?;
+        if tiled || !matches!(compression, None | Some(1)) || !matches!(photometric, Some(1 | 2)) {
+            return Err(format!(
+                "the TIFF {} of {}x{} pixels are too large to decode with bounded memory, \
+                 convert the input to a tiled TIFF, e.g. with `tiffcp -t` or \
+                 `gdal_translate -co TILED=YES`",
+                if tiled { "tiles" } else { "strips" },
+                chunk_width,
+                rows_per_strip
+            )
+            .into());
+        }
+        let offsets = decoder.get_tag_u64_vec(Tag::StripOffsets)?;
+        let mut input = BufReader::new(File::open(path)?);
+        let mut byte_order = [0; 2];
+        input.read_exact(&mut byte_order)?;
+
+        let source = SpooledSource::spool(width, height, color)?;
+        let mut file = BufWriter::new(File::create(source.spool_path().unwrap())?);
+        let mut row = vec![0; width as usize * color.bytes_per_pixel() as usize];
+        for y in 0..height {
+            if y % rows_per_strip == 0 {
+                let offset = offsets
+                    .get((y / rows_per_strip) as usize)
+                    .ok_or("TIFF strip offsets are missing")?;
+                input.seek(SeekFrom::Start(*offset))?;
+            }
+            input.read_exact(&mut row)?;
+            swap_samples(&mut row, color, &byte_order == b"MM");
+            file.write_all(&row)?;
+        }
+        file.flush()?;
+        Ok(source)
+    }
+
+    /// Gibt die Position eines Pixels in der Zwischendatei zurück.
+    fn offset(&self, x: u32, y: u32) -> u64 {
+        (y as u64 * self.width as u64 + x as u64) * self.color.bytes_per_pixel() as u64
+    }
+
+    /// Liest einen Ausschnitt des Quellbildes.
+    ///
+    /// Jeder Aufruf öffnet die Datei neu, sodass mehrere Threads gleichzeitig lesen können.
+    ///
+    /// # Parameter
+    /// - `rect` (`Rect`): Der Ausschnitt, er muss innerhalb des Bildes liegen.
+    ///
+    /// # Rückgabewert
+    /// Die Pixel des Ausschnitts zeilenweise im Speicherlayout der `image`-Crate.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Datei nicht gelesen oder ein TIFF-Abschnitt nicht
+    /// dekodiert werden kann.
+    pub fn read_region(&self, rect: Rect) -> io::Result<Vec<u8>> {
+        let row_bytes = rect.width as usize * self.color.bytes_per_pixel() as usize;
+        let mut pixels = vec![0; row_bytes * rect.height as usize];
+        match &self.storage {
+            Storage::Spool(path) => {
+                let mut file = File::open(path)?;
+                for (row, line) in pixels.chunks_exact_mut(row_bytes).enumerate() {
+                    file.seek(SeekFrom::Start(self.offset(rect.x, rect.y + row as u32)))?;
+                    file.read_exact(line)?;
+                }
+            }
+            Storage::Tiff {
+                path,
+                chunk_width,
+                chunk_height,
+                last_chunk,
+            } => self.read_tiff_region(
+                path,
+                (*chunk_width, *chunk_height),
+                last_chunk,
+                rect,
+                &mut pixels,
+            )?,
+        }
+        Ok(pixels)
+    }
+
+    /// Dekodiert die TIFF-Abschnitte, die einen Ausschnitt berühren, und kopiert ihre
+    /// Pixel in `pixels`. Der zuletzt dekodierte Abschnitt wird wiederverwendet.
+    fn read_tiff_region(
+        &self,
+        path: &Path,
+        (chunk_width, chunk_height): (u32, u32),
+        last_chunk: &ChunkCache,
+        rect: Rect,
+        pixels: &mut [u8],
+    ) -> io::Result<()> {
+        let invalid = |error| io::Error::new(io::ErrorKind::InvalidData, error);
+        let mut decoder = None;
+        let bytes_per_pixel = self.color.bytes_per_pixel() as usize;
+        let chunks_across = self.width.div_ceil(chunk_width);
+        let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+        for chunk_y in rect.y / chunk_height..bottom.div_ceil(chunk_height) {
+            for chunk_x in rect.x / chunk_width..right.div_ceil(chunk_width) {
+                let index = chunk_y * chunks_across + chunk_x;
+                let cached = match &*last_chunk.lock().unwrap() {
+                    Some((cached, data)) if *cached == index => Some(Arc::clone(data)),
+                    _ => None,
+                };
+                let data = match cached {
+                    Some(data) => data,
+                    None => {
+                        let decoder = match &mut decoder {
+                            Some(decoder) => decoder,
+                            None => decoder.insert(
+                                TiffDecoder::new(BufReader::new(File::open(path)?))
+                                    .map_err(invalid)?,
+                            ),
+                        };
+                        let data = native_bytes(decoder.read_chunk(index).map_err(invalid)?)
+                            .ok_or_else(|| {
+                                io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    "TIFF sample type changes between chunks",
+                                )
+                            })?;
+                        let data = Arc::new(data);
+                        *last_chunk.lock().unwrap() = Some((index, Arc::clone(&data)));
+                        data
+                    }
+                };
+                let (left, top) = (chunk_x * chunk_width, chunk_y * chunk_height);
+                // Abschnitte am rechten und unteren Rand sind abgeschnitten
+                let data_width = chunk_width.min(self.width - left);
+                let data_height = chunk_height.min(self.height - top);
+                let (from_x, to_x) = (rect.x.max(left), right.min(left + data_width));
+                let length = (to_x - from_x) as usize * bytes_per_pixel;
+                for y in rect.y.max(top)..bottom.min(top + data_height) {
+                    let start = ((y - top) * data_width + from_x - left) as usize * bytes_per_pixel;
+                    let target =
+                        ((y - rect.y) * rect.width + from_x - rect.x) as usize * bytes_per_pixel;
+                    pixels[target..target + length].copy_from_slice(&data[start..start + length]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SpooledSource {
+    fn drop(&mut self) {
+        if let Some(path) = self.spool_path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Wandelt die Samples eines TIFF-Abschnitts in Bytes in der Reihenfolge des Rechners um.
+///
+/// # Rückgabewert
+/// Die Bytes oder `None` für Sampletypen ohne Entsprechung in der `image`-Crate.
+fn native_bytes(data: DecodingResult) -> Option<Vec<u8>> {
+    match data {
+        DecodingResult::U8(samples) => Some(samples),
+        DecodingResult::U16(samples) => {
+            Some(samples.iter().flat_map(|s| s.to_ne_bytes()).collect())
+        }
+        DecodingResult::F32(samples) => {
+            Some(samples.iter().flat_map(|s| s.to_ne_bytes()).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Vertauscht die Bytes jedes Samples, wenn die Byte-Reihenfolge einer Datei nicht der
+/// des Rechners entspricht. Die Umwandlung ist in beide Richtungen dieselbe.
+///
+/// # Parameter
+/// - `data` (`&mut [u8]`): Die Pixel.
+/// - `color` (`ColorType`): Der Pixeltyp.
+/// - `big_endian` (`bool`): Ob die Datei Big-Endian speichert.
+fn swap_samples(data: &mut [u8], color: ColorType, big_endian: bool) {
+    let sample_size = (color.bytes_per_pixel() / color.channel_count()) as usize;
+    if sample_size > 1 && big_endian != cfg!(target_endian = "big") {
+        for sample in data.chunks_exact_mut(sample_size) {
+            sample.reverse();
+        }
+    }
+}
+
+/// Ziel, in das ein kachelweise berechnetes Bild geschrieben wird.
+///
+/// Das Bild wird in Bändern übergeben, also Reihen von Kacheln über die volle Bildbreite,
+/// von oben nach unten. Alle Bänder außer dem letzten sind genau eine Kachel hoch.
+pub trait TileSink {
+    /// Schreibt das nächste Band.
+    ///
+    /// # Parameter
+    /// - `band` (`&[u8]`): Die Pixel des Bandes zeilenweise im Speicherlayout der `image`-Crate.
+    /// - `height` (`u32`): Die Anzahl der Zeilen im Band.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Schreiben fehlschlägt.
+    fn write_band(&mut self, band: &[u8], height: u32) -> Result<(), Box<dyn Error>>;
+
+    /// Schließt die Datei ab.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Schreiben fehlschlägt.
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
+
+/// Schreibt ein PNG zeilenweise.
+struct PngSink {
+    writer: png::StreamWriter<'static, Box<dyn Write>>,
+    color: ColorType,
+}
+
+impl PngSink {
+    /// Schreibt Kopf und Metadaten des PNG.
+    fn new(
+        out: Box<dyn Write>,
+        width: u32,
+        height: u32,
+        color: ColorType,
+        metadata: Option<&RenderMetadata>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (png_color, depth) = match color {
+            ColorType::L8 => (png::ColorType::Grayscale, png::BitDepth::Eight),
+            ColorType::La8 => (png::ColorType::GrayscaleAlpha, png::BitDepth::Eight),
+            ColorType::Rgb8 => (png::ColorType::Rgb, png::BitDepth::Eight),
+            ColorType::Rgba8 => (png::ColorType::Rgba, png::BitDepth::Eight),
+            ColorType::L16 => (png::ColorType::Grayscale, png::BitDepth::Sixteen),
+            ColorType::La16 => (png::ColorType::GrayscaleAlpha, png::BitDepth::Sixteen),
+            ColorType::Rgb16 => (png::ColorType::Rgb, png::BitDepth::Sixteen),
+            ColorType::Rgba16 => (png::ColorType::Rgba, png::BitDepth::Sixteen),
+            color => return Err(format!("PNG cannot store {:?} pixels, use TIFF", color).into()),
+        };
+        let mut encoder = png::Encoder::new(out, width, height);
+        encoder.set_color(png_color);
+        encoder.set_depth(depth);
+        let mut writer = encoder.write_header()?;
+        for (kind, data) in metadata.map(|m| m.png_text_chunks()).unwrap_or_default() {
+            writer.write_chunk(png::chunk::ChunkType(kind), &data)?;
+        }
+        Ok(PngSink {
+            writer: writer.into_stream_writer()?,
+            color,
+        })
+    }
+}
+
+impl TileSink for PngSink {
+    fn write_band(&mut self, band: &[u8], _height: u32) -> Result<(), Box<dyn Error>> {
+        let mut rows = band.to_vec();
+        swap_samples(&mut rows, self.color, true);
+        self.writer.write_all(&rows)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Schreibt ein unkomprimiertes, gekacheltes TIFF.
+///
+/// Da alle Kacheln gleich groß sind, stehen ihre Positionen von Anfang an fest. Kopf und
+/// IFD werden daher vor den Bilddaten geschrieben, sodass kein Zurückspulen nötig ist und
+/// auch die Standardausgabe als Ziel dient. Dateien über 4 GiB werden als BigTIFF geschrieben.
+struct TiffSink {
+    out: BufWriter<Box<dyn Write>>,
+    width: u32,
+    tile_size: u32,
+    color: ColorType,
+}
+
+impl TiffSink {
+    /// Schreibt Kopf und IFD des TIFF.
+    fn new(
+        out: Box<dyn Write>,
+        width: u32,
+        height: u32,
+        color: ColorType,
+        tile_size: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut out = BufWriter::new(out);
+        out.write_all(&tiff_header(width, height, color, tile_size))?;
+        Ok(TiffSink {
+            out,
+            width,
+            tile_size,
+            color,
+        })
+    }
+}
+
+impl TileSink for TiffSink {
+    fn write_band(&mut self, band: &[u8], height: u32) -> Result<(), Box<dyn Error>> {
+        let bytes_per_pixel = self.color.bytes_per_pixel() as usize;
+        let tile_row = self.tile_size as usize * bytes_per_pixel;
+        let band_row = self.width as usize * bytes_per_pixel;
+        let mut tile = vec![0; tile_row * self.tile_size as usize];
+        for x in (0..self.width).step_by(self.tile_size as usize) {
+            // Kacheln am Rand werden mit Nullen aufgefüllt
+            tile.fill(0);
+            let width = (self.width - x).min(self.tile_size) as usize * bytes_per_pixel;
+            for row in 0..height as usize {
+                let start = row * band_row + x as usize * bytes_per_pixel;
+                tile[row * tile_row..][..width].copy_from_slice(&band[start..start + width]);
+            }
+            swap_samples(&mut tile, self.color, false);
+            self.out.write_all(&tile)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Erstellt Kopf und IFD eines gekachelten TIFF, dessen Kacheln direkt im Anschluss folgen.
+fn tiff_header(width: u32, height: u32, color: ColorType, tile_size: u32) -> Vec<u8> {
+    let tiles = width.div_ceil(tile_size) as u64 * height.div_ceil(tile_size) as u64;
+    let tile_bytes = tile_size as u64 * tile_size as u64 * color.bytes_per_pixel() as u64;
+    let channels = color.channel_count() as usize;
+    let bits = (color.bytes_per_pixel() / color.channel_count()) as u64 * 8;
+    let float = matches!(color, ColorType::Rgb32F | ColorType::Rgba32F);
+
+    let header = |big: bool| {
+        let offset_type = if big { TIFF_LONG8 } else { TIFF_LONG };
+        let mut fields = vec![
+            (256, TIFF_LONG, vec![width as u64]),
+            (257, TIFF_LONG, vec![height as u64]),
+            (258, TIFF_SHORT, vec![bits; channels]),
+            (259, TIFF_SHORT, vec![1]), // unkomprimiert
+            (262, TIFF_SHORT, vec![if channels >= 3 { 2 } else { 1 }]), // RGB bzw. Graustufen
+            (277, TIFF_SHORT, vec![channels as u64]),
+            (284, TIFF_SHORT, vec![1]), // Kanäle verschachtelt
+            (322, TIFF_LONG, vec![tile_size as u64]),
+            (323, TIFF_LONG, vec![tile_size as u64]),
+            (324, offset_type, vec![0; tiles as usize]),
+            (325, offset_type, vec![tile_bytes; tiles as usize]),
+        ];
+        if color.has_alpha() {
+            fields.push((338, TIFF_SHORT, vec![2])); // nicht vormultiplizierter Alphakanal
+        }
+        fields.push((339, TIFF_SHORT, vec![if float { 3 } else { 1 }; channels]));
+
+        // Die Länge hängt nicht von den Werten ab, die Kacheln folgen direkt danach
+        let length = encode_tiff_ifd(&fields, big).len() as u64;
+        fields[9].2 = (0..tiles).map(|tile| length + tile * tile_bytes).collect();
+        encode_tiff_ifd(&fields, big)
+    };
+
+    let classic = header(false);
+    if classic.len() as u64 + tiles * tile_bytes <= u32::MAX as u64 {
+        classic
+    } else {
+        header(true)
+    }
+}
+
+/// Kodiert Kopf und eine IFD einer Little-Endian-TIFF-Datei.
+///
+/// # Parameter
+/// - `fields` (`&[(u16, u16, Vec<u64>)]`): Tag, Feldtyp und Werte, aufsteigend nach Tag.
+/// - `big` (`bool`): Ob BigTIFF mit 64-Bit-Offsets geschrieben wird.
+fn encode_tiff_ifd(fields: &[(u16, u16, Vec<u64>)], big: bool) -> Vec<u8> {
+    let push =
+        |out: &mut Vec<u8>, value: u64, size: usize| out.extend(&value.to_le_bytes()[..size]);
+    // Größe von Offsets und Anzahlen sowie des Eintragszählers der IFD
+    let (offset_size, count_size) = if big { (8, 8) } else { (4, 2) };
+
+    let mut out = b"II".to_vec();
+    if big {
+        push(&mut out, 43, 2);
+        push(&mut out, 8, 2); // Größe der Offsets
+        push(&mut out, 0, 2);
+        push(&mut out, 16, 8);
+    } else {
+        push(&mut out, 42, 2);
+        push(&mut out, 8, 4);
+    }
+
+    let entry_size = 4 + 2 * offset_size;
+    let data_start = out.len() + count_size + fields.len() * entry_size + offset_size;
+    let mut data = Vec::new();
+    push(&mut out, fields.len() as u64, count_size);
+    for (tag, kind, values) in fields {
+        let size = match *kind {
+            TIFF_SHORT => 2,
+            TIFF_LONG => 4,
+            _ => 8,
+        };
+        let mut bytes = Vec::with_capacity(values.len() * size);
+        for &value in values {
+            push(&mut bytes, value, size);
+        }
+        push(&mut out, *tag as u64, 2);
+        push(&mut out, *kind as u64, 2);
+        push(&mut out, values.len() as u64, offset_size);
+        if bytes.len() <= offset_size {
+            bytes.resize(offset_size, 0);
+            out.extend(bytes);
+        } else {
+            push(&mut out, (data_start + data.len()) as u64, offset_size);
+            data.extend(bytes);
+        }
+    }
+    push(&mut out, 0, offset_size); // keine weitere IFD
+    out.extend(data);
+    out
+}
+
+/// Kennzahlen einer kachelweisen Berechnung.
+///
+/// # Felder
+/// - `tiles` (`usize`): Anzahl der berechneten Kacheln.
+/// - `splits` (`usize`): Anzahl der Teilungen wegen eines zu großen Einzugsbereichs.
+/// - `reads` (`usize`): Anzahl der gelesenen Ausschnitte des Quellbildes.
+/// - `largest_read` (`u64`): Größter gelesener Ausschnitt in Pixeln.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TileStats {
+    pub tiles: usize,
+    pub splits: usize,
+    pub reads: usize,
+    pub largest_read: u64,
+}
+
+impl TileStats {
+    /// Addiert die Kennzahlen einer weiteren Kachel.
+    fn merge(&mut self, other: TileStats) {
+        self.tiles += other.tiles;
+        self.splits += other.splits;
+        self.reads += other.reads;
+        self.largest_read = self.largest_read.max(other.largest_read);
+    }
+}
+
+impl fmt::Display for TileStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} tiles, {} source reads of up to {} pixels, {} splits",
+            self.tiles, self.reads, self.largest_read, self.splits
+        )
+    }
+}
+
+/// Einstellungen für die kachelweise Berechnung großer Bilder.
+///
+/// Die Lookup-Tabelle wird pro Kachel des Ausgabebildes berechnet, aus dem Quellbild wird
+/// nur der Ausschnitt gelesen, den die Kachel benötigt. Ist dieser größer als
+/// `max_source_pixels` (etwa nahe einer Polstelle), wird die Kachel geteilt. Der
+/// Speicherbedarf beträgt damit etwa ein Band (Bildbreite mal Kachelhöhe) plus einen
+/// Quellausschnitt pro Thread, unabhängig von der Bildhöhe.
+///
+/// # Felder
+/// - `tile_size` (`u32`): Kantenlänge der Kacheln in Pixeln, ein Vielfaches von [`TILE_ALIGNMENT`].
+/// - `max_source_pixels` (`u64`): Höchstzahl an Quellpixeln, die pro Kachel gelesen werden.
+#[derive(Debug, Clone, Copy)]
+pub struct TiledRenderer {
+    pub tile_size: u32,
+    pub max_source_pixels: u64,
+}
+
+impl Default for TiledRenderer {
+    fn default() -> Self {
+        TiledRenderer {
+            tile_size: DEFAULT_TILE_SIZE,
+            max_source_pixels: MAX_SOURCE_PIXELS,
+        }
+    }
+}
+
+impl TiledRenderer {
+    /// Überprüft die Einstellungen.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Kachelgröße kein positives Vielfaches von
+    /// [`TILE_ALIGNMENT`] ist oder kein einziges Quellpixel gelesen werden darf.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tile_size == 0 || !self.tile_size.is_multiple_of(TILE_ALIGNMENT) {
+            Err(format!(
+                "tile size must be a positive multiple of {} (got {})",
+                TILE_ALIGNMENT, self.tile_size
+            ))
+        } else if self.max_source_pixels == 0 {
+            Err(String::from(
+                "the source budget must allow at least one pixel",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Transformiert ein Quellbild kachelweise und übergibt das Ergebnis bandweise an `sink`.
+    ///
+    /// Die Kacheln eines Bandes werden parallel berechnet.
+    ///
+    /// # Parameter
+    /// - `f` (`impl Fn(Complex<f64>) -> Option<Complex<f64>> + Sync`): Die Abbildung, `None`
+    ///   für Punkte ohne Quellpixel.
+    /// - `source` (`&SpooledSource`): Das Quellbild.
+    /// - `sink` (`&mut dyn TileSink`): Das Ziel.
+    ///
+    /// # Rückgabewert
+    /// Die Kennzahlen der Berechnung.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Einstellungen ungültig sind oder Lesen bzw.
+    /// Schreiben fehlschlägt.
+    pub fn render<F>(
+        &self,
+        f: F,
+        source: &SpooledSource,
+        sink: &mut dyn TileSink,
+    ) -> Result<TileStats, Box<dyn Error>>
+    where
+        F: Fn(Complex<f64>) -> Option<Complex<f64>> + Sync,
+    {
+        self.validate()?;
+        let bytes_per_pixel = source.color.bytes_per_pixel() as usize;
+        let band_row = source.width as usize * bytes_per_pixel;
+        let mut stats = TileStats::default();
+
+        for y in (0..source.height).step_by(self.tile_size as usize) {
+            let height = (source.height - y).min(self.tile_size);
+            let tiles: Vec<Rect> = (0..source.width)
+                .step_by(self.tile_size as usize)
+                .map(|x| Rect {
+                    x,
+                    y,
+                    width: (source.width - x).min(self.tile_size),
+                    height,
+                })
+                .collect();
+            let rendered = tiles
+                .par_iter()
+                .map(|&tile| self.render_tile(&f, source, tile))
+                .collect::<io::Result<Vec<_>>>()?;
+
+            let mut band = vec![0; band_row * height as usize];
+            for (tile, (pixels, tile_stats)) in tiles.iter().zip(rendered) {
+                let tile_row = tile.width as usize * bytes_per_pixel;
+                for (row, line) in pixels.chunks_exact(tile_row).enumerate() {
+                    let start = row * band_row + tile.x as usize * bytes_per_pixel;
+                    band[start..start + tile_row].copy_from_slice(line);
+                }
+                stats.merge(tile_stats);
+            }
+            sink.write_band(&band, height)?;
+        }
+        Ok(stats)
+    }
+
+    /// Berechnet die Pixel einer Kachel.
+    fn render_tile<F>(
+        &self,
+        f: &F,
+        source: &SpooledSource,
+        tile: Rect,
+    ) -> io::Result<(Vec<u8>, TileStats)>
+    where
+        F: Fn(Complex<f64>) -> Option<Complex<f64>>,
+    {
+        let mut pixels = vec![0; tile.area() as usize * source.color.bytes_per_pixel() as usize];
+        let mut stats = TileStats {
+            tiles: 1,
+            ..TileStats::default()
+        };
+        self.fill(f, source, tile, tile, &mut pixels, &mut stats)?;
+        Ok((pixels, stats))
+    }
+
+    /// Füllt einen Ausschnitt einer Kachel und teilt ihn, solange sein Einzugsbereich im
+    /// Quellbild das Budget übersteigt. Ein einzelnes Pixel benötigt genau ein Quellpixel,
+    /// die Teilung endet also immer.
+    fn fill<F>(
+        &self,
+        f: &F,
+        source: &SpooledSource,
+        tile: Rect,
+        rect: Rect,
+        pixels: &mut [u8],
+        stats: &mut TileStats,
+    ) -> io::Result<()>
+    where
+        F: Fn(Complex<f64>) -> Option<Complex<f64>>,
+    {
+        let lookup = TileLookup::new(f, rect, source.width, source.height);
+        let Some(bounds) = lookup.bounding_box() else {
+            return Ok(()); // Pixel ohne Quellpixel bleiben 0
+        };
+        if bounds.area() > self.max_source_pixels && rect.area() > 1 {
+            stats.splits += 1;
+            for part in rect.split() {
+                self.fill(f, source, tile, part, pixels, stats)?;
+            }
+            return Ok(());
+        }
+
+        let region = source.read_region(bounds)?;
+        stats.reads += 1;
+        stats.largest_read = stats.largest_read.max(bounds.area());
+
+        let bytes_per_pixel = source.color.bytes_per_pixel() as usize;
+        for (index, mapped) in lookup.sources.iter().enumerate() {
+            let Some((source_x, source_y)) = mapped else {
+                continue;
+            };
+            let x = (rect.x - tile.x) as usize + index % rect.width as usize;
+            let y = (rect.y - tile.y) as usize + index / rect.width as usize;
+            let from = ((source_y - bounds.y) as usize * bounds.width as usize
+                + (source_x - bounds.x) as usize)
+                * bytes_per_pixel;
+            let to = (y * tile.width as usize + x) * bytes_per_pixel;
+            pixels[to..to + bytes_per_pixel].copy_from_slice(&region[from..from + bytes_per_pixel]);
+        }
+        Ok(())
+    }
+}
+
+/// Transformiert eine PNG- oder TIFF-Datei kachelweise und schreibt ein PNG oder TIFF.
+///
+/// Der Pixeltyp der Quelle bleibt erhalten. Gleitkommabilder können nur als TIFF
+/// geschrieben werden, Metadaten nur in PNG.
+///
+/// # Parameter
+/// - `f` (`impl Fn(Complex<f64>) -> Option<Complex<f64>> + Sync`): Die Abbildung.
+/// - `renderer` (`&TiledRenderer`): Kachelgröße und Budget.
+/// - `input` (`&Path`): Das Quellbild.
+/// - `output` (`&Path`): Der Ausgabepfad, `-` für die Standardausgabe.
+/// - `format` (`ImageFormat`): Das Ausgabeformat, PNG oder TIFF.
+/// - `metadata` (`Option<&RenderMetadata>`): Optionale Beschreibung der Berechnung.
+///
+/// # Rückgabewert
+/// Die Kennzahlen der Berechnung.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn ein Format nicht unterstützt wird, die Quelle die
+/// Standardeingabe ist oder Lesen bzw. Schreiben fehlschlägt.
+pub fn render_file<F>(
+    f: F,
+    renderer: &TiledRenderer,
+    input: &Path,
+    output: &Path,
+    format: ImageFormat,
+    metadata: Option<&RenderMetadata>,
+) -> Result<TileStats, Box<dyn Error>>
+where
+    F: Fn(Complex<f64>) -> Option<Complex<f64>> + Sync,
+{
+    renderer.validate()?;
+    if !matches!(format, ImageFormat::Png | ImageFormat::Tiff) {
+        return Err(format!("tiled rendering writes PNG or TIFF, not {:?}", format).into());
+    }
+    if input == Path::new(STDIO_PATH) {
+        return Err("tiled rendering needs a PNG or TIFF file, not standard input".into());
+    }
+    let source = SpooledSource::open(input, renderer.max_source_pixels)?;
+
+    let out: Box<dyn Write> = if output == Path::new(STDIO_PATH) {
+        Box::new(io::stdout())
+    } else {
+        create_parent_dir(output)?;
+        Box::new(File::create(output)?)
+    };
+    let (width, height, color) = (source.width, source.height, source.color);
+    let mut sink: Box<dyn TileSink> = match format {
+        ImageFormat::Png => Box::new(PngSink::new(out, width, height, color, metadata)?),
+        _ => Box::new(TiffSink::new(
+            out,
+            width,
+            height,
+            color,
+            renderer.tile_size,
+        )?),
+    };
+    let stats = renderer.render(f, &source, sink.as_mut())?;
+    sink.finish()?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holo::HolomorphicLookup;
+    use image::{DynamicImage, ImageBuffer, Rgb, Rgb32FImage, Rgba};
+
+    #[test]
+    fn test_tiled_render_matches_lookup() {
+        let root = std::env::temp_dir().join(format!("holo_tiled_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let (width, height) = (50, 37);
+        let img = DynamicImage::ImageRgba16(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgba([x as u16 * 1000, y as u16 * 1500, 7, 65535 - x as u16])
+        }));
+        let input = root.join("in.png");
+        img.save(&input).unwrap();
+
+        let f = |z: Complex<f64>| Some(z * z + 0.3);
+        let lookup = HolomorphicLookup::new(|z| z * z + 0.3, width, height);
+        let expected = lookup.apply_dynamic(&img).unwrap();
+
+        // Ein kleines Budget erzwingt geteilte Kacheln
+        let renderer = TiledRenderer {
+            tile_size: 16,
+            max_source_pixels: 64,
+        };
+        for name in ["out.png", "out.tiff"] {
+            let output = root.join(name);
+            let format = ImageFormat::from_path(&output).unwrap();
+            let stats = render_file(f, &renderer, &input, &output, format, None).unwrap();
+            assert_eq!(stats.tiles, 12);
+            assert!(stats.splits > 0 && stats.largest_read <= 64);
+            assert_eq!(image::open(&output).unwrap(), expected, "{}", name);
+        }
+
+        // Das PNG braucht eine Zwischendatei, das gekachelte TIFF wird direkt gelesen
+        assert!(SpooledSource::open(&input, 64)
+            .unwrap()
+            .spool_path()
+            .is_some());
+        let source = SpooledSource::open(&root.join("out.tiff"), MAX_SOURCE_PIXELS).unwrap();
+        assert!(source.spool_path().is_none());
+        let rect = Rect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        assert_eq!(source.read_region(rect).unwrap(), expected.as_bytes());
+        // Ein Ausschnitt über mehrere Kacheln hinweg
+        let rect = Rect {
+            x: 10,
+            y: 14,
+            width: 30,
+            height: 20,
+        };
+        let crop = expected.crop_imm(10, 14, 30, 20);
+        assert_eq!(source.read_region(rect).unwrap(), crop.as_bytes());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Setzt das Compression-Tag eines Little-Endian-TIFF mit 16-Bit-Wert.
+    fn set_tiff_compression(path: &Path, compression: u16) {
+        let mut bytes = fs::read(path).unwrap();
+        let ifd = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        let count = u16::from_le_bytes([bytes[ifd], bytes[ifd + 1]]) as usize;
+        let entry = (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| u16::from_le_bytes([bytes[entry], bytes[entry + 1]]) == 259)
+            .unwrap();
+        bytes[entry + 8..entry + 10].copy_from_slice(&compression.to_le_bytes());
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_large_tiff_strips_are_streamed_or_rejected() {
+        let root = std::env::temp_dir().join(format!("holo_tiled_strip_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let img = DynamicImage::ImageRgb16(ImageBuffer::from_fn(40, 30, |x, y| {
+            Rgb([x as u16 * 1000, y as u16 * 2000, 300])
+        }));
+        let input = root.join("strips.tiff");
+        img.save(&input).unwrap();
+
+        // Streifen über der Grenze werden zeilenweise entpackt statt dekodiert
+        let source = SpooledSource::open(&input, 16).unwrap();
+        assert!(source.spool_path().is_some());
+        let rect = Rect {
+            x: 5,
+            y: 3,
+            width: 30,
+            height: 20,
+        };
+        let crop = img.crop_imm(5, 3, 30, 20);
+        assert_eq!(source.read_region(rect).unwrap(), crop.as_bytes());
+
+        // Direkt gelesen liefert der zwischengespeicherte Abschnitt dieselben Pixel
+        let direct = SpooledSource::open(&input, MAX_SOURCE_PIXELS).unwrap();
+        assert!(direct.spool_path().is_none());
+        for _ in 0..2 {
+            assert_eq!(direct.read_region(rect).unwrap(), crop.as_bytes());
+        }
+
+        set_tiff_compression(&input, 8);
+        let error = SpooledSource::open(&input, 16).err().unwrap().to_string();
+        assert!(error.contains("tiled TIFF"), "{}", error);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_float_tiff_and_invalid_settings() {
+        let root = std::env::temp_dir().join(format!("holo_tiled_hdr_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let hdr = DynamicImage::ImageRgb32F(Rgb32FImage::from_fn(20, 20, |x, y| {
+            Rgb([x as f32 * 0.5, y as f32, -1.0])
+        }));
+        let input = root.join("in.tiff");
+        hdr.save(&input).unwrap();
+
+        let lookup = HolomorphicLookup::new(|z| 1.0 / z, 20, 20);
+        let renderer = TiledRenderer::default();
+        let output = root.join("out.tiff");
+        render_file(
+            |z| Some(1.0 / z),
+            &renderer,
+            &input,
+            &output,
+            ImageFormat::Tiff,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            image::open(&output).unwrap(),
+            lookup.apply_dynamic(&hdr).unwrap()
+        );
+
+        let png = root.join("out.png");
+        assert!(render_file(Some, &renderer, &input, &png, ImageFormat::Png, None).is_err());
+        let unaligned = TiledRenderer {
+            tile_size: 100,
+            ..TiledRenderer::default()
+        };
+        assert!(unaligned.validate().is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
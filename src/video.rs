@@ -0,0 +1,292 @@
+use std::path::Path;
+
+use image::RgbImage;
+use opencv::core::Size;
+use opencv::prelude::*;
+use opencv::videoio::{VideoCapture, VideoWriter, CAP_PROP_FPS};
+
+use crate::holo::{process_frame, HolomorphicLookup};
+use crate::output::create_parent_dir;
+use crate::webcam::{capture_frame, rgb_image_to_mat};
+
+/// Bildrate, die verwendet wird, wenn die Quelle keine Bildrate meldet (z. B. viele Webcams).
+pub const DEFAULT_FPS: f64 = 30.0;
+
+/// Vorlage für Aufzeichnungen aus dem Vorschaufenster, wenn kein `--output` angegeben ist.
+pub const DEFAULT_RECORDING_TEMPLATE: &str = "./images/output/{stem}_{expr}_{ts}.mp4";
+
+/// Dateiendungen der Container, in die transformierte Videos geschrieben werden.
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "avi", "mkv", "webm"];
+
+/// Gibt an, ob ein Pfad die Endung eines Videocontainers hat, siehe [`VIDEO_EXTENSIONS`].
+///
+/// # Parameter
+/// - `path` (`&str`): Der Pfad bzw. die Namensvorlage.
+pub fn is_video_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Umgebungsvariable, über die OpenCV Optionen an den FFmpeg-Encoder weitergibt.
+const FFMPEG_WRITER_OPTIONS: &str = "OPENCV_FFMPEG_WRITER_OPTIONS";
+
+/// Einstellungen für das Schreiben eines transformierten Videos.
+///
+/// # Felder
+/// - `path` (`String`): Der Pfad der Ausgabedatei; die Endung bestimmt den Container.
+/// - `codec` (`String`): Der Codec als FourCC-Code, z. B. `mp4v`, `avc1` oder `MJPG`.
+/// - `fps` (`Option<f64>`): Bildrate der Ausgabe, standardmäßig die der Quelle.
+///
+/// Die Bitrate gilt für den ganzen Prozess und wird einmalig mit [`set_bitrate`] gesetzt.
+#[derive(Debug, Clone)]
+pub struct VideoOutput {
+    pub path: String,
+    pub codec: String,
+    pub fps: Option<f64>,
+}
+
+/// Baut die Encoder-Optionen für eine Ziel-Bitrate im Format von OpenCV.
+///
+/// # Parameter
+/// - `bitrate` (`u32`): Die Ziel-Bitrate in kbit/s.
+///
+/// # Rückgabewert
+/// Die Optionen als `schlüssel;wert`, z. B. `b;4000k`.
+pub fn writer_options(bitrate: u32) -> String {
+    format!("b;{}k", bitrate)
+}
+
+/// Setzt die Ziel-Bitrate aller Videoausgaben des FFmpeg-Backends.
+///
+/// OpenCV liest die Encoder-Optionen beim Öffnen eines Writers aus der Umgebung. Da
+/// Umgebungsvariablen nicht threadsicher geändert werden können, muss diese Funktion
+/// aufgerufen werden, bevor weitere Threads gestartet werden, also am Programmanfang.
+///
+/// # Parameter
+/// - `bitrate` (`Option<u32>`): Die Ziel-Bitrate in kbit/s; ohne Angabe bleibt die Umgebung
+///   unverändert.
+pub fn set_bitrate(bitrate: Option<u32>) {
+    if let Some(bitrate) = bitrate {
+        std::env::set_var(FFMPEG_WRITER_OPTIONS, writer_options(bitrate));
+    }
+}
+
+/// Wandelt einen Codec-Namen in einen FourCC-Code um.
+///
+/// # Parameter
+/// - `codec` (`&str`): Der Codec-Name aus genau vier Zeichen.
+///
+/// # Rückgabewert
+/// Der FourCC-Code oder eine Fehlermeldung, falls der Name nicht aus vier Zeichen besteht.
+pub fn fourcc(codec: &str) -> Result<i32, Box<dyn std::error::Error>> {
+    let chars: Vec<char> = codec.chars().collect();
+    match chars[..] {
+        [c1, c2, c3, c4] => Ok(VideoWriter::fourcc(c1, c2, c3, c4)?),
+        _ => Err(format!("codec must be a four character code (got '{}')", codec).into()),
+    }
+}
+
+/// Liest die Bildrate einer Videoquelle.
+///
+/// # Parameter
+/// - `cap` (`&VideoCapture`): Die geöffnete Videoquelle.
+///
+/// # Rückgabewert
+/// Die gemeldete Bildrate oder [`DEFAULT_FPS`], falls die Quelle keine gültige Rate liefert.
+pub fn source_fps(cap: &VideoCapture) -> f64 {
+    match cap.get(CAP_PROP_FPS) {
+        Ok(fps) if fps.is_finite() && fps > 0.0 => fps,
+        _ => DEFAULT_FPS,
+    }
+}
+
+impl VideoOutput {
+    /// Öffnet einen `VideoWriter` für die Ausgabe.
+    ///
+    /// # Parameter
+    /// - `source_fps` (`f64`): Die Bildrate der Quelle, falls `fps` nicht gesetzt ist.
+    /// - `width` (`u32`): Die Breite der Frames.
+    /// - `height` (`u32`): Die Höhe der Frames.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn der Codec ungültig ist oder die Datei nicht
+    /// geöffnet werden kann.
+    pub fn open(
+        &self,
+        source_fps: f64,
+        width: u32,
+        height: u32,
+    ) -> Result<VideoWriter, Box<dyn std::error::Error>> {
+        create_parent_dir(std::path::Path::new(&self.path))?;
+
+        let writer = VideoWriter::new(
+            &self.path,
+            fourcc(&self.codec)?,
+            self.fps.unwrap_or(source_fps),
+            Size::new(width as i32, height as i32),
+            true,
+        )?;
+        if !writer.is_opened()? {
+            return Err(format!(
+                "failed to open '{}' for writing with codec {}",
+                self.path, self.codec
+            )
+            .into());
+        }
+        Ok(writer)
+    }
+}
+
+/// Eine laufende Aufzeichnung aus dem Vorschaufenster.
+///
+/// # Felder
+/// - `writer` (`VideoWriter`): Das geöffnete Ziel.
+/// - `path` (`String`): Der Pfad der Ausgabedatei.
+/// - `frames` (`u64`): Anzahl der bisher geschriebenen Frames.
+pub struct Recording {
+    writer: VideoWriter,
+    pub path: String,
+    pub frames: u64,
+}
+
+impl Recording {
+    /// Beginnt eine Aufzeichnung in die Datei aus `output`.
+    ///
+    /// # Parameter
+    /// - `output` (`&VideoOutput`): Pfad und Codec der Aufzeichnung.
+    /// - `source_fps` (`f64`): Die Bildrate der Quelle, falls `output` keine vorgibt.
+    /// - `width` (`u32`): Die Breite der Frames.
+    /// - `height` (`u32`): Die Höhe der Frames.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Datei nicht geöffnet werden kann.
+    pub fn start(
+        output: &VideoOutput,
+        source_fps: f64,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Recording {
+            writer: output.open(source_fps, width, height)?,
+            path: output.path.clone(),
+            frames: 0,
+        })
+    }
+
+    /// Schreibt ein Frame in die Aufzeichnung.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn das Frame nicht geschrieben werden kann.
+    pub fn write(&mut self, img: &RgbImage) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.write(&rgb_image_to_mat(img)?)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Schließt die Datei ab.
+    ///
+    /// # Fehler
+    /// Gibt einen Fehler zurück, wenn die Datei nicht abgeschlossen werden kann.
+    pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.release()?;
+        Ok(())
+    }
+}
+
+/// Transformiert alle Frames einer Videoquelle und schreibt sie in einen `VideoWriter`.
+///
+/// Die Lookup-Tabelle wird einmal berechnet und für alle Frames wiederverwendet.
+///
+/// # Parameter
+/// - `cap` (`&mut VideoCapture`): Die geöffnete Videoquelle.
+/// - `lookup` (`&HolomorphicLookup`): Die vorab berechnete Lookup-Tabelle.
+/// - `writer` (`&mut VideoWriter`): Das Ziel der transformierten Frames.
+/// - `limit` (`Option<u64>`): Höchstzahl der geschriebenen Frames, ohne Angabe bis zum Ende
+///   der Quelle.
+///
+/// # Rückgabewert
+/// Die Anzahl der geschriebenen Frames.
+///
+/// # Fehler
+/// Gibt einen Fehler zurück, wenn ein Frame nicht geschrieben werden kann.
+pub fn transform_video(
+    cap: &mut VideoCapture,
+    lookup: &HolomorphicLookup,
+    writer: &mut VideoWriter,
+    limit: Option<u64>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut frames = 0;
+    while frames < limit.unwrap_or(u64::MAX) {
+        let Some(frame) = capture_frame(cap) else {
+            break;
+        };
+        if let Some(transformed_img) = process_frame(lookup, &frame) {
+            writer.write(&rgb_image_to_mat(&transformed_img)?)?;
+            frames += 1;
+        }
+    }
+    writer.release()?;
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+    use opencv::videoio::CAP_ANY;
+
+    #[test]
+    fn test_fourcc_and_writer_options() {
+        assert_eq!(fourcc("MJPG").unwrap(), i32::from_le_bytes(*b"MJPG"));
+        assert!(fourcc("h264x").is_err());
+        assert!(fourcc("").is_err());
+        assert_eq!(writer_options(4000), "b;4000k");
+    }
+
+    #[test]
+    fn test_record_and_transform_video() {
+        let dir = std::env::temp_dir().join(format!("holo_video_{}", std::process::id()));
+        let output = |name: &str| VideoOutput {
+            path: dir.join(name).to_string_lossy().into_owned(),
+            codec: String::from("MJPG"),
+            fps: Some(10.0),
+        };
+
+        let recorded = output("recorded.avi");
+        let mut recording = Recording::start(&recorded, DEFAULT_FPS, 32, 16).unwrap();
+        for value in [0, 80, 160] {
+            recording
+                .write(&RgbImage::from_pixel(32, 16, Rgb([value, 40, 200])))
+                .unwrap();
+        }
+        assert_eq!(recording.frames, 3);
+        recording.finish().unwrap();
+
+        let mut cap = VideoCapture::from_file(&recorded.path, CAP_ANY).unwrap();
+        assert!(cap.is_opened().unwrap());
+        assert_eq!(source_fps(&cap), 10.0);
+        let lookup = HolomorphicLookup::new(|z| z, 32, 16);
+        let transformed = output("transformed.avi");
+        let mut writer = transformed.open(source_fps(&cap), 32, 16).unwrap();
+        assert_eq!(
+            transform_video(&mut cap, &lookup, &mut writer, None).unwrap(),
+            3
+        );
+        // Eine Grenze kürzt das Video
+        let mut cap = VideoCapture::from_file(&recorded.path, CAP_ANY).unwrap();
+        let shortened = output("shortened.avi");
+        let mut writer = shortened.open(source_fps(&cap), 32, 16).unwrap();
+        assert_eq!(
+            transform_video(&mut cap, &lookup, &mut writer, Some(2)).unwrap(),
+            2
+        );
+
+        let mut cap = VideoCapture::from_file(&transformed.path, CAP_ANY).unwrap();
+        let frame = capture_frame(&mut cap).unwrap();
+        assert_eq!((frame.cols(), frame.rows()), (32, 16));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,20 @@
-use image::{Rgb, RgbImage};
-use opencv::core::Mat;
+use std::borrow::Cow;
+use std::thread;
+use std::time::Duration;
+
+use image::RgbImage;
+use opencv::core::{Mat, Scalar, Vec3b, CV_8UC3};
 use opencv::prelude::*;
-use opencv::videoio::VideoCapture;
+use opencv::videoio::{VideoCapture, CAP_FFMPEG};
+
+/// Fehlgeschlagene Lesevorgänge in Folge, nach denen ein Netzwerkstream als getrennt gilt.
+pub const RECONNECT_AFTER_MISSES: u32 = 10;
+
+/// Verbindungsversuche in Folge, nach denen ein getrennter Stream aufgegeben wird.
+pub const MAX_RECONNECTS: u32 = 5;
+
+/// Wartezeit vor dem ersten Verbindungsversuch, sie verdoppelt sich mit jedem weiteren.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
 
 /// Erfasst ein einzelnes Frame von der Webcam oder einer Videoquelle.
 ///
@@ -18,7 +31,7 @@ use opencv::videoio::VideoCapture;
 /// - Gibt `None` zurück, wenn das Frame leer ist oder ein Fehler beim Lesen auftritt.
 pub fn capture_frame(cap: &mut VideoCapture) -> Option<Mat> {
     let mut frame = Mat::default();
-    if cap.read(&mut frame).unwrap() && !frame.empty() {
+    if cap.read(&mut frame).unwrap_or(false) && !frame.empty() {
         // Optional: Frame skalieren oder vorverarbeiten
         Some(frame)
     } else {
@@ -26,10 +39,58 @@ pub fn capture_frame(cap: &mut VideoCapture) -> Option<Mat> {
     }
 }
 
+/// Liefert die Pixel eines OpenCV-Frames als BGR-Bytes ohne Lücken zwischen den Zeilen.
+///
+/// Liegt das Mat-Objekt zusammenhängend im Speicher (der Normalfall bei Kamera- und
+/// Videoframes), werden seine Daten nur ausgeliehen. Bei Ausschnitten mit Zeilenabstand
+/// werden die Zeilen einzeln zusammenkopiert.
+///
+/// # Parameter
+/// - `mat` (`&Mat`): Das Frame vom Typ `CV_8UC3`.
+///
+/// # Rückgabewert
+/// Die Bytes zeilenweise, je Pixel Blau, Grün, Rot, oder `None` bei einem anderen Typ
+/// oder einem Lesefehler.
+pub fn bgr_bytes(mat: &Mat) -> Option<Cow<'_, [u8]>> {
+    if mat.typ() != CV_8UC3 {
+        return None;
+    }
+    if mat.is_continuous() {
+        return mat.data_bytes().ok().map(Cow::Borrowed);
+    }
+    let mut bytes = Vec::with_capacity(mat.rows() as usize * mat.cols() as usize * 3);
+    for y in 0..mat.rows() {
+        let row = mat.at_row::<Vec3b>(y).ok()?;
+        bytes.extend(row.iter().flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]));
+    }
+    Some(Cow::Owned(bytes))
+}
+
+/// Ordnet BGR-Bytes in einem Durchlauf zu einem `RgbImage` um.
+///
+/// # Parameter
+/// - `bgr` (`&[u8]`): Die Pixel zeilenweise, wie von [`bgr_bytes`] geliefert.
+/// - `width` (`u32`): Die Breite des Bildes.
+/// - `height` (`u32`): Die Höhe des Bildes.
+///
+/// # Rückgabewert
+/// Das Bild oder `None`, falls die Anzahl der Bytes nicht zur Größe passt.
+pub fn bgr_to_rgb_image(bgr: &[u8], width: u32, height: u32) -> Option<RgbImage> {
+    if bgr.len() != width as usize * height as usize * 3 {
+        return None;
+    }
+    let mut rgb = bgr.to_vec();
+    for pixel in rgb.chunks_exact_mut(3) {
+        pixel.swap(0, 2); // Konvertiere BGR zu RGB
+    }
+    RgbImage::from_raw(width, height, rgb)
+}
+
 /// Konvertiert ein OpenCV-Mat-Bild in ein `RgbImage`.
 ///
 /// Diese Funktion nimmt ein OpenCV-Bild (im BGR-Format) und konvertiert es
 /// in ein `RgbImage` aus der `image`-Crate, indem die Farbkanäle von BGR zu RGB umgeordnet werden.
+/// Die Daten werden über [`bgr_bytes`] gelesen und nur einmal kopiert.
 ///
 /// # Parameter
 /// - `mat` (`&Mat`): Das OpenCV-Mat-Objekt, das konvertiert werden soll.
@@ -38,18 +99,185 @@ pub fn capture_frame(cap: &mut VideoCapture) -> Option<Mat> {
 /// Gibt ein `RgbImage` zurück, das das konvertierte Bild enthält, oder `None`, wenn ein Fehler auftritt.
 ///
 /// # Fehler
-/// - Gibt `None` zurück, wenn Pixel nicht aus dem Mat-Objekt extrahiert werden können.
+/// - Gibt `None` zurück, wenn das Mat-Objekt nicht vom Typ `CV_8UC3` ist oder nicht gelesen werden kann.
 pub fn mat_to_rgb_image(mat: &Mat) -> Option<RgbImage> {
-    let mut rgb_image = RgbImage::new(mat.cols() as u32, mat.rows() as u32);
-    for y in 0..mat.rows() {
-        for x in 0..mat.cols() {
-            let pixel = mat.at_2d::<opencv::core::Vec3b>(y, x).ok()?;
-            rgb_image.put_pixel(
-                x as u32,
-                y as u32,
-                Rgb([pixel[2], pixel[1], pixel[0]]), // Konvertiere BGR zu RGB
-            );
+    bgr_to_rgb_image(&bgr_bytes(mat)?, mat.cols() as u32, mat.rows() as u32)
+}
+
+/// Konvertiert ein `RgbImage` in ein OpenCV-Mat-Bild im BGR-Format.
+///
+/// Das Gegenstück zu [`mat_to_rgb_image`], z. B. um transformierte Frames mit einem
+/// `VideoWriter` zu schreiben.
+///
+/// # Parameter
+/// - `img` (`&RgbImage`): Das Bild, das konvertiert werden soll.
+///
+/// # Rückgabewert
+/// Gibt ein `Mat`-Objekt vom Typ `CV_8UC3` zurück.
+///
+/// # Fehler
+/// - Gibt einen Fehler zurück, wenn das Mat-Objekt nicht angelegt werden kann.
+pub fn rgb_image_to_mat(img: &RgbImage) -> opencv::Result<Mat> {
+    let mut mat = Mat::new_rows_cols_with_default(
+        img.height() as i32,
+        img.width() as i32,
+        CV_8UC3,
+        Scalar::all(0.0),
+    )?;
+    for (dst, src) in mat.data_bytes_mut()?.chunks_exact_mut(3).zip(img.pixels()) {
+        dst.copy_from_slice(&[src[2], src[1], src[0]]); // Konvertiere RGB zu BGR
+    }
+    Ok(mat)
+}
+
+/// Nächster Schritt nach einem Lesevorgang, siehe [`StreamReconnect::after_read`].
+///
+/// # Varianten
+/// - `Continue`: Weiterlesen.
+/// - `Reconnect(Duration)`: Nach der Wartezeit den Stream neu öffnen.
+/// - `GiveUp`: Den Stream aufgeben.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectStep {
+    Continue,
+    Reconnect(Duration),
+    GiveUp,
+}
+
+/// Liest einen Netzwerkstream und verbindet ihn nach Abbrüchen neu.
+///
+/// Nach [`RECONNECT_AFTER_MISSES`] fehlgeschlagenen Lesevorgängen in Folge wird der Stream
+/// mit wachsender Wartezeit neu geöffnet. Bleiben [`MAX_RECONNECTS`] Versuche ohne Frame, wird
+/// er aufgegeben, sodass der Lauf endet, statt endlos auf Frames zu warten.
+///
+/// # Felder
+/// - `url` (`String`): Die URL des Streams.
+/// - `misses` (`u32`): Fehlgeschlagene Lesevorgänge seit dem letzten Frame bzw. Versuch.
+/// - `attempts` (`u32`): Verbindungsversuche seit dem letzten Frame.
+pub struct StreamReconnect {
+    url: String,
+    misses: u32,
+    attempts: u32,
+}
+
+impl StreamReconnect {
+    /// Legt den Zustand für einen geöffneten Stream an.
+    pub fn new(url: &str) -> Self {
+        StreamReconnect {
+            url: url.to_string(),
+            misses: 0,
+            attempts: 0,
         }
     }
-    Some(rgb_image)
+
+    /// Wertet einen Lesevorgang aus.
+    ///
+    /// # Parameter
+    /// - `success` (`bool`): Ob ein Frame gelesen wurde.
+    ///
+    /// # Rückgabewert
+    /// Der nächste Schritt.
+    pub fn after_read(&mut self, success: bool) -> ReconnectStep {
+        if success {
+            (self.misses, self.attempts) = (0, 0);
+            return ReconnectStep::Continue;
+        }
+        self.misses += 1;
+        if self.misses < RECONNECT_AFTER_MISSES {
+            return ReconnectStep::Continue;
+        }
+        if self.attempts >= MAX_RECONNECTS {
+            return ReconnectStep::GiveUp;
+        }
+        // Jeder Versuch bekommt erneut einige Lesevorgänge
+        self.misses = 0;
+        self.attempts += 1;
+        ReconnectStep::Reconnect(RECONNECT_DELAY * (1 << (self.attempts - 1)))
+    }
+
+    /// Liest das nächste Frame und öffnet den Stream bei Bedarf neu.
+    ///
+    /// # Parameter
+    /// - `cap` (`&mut VideoCapture`): Der Stream, er wird beim Neuverbinden ersetzt.
+    ///
+    /// # Rückgabewert
+    /// Das Frame, `Some(None)` bei einem Aussetzer oder `None`, wenn der Stream aufgegeben
+    /// wird.
+    pub fn read(&mut self, cap: &mut VideoCapture) -> Option<Option<Mat>> {
+        let frame = capture_frame(cap);
+        match self.after_read(frame.is_some()) {
+            ReconnectStep::Continue => Some(frame),
+            ReconnectStep::Reconnect(delay) => {
+                eprintln!(
+                    "warning: stream '{}' lost, reconnecting in {:.1} s ({}/{})",
+                    self.url,
+                    delay.as_secs_f64(),
+                    self.attempts,
+                    MAX_RECONNECTS
+                );
+                thread::sleep(delay);
+                match VideoCapture::from_file(&self.url, CAP_FFMPEG) {
+                    Ok(reopened) if reopened.is_opened().unwrap_or(false) => *cap = reopened,
+                    _ => eprintln!("warning: failed to reopen stream '{}'", self.url),
+                }
+                Some(None)
+            }
+            ReconnectStep::GiveUp => {
+                eprintln!(
+                    "error: stream '{}' lost, giving up after {} reconnects",
+                    self.url, MAX_RECONNECTS
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_reconnect_backs_off_and_gives_up() {
+        let mut reconnect = StreamReconnect::new("rtsp://camera/live");
+        let mut misses = |count| {
+            (0..count)
+                .map(|_| reconnect.after_read(false))
+                .last()
+                .unwrap()
+        };
+        assert_eq!(misses(RECONNECT_AFTER_MISSES - 1), ReconnectStep::Continue);
+        assert_eq!(
+            misses(1),
+            ReconnectStep::Reconnect(Duration::from_millis(500))
+        );
+        assert_eq!(
+            misses(RECONNECT_AFTER_MISSES),
+            ReconnectStep::Reconnect(Duration::from_secs(1))
+        );
+        // Ein Frame setzt Aussetzer und Versuche zurück
+        assert_eq!(reconnect.after_read(true), ReconnectStep::Continue);
+        let mut steps = Vec::new();
+        for _ in 0..=MAX_RECONNECTS {
+            steps.extend((0..RECONNECT_AFTER_MISSES).map(|_| reconnect.after_read(false)));
+        }
+        assert_eq!(
+            steps
+                .iter()
+                .filter(|step| **step != ReconnectStep::Continue)
+                .count(),
+            MAX_RECONNECTS as usize + 1
+        );
+        assert_eq!(
+            steps[RECONNECT_AFTER_MISSES as usize - 1],
+            ReconnectStep::Reconnect(RECONNECT_DELAY)
+        );
+        assert_eq!(steps.last(), Some(&ReconnectStep::GiveUp));
+    }
+
+    #[test]
+    fn test_bgr_to_rgb_image_swaps_channels() {
+        let img = bgr_to_rgb_image(&[1, 2, 3, 4, 5, 6], 2, 1).unwrap();
+        assert_eq!(img.as_raw(), &vec![3, 2, 1, 6, 5, 4]);
+        assert!(bgr_to_rgb_image(&[1, 2, 3], 2, 1).is_none());
+    }
 }